@@ -1,9 +1,10 @@
+use crate::radio::RadioInfo;
 use crate::video::MetaVideo;
 use anyhow::Result;
 use serenity::{
     all::{
-        ChannelId, CommandInteraction, EditInteractionResponse, GuildId, Http, ModalInteraction,
-        UserId,
+        ChannelId, CommandInteraction, EditInteractionResponse, GuildId, Http, MessageId,
+        ModalInteraction, UserId,
     },
     prelude::TypeMapKey,
 };
@@ -47,22 +48,49 @@ pub enum AudioPromiseCommand {
 
     Skip,
     Remove(usize),
+    SwitchRadio(RadioStation),
+
+    // stops the message being read out
+    TtsSkip,
+    // stops the message being read out and drops everything waiting to be read
+    TtsClear,
+    TtsPause(OrToggle),
+    // drops a message's tts if it hasn't been read yet, eg because it was deleted
+    TtsCancel(MessageId),
 
     MetaCommand(MetaCommand),
     // Consent { user_id: UserId, consent: bool },
 }
+/// a named radio station to switch to for the current session
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioStation {
+    pub name: Arc<str>,
+    pub audio_url: Arc<str>,
+    pub data_url: Option<Arc<str>>,
+    pub art_url: Option<Arc<str>>,
+}
 #[derive(Debug, Clone)]
 pub enum MetaCommand {
     RetrieveLog(mpsc::Sender<Vec<String>>),
+    RetrieveRadioInfo(mpsc::Sender<RadioInfo>),
     UserConnect(UserId),
     ChangeDefaultRadioVolume(f32),
     ChangeDefaultSongVolume(f32),
     ChangeReadTitles(bool),
     ChangeTalkOverEachother(bool),
+    ChangeRadioAnnouncements(RadioAnnouncements),
+    ChangeTransitions(Transitions),
     ChangeRadioAudioUrl(Arc<str>),
     ChangeRadioDataUrl(Arc<str>),
     ResetCustomRadioData,
+    ChangeIdlePlaylist(Option<Arc<str>>),
     ChangeAloneTimeout(Duration),
+    // whether the voice channel only reads messages for people muted in it
+    ChangeMutedOnly(bool),
+    // the guild's announcer settings were changed, and need loading again
+    ReloadAnnouncer,
+    // where live captions for the voice channel are posted, None when they're off
+    ChangeCaptionChannel(Option<ChannelId>),
 }
 pub enum GenericInteraction<'a> {
     Command(&'a CommandInteraction),
@@ -95,6 +123,46 @@ impl<'a> From<&'a ModalInteraction> for GenericInteraction<'a> {
         Self::Modal(interaction)
     }
 }
+/// whether to say what the radio is playing over tts when it changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RadioAnnouncements {
+    #[default]
+    Off,
+    // "Now playing <title>", only when the title changes
+    TitlesOnly,
+    // "Now on <station>: <title> by <artist>", whenever any of those change
+    EveryChange,
+}
+impl Display for RadioAnnouncements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RadioAnnouncements::Off => write!(f, "off"),
+            RadioAnnouncements::TitlesOnly => write!(f, "titles only"),
+            RadioAnnouncements::EveryChange => write!(f, "every change"),
+        }
+    }
+}
+/// how one queued song hands over to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transitions {
+    // how long both songs play at once while one fades out and the next fades in, zero for hard cuts
+    pub crossfade: Duration,
+    // songs queued together from the same album or playlist run straight into each other with no fade or title
+    pub gapless: bool,
+}
+impl Display for Transitions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.crossfade.is_zero() {
+            write!(f, "hard cuts")?;
+        } else {
+            write!(f, "a {} second crossfade", self.crossfade.as_secs())?;
+        }
+        if self.gapless {
+            write!(f, ", gapless within albums and playlists")?;
+        }
+        Ok(())
+    }
+}
 #[derive(Debug, Clone, Copy)]
 pub enum OrToggle {
     Specific(bool),
@@ -14,8 +14,26 @@ pub struct Config {
     pub string_api_token: String,
     pub idle_url: String,
     pub api_url: String,
+    // the azuracast instance behind api_url, for song requests. if missing it's everything before /api/ in api_url
+    #[serde(default)]
+    pub azuracast_url: Option<String>,
     #[cfg(feature = "tts")]
     pub gcloud_script: String,
+    // a piper voice model (.onnx) for offline tts, espeak-ng is used when this isn't set
+    #[cfg(feature = "tts")]
+    #[serde(default)]
+    pub piper_model: Option<PathBuf>,
+    // dectalk's say binary, the old hardcoded location when this isn't set
+    #[cfg(feature = "tts")]
+    #[serde(default)]
+    pub dectalk_path: Option<PathBuf>,
+    // limits for synthesized speech kept on disk, 256 MB and 30 days when these aren't set
+    #[cfg(feature = "tts")]
+    #[serde(default)]
+    pub tts_cache_max_mb: Option<u64>,
+    #[cfg(feature = "tts")]
+    #[serde(default)]
+    pub tts_cache_max_age_days: Option<u64>,
     #[cfg(feature = "youtube-search")]
     pub youtube_api_key: String,
     #[cfg(feature = "youtube-search")]
@@ -31,6 +49,10 @@ pub struct Config {
     pub transcribe_url: String,
     #[cfg(feature = "transcribe")]
     pub transcribe_token: String,
+    // which speech to text backend transcribes voice, the transcribe_url service when not set
+    #[cfg(feature = "transcribe")]
+    #[serde(default)]
+    pub speech_to_text: SpeechToTextConfig,
     #[cfg(feature = "transcribe")]
     pub alert_phrases_path: PathBuf,
     #[cfg(feature = "transcribe")]
@@ -142,6 +164,15 @@ impl Config {
                 } else {
                     Self::safe_read("\nPlease enter your api url:")
                 },
+                azuracast_url: rec.azuracast_url,
+                #[cfg(feature = "tts")]
+                piper_model: rec.piper_model,
+                #[cfg(feature = "tts")]
+                dectalk_path: rec.dectalk_path,
+                #[cfg(feature = "tts")]
+                tts_cache_max_mb: rec.tts_cache_max_mb,
+                #[cfg(feature = "tts")]
+                tts_cache_max_age_days: rec.tts_cache_max_age_days,
                 bumper_url: if let Some(bumper_url) = rec.bumper_url {
                     bumper_url
                 } else {
@@ -180,6 +211,8 @@ impl Config {
                     Self::safe_read("\nPlease enter your transcribe token:")
                 },
                 #[cfg(feature = "transcribe")]
+                speech_to_text: rec.speech_to_text.unwrap_or_default(),
+                #[cfg(feature = "transcribe")]
                 alert_phrases_path: if let Some(alert_phrase_path) = rec.alert_phrase_path {
                     alert_phrase_path
                 } else {
@@ -268,6 +301,15 @@ impl Config {
                 spotify_client_secret: Self::safe_read("\nPlease enter your spotify client secret:"),
                 idle_url: Self::safe_read("\nPlease enter your idle audio URL (NOT A FILE PATH):"),
                 api_url: Self::safe_read("\nPlease enter your api url:"),
+                azuracast_url: None,
+                #[cfg(feature = "tts")]
+                piper_model: None,
+                #[cfg(feature = "tts")]
+                dectalk_path: None,
+                #[cfg(feature = "tts")]
+                tts_cache_max_mb: None,
+                #[cfg(feature = "tts")]
+                tts_cache_max_age_days: None,
                 bumper_url: Self::safe_read("\nPlease enter your bumper audio URL (NOT A FILE PATH) (for silence put \"https://www.youtube.com/watch?v=Vbks4abvLEw\"):"),
                 shitgpt_path: Self::safe_read("\nPlease enter your shitgpt path:"),
                 whitelist_path: Self::safe_read("\nPlease enter your whitelist path:"),
@@ -277,6 +319,8 @@ impl Config {
                 #[cfg(feature = "transcribe")]
                 transcribe_url: Self::safe_read("\nPlease enter your transcribe url:"),
                 #[cfg(feature = "transcribe")]
+                speech_to_text: SpeechToTextConfig::default(),
+                #[cfg(feature = "transcribe")]
                 alert_phrases_path: Self::safe_read("\nPlease enter your alert phrase path:"),
                 #[cfg(feature = "transcribe")]
                 sam_path: Self::safe_read("\nPlease enter your sam path:"),
@@ -416,6 +460,14 @@ struct RecoverConfig {
     data_path: Option<PathBuf>,
     #[cfg(feature = "tts")]
     gcloud_script: Option<String>,
+    #[cfg(feature = "tts")]
+    piper_model: Option<PathBuf>,
+    #[cfg(feature = "tts")]
+    dectalk_path: Option<PathBuf>,
+    #[cfg(feature = "tts")]
+    tts_cache_max_mb: Option<u64>,
+    #[cfg(feature = "tts")]
+    tts_cache_max_age_days: Option<u64>,
     #[cfg(feature = "youtube-search")]
     youtube_api_key: Option<String>,
     #[cfg(feature = "youtube-search")]
@@ -428,6 +480,7 @@ struct RecoverConfig {
     spotify_client_secret: Option<String>,
     idle_url: Option<String>,
     api_url: Option<String>,
+    azuracast_url: Option<String>,
     shitgpt_path: Option<PathBuf>,
     whitelist_path: Option<PathBuf>,
     string_api_token: Option<String>,
@@ -437,6 +490,8 @@ struct RecoverConfig {
     #[cfg(feature = "transcribe")]
     transcribe_token: Option<String>,
     #[cfg(feature = "transcribe")]
+    speech_to_text: Option<SpeechToTextConfig>,
+    #[cfg(feature = "transcribe")]
     alert_phrase_path: Option<PathBuf>,
     #[cfg(feature = "transcribe")]
     sam_path: Option<PathBuf>,
@@ -453,3 +508,24 @@ struct RecoverConfig {
     #[cfg(feature = "seq")]
     seq_log_level: Option<String>,
 }
+/// where voice gets transcribed, set with `"backend"` and the backend's own fields
+#[cfg(feature = "transcribe")]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum SpeechToTextConfig {
+    // the service at transcribe_url
+    #[default]
+    Service,
+    // anything serving /v1/audio/transcriptions under `url`, like a local whisper.cpp or
+    // faster-whisper server. the model defaults to whisper-1
+    #[serde(rename = "openai")]
+    OpenAi {
+        url: String,
+        #[serde(default)]
+        api_key: Option<String>,
+        #[serde(default)]
+        model: Option<String>,
+    },
+    // a ggml whisper model run in process, only with the whisper feature of voice_events
+    Whisper { model: PathBuf },
+}
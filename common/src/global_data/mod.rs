@@ -18,8 +18,9 @@ pub async fn save() {
 
 pub mod voice_data {
     pub use super::raw_voice_data::{
-        add_satellite, add_satellite_wait, bot_connected, channel_count_besides, initialize_planet,
-        insert_guild, lazy_refresh_guild, mutual_channel, refresh_guild, update_voice, VoiceAction,
+        add_satellite, add_satellite_wait, bot_connected, channel_action, channel_count_besides,
+        initialize_planet, insert_guild, lazy_refresh_guild, member_state, mutual_channel,
+        refresh_guild, update_voice, VoiceAction,
     };
 }
 
@@ -83,6 +83,14 @@ pub async fn mutual_channel(guild: &GuildId, member: &UserId) -> Result<VoiceAct
         None => Err(anyhow::anyhow!("Voice data uninitialized")),
     }
 }
+/// same satellite selection as mutual_channel, but for a specific channel instead of wherever the member is
+pub async fn channel_action(guild: &GuildId, channel: ChannelId) -> Result<VoiceActionWithContext> {
+    let mut data = VOICE_DATA.write().await;
+    match data.as_mut() {
+        Some(data) => Ok(data.channel_action(guild, channel).await),
+        None => Err(anyhow::anyhow!("Voice data uninitialized")),
+    }
+}
 pub async fn bot_connected(guild: &GuildId, bot: &UserId) -> Result<bool> {
     log::trace!("Checking if bot {:?} is connected to {:?}", bot, guild);
     let data = VOICE_DATA.read().await;
@@ -108,6 +116,30 @@ pub async fn channel_count_besides(
         None => Err(anyhow::anyhow!("Voice data uninitialized")),
     }
 }
+/// the last voice state we saw for a member, if they're in a voice channel in the guild
+pub async fn member_state(guild: &GuildId, member: &UserId) -> Result<Option<VoiceState>> {
+    let data = VOICE_DATA.read().await;
+    match data.as_ref() {
+        Some(data) => Ok(data
+            .guilds
+            .get(guild)
+            .and_then(|guild| {
+                guild
+                    .channels
+                    .values()
+                    .find_map(|members| members.get(member))
+                    .and_then(|m| m.last_known_state.clone())
+            })
+            // refreshed channels only know who is in them, the cache still has their state
+            .or_else(|| {
+                data.planet_context
+                    .cache
+                    .guild(*guild)
+                    .and_then(|g| g.voice_states.get(member).cloned())
+            })),
+        None => Err(anyhow::anyhow!("Voice data uninitialized")),
+    }
+}
 struct VoiceData {
     guilds: HashMap<GuildId, GuildVc>,
     planet_context: Context,
@@ -153,15 +185,16 @@ impl VoiceData {
     }
     async fn mutual_channel(&mut self, guild: &GuildId, member: &UserId) -> VoiceActionWithContext {
         let guildstate = self.guilds.entry(*guild).or_default();
-        let memberstate = match guildstate.find_user(*member) {
-            Some(channel) => channel,
-            None => {
-                return VoiceActionWithContext {
-                    planet_ctx: self.planet_context.clone(),
-                    action: VoiceAction::UserNotConnected,
-                }
-            }
-        };
+        match guildstate.find_user(*member) {
+            Some(channel) => self.channel_action(guild, channel).await,
+            None => VoiceActionWithContext {
+                planet_ctx: self.planet_context.clone(),
+                action: VoiceAction::UserNotConnected,
+            },
+        }
+    }
+    async fn channel_action(&mut self, guild: &GuildId, memberstate: ChannelId) -> VoiceActionWithContext {
+        let guildstate = self.guilds.entry(*guild).or_default();
         if let Some(bot) = guildstate.first_in(memberstate, self.bot_ids.as_slice()) {
             return VoiceActionWithContext {
                 planet_ctx: self.planet_context.clone(),
@@ -508,7 +541,6 @@ impl Default for GuildVc {
 #[derive(Debug, Clone)]
 pub struct UserMetadata {
     pub member: Member,
-    #[allow(dead_code)]
     pub last_known_state: Option<VoiceState>,
 }
 impl UserMetadata {
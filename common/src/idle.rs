@@ -0,0 +1,311 @@
+use anyhow::{anyhow, bail, Result};
+use rand::seq::SliceRandom;
+use songbird::input::{Compose as _, File, Input, YoutubeDl};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+// playlists are always somewhere inside this folder in the data path, guilds can't point the bot at arbitrary files
+const IDLE_FOLDER: &str = "idle";
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "ogg", "opus", "flac", "wav", "m4a", "aac", "webm"];
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdleEntry {
+    File(PathBuf),
+    Url(Arc<str>),
+}
+impl IdleEntry {
+    pub fn input(&self) -> Input {
+        match self {
+            Self::File(path) => File::new(path.clone()).into(),
+            Self::Url(url) => YoutubeDl::new(crate::WEB_CLIENT.clone(), url.to_string()).into(),
+        }
+    }
+    pub async fn tags(&self) -> IdleTags {
+        match self {
+            Self::File(path) => {
+                let path = path.clone();
+                match tokio::task::spawn_blocking(move || file_tags(&path)).await {
+                    Ok(tags) => tags,
+                    Err(e) => {
+                        log::warn!("Failed to read idle track tags: {}", e);
+                        IdleTags::default()
+                    }
+                }
+            }
+            Self::Url(url) => {
+                let mut ytdl = YoutubeDl::new(crate::WEB_CLIENT.clone(), url.to_string());
+                match ytdl.aux_metadata().await {
+                    Ok(meta) => IdleTags {
+                        title: meta.title.unwrap_or_else(|| url.to_string()),
+                        artist: meta.artist,
+                        album: meta.album,
+                        art: meta.thumbnail,
+                        duration: meta.duration.map(|d| d.as_secs_f64()),
+                    },
+                    Err(e) => {
+                        log::warn!("Failed to get idle track metadata: {}", e);
+                        IdleTags {
+                            title: url.to_string(),
+                            ..Default::default()
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+fn file_tags(path: &Path) -> IdleTags {
+    let fallback = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let duration = ffprobe::ffprobe(path).ok().and_then(|s| {
+        s.streams
+            .first()
+            .and_then(|s| s.duration.as_ref())
+            .and_then(|d| d.parse::<f64>().ok())
+    });
+    match audiotags::Tag::new().read_from_path(path) {
+        Ok(tag) => IdleTags {
+            title: tag.title().map(|t| t.to_owned()).unwrap_or(fallback),
+            artist: tag.artist().map(|a| a.to_owned()),
+            album: tag.album_title().map(|a| a.to_owned()),
+            art: None,
+            duration,
+        },
+        Err(_) => IdleTags {
+            title: fallback,
+            duration,
+            ..Default::default()
+        },
+    }
+}
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdleTags {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub art: Option<String>,
+    pub duration: Option<f64>,
+}
+impl IdleTags {
+    /// eg `title by artist on album`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.title,
+            match self.artist {
+                Some(ref artist) => format!(" by {}", artist),
+                None => "".to_owned(),
+            },
+            match self.album {
+                Some(ref album) => format!(" on {}", album),
+                None => "".to_owned(),
+            }
+        )
+    }
+}
+#[derive(Debug, Clone)]
+pub struct IdlePlaylist {
+    source: Arc<str>,
+    entries: Vec<IdleEntry>,
+    // indexes into entries that haven't been played yet this round
+    order: Vec<usize>,
+    last: Option<usize>,
+}
+impl IdlePlaylist {
+    pub fn root() -> PathBuf {
+        let mut path = crate::get_config().data_path;
+        path.push(IDLE_FOLDER);
+        path
+    }
+    /// `source` is either a url to a playlist, or the name of a folder or playlist file inside the idle folder
+    pub async fn load(source: &str) -> Result<Self> {
+        let entries = if source.starts_with("http://") || source.starts_with("https://") {
+            let body = crate::WEB_CLIENT
+                .get(source)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            // a remote playlist can only point at other urls
+            parse_playlist(&body)
+                .filter(|line| is_url(line))
+                .map(|line| IdleEntry::Url(line.into()))
+                .collect()
+        } else {
+            let path = inside(&Self::root(), source)?;
+            let meta = tokio::fs::metadata(&path).await?;
+            if meta.is_dir() {
+                audio_files(path).await?
+            } else {
+                let body = tokio::fs::read_to_string(&path).await?;
+                let parent = path
+                    .parent()
+                    .ok_or_else(|| anyhow!("Playlist has no parent folder"))?;
+                let mut entries = Vec::new();
+                for line in parse_playlist(&body) {
+                    if is_url(line) {
+                        entries.push(IdleEntry::Url(line.into()));
+                        continue;
+                    }
+                    match inside(parent, line) {
+                        Ok(p) if p.exists() => entries.push(IdleEntry::File(p)),
+                        Ok(p) => log::warn!("Idle playlist entry {:?} does not exist", p),
+                        Err(e) => log::warn!("Skipping idle playlist entry {}: {}", line, e),
+                    }
+                }
+                entries
+            }
+        };
+        if entries.is_empty() {
+            bail!("No playable files or urls found in `{}`", source);
+        }
+        Ok(Self {
+            source: source.into(),
+            entries,
+            order: Vec::new(),
+            last: None,
+        })
+    }
+    /// folders and playlist files in the idle folder, for autocomplete
+    pub async fn available() -> Vec<String> {
+        let mut names = Vec::new();
+        if let Ok(mut dir) = tokio::fs::read_dir(Self::root()).await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        names.sort();
+        names
+    }
+    pub fn source(&self) -> Arc<str> {
+        Arc::clone(&self.source)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// shuffles through every entry before repeating any, never plays the same entry twice in a row
+    pub fn next_entry(&mut self) -> Option<IdleEntry> {
+        if self.order.is_empty() {
+            self.order = (0..self.entries.len()).collect();
+            self.order.shuffle(&mut rand::thread_rng());
+            // order is popped from the back
+            if self.order.len() > 1 && self.order.last() == self.last.as_ref() {
+                self.order.swap(0, self.entries.len() - 1);
+            }
+        }
+        let index = self.order.pop()?;
+        self.last = Some(index);
+        self.entries.get(index).cloned()
+    }
+}
+fn parse_playlist(body: &str) -> impl Iterator<Item = &str> {
+    body.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+}
+fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+// only plain relative paths, so nothing can escape the folder it is resolved against
+fn inside(base: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative.as_os_str().is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        bail!("`{}` has to be a path inside the idle folder", relative.display());
+    }
+    Ok(base.join(relative))
+}
+async fn audio_files(dir: PathBuf) -> Result<Vec<IdleEntry>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir];
+    while let Some(dir) = dirs.pop() {
+        let mut read = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = read.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                .unwrap_or(false)
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(IdleEntry::File).collect())
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn playlist(len: usize) -> IdlePlaylist {
+        IdlePlaylist {
+            source: "test".into(),
+            entries: (0..len)
+                .map(|i| IdleEntry::File(PathBuf::from(format!("{}.mp3", i))))
+                .collect(),
+            order: Vec::new(),
+            last: None,
+        }
+    }
+
+    #[test]
+    fn inside_idle_folder() {
+        let base = Path::new("/data/idle");
+        assert_eq!(inside(base, "rock").unwrap(), base.join("rock"));
+        assert_eq!(inside(base, "rock/song.mp3").unwrap(), base.join("rock/song.mp3"));
+    }
+
+    #[test]
+    fn escaping_idle_folder() {
+        let base = Path::new("/data/idle");
+        for path in [
+            "",
+            "..",
+            "../secrets",
+            "rock/../../secrets",
+            "rock/..",
+            "/etc/passwd",
+            "./rock",
+        ] {
+            assert!(inside(base, path).is_err(), "{} was accepted", path);
+        }
+    }
+
+    #[test]
+    fn every_entry_once_per_shuffle() {
+        let mut playlist = playlist(5);
+        let mut last = None;
+        for _ in 0..20 {
+            let mut round = (0..5)
+                .map(|_| playlist.next_entry().unwrap())
+                .collect::<Vec<_>>();
+            assert_ne!(round.first(), last.as_ref());
+            last = round.last().cloned();
+            round.sort_by_key(|e| format!("{:?}", e));
+            round.dedup();
+            assert_eq!(round.len(), 5);
+        }
+    }
+
+    #[test]
+    fn single_entry_repeats() {
+        let mut single = playlist(1);
+        for _ in 0..3 {
+            assert_eq!(single.next_entry(), Some(IdleEntry::File("0.mp3".into())));
+        }
+        assert_eq!(playlist(0).next_entry(), None);
+    }
+}
@@ -2,10 +2,12 @@
 pub mod audio;
 mod config;
 pub mod global_data;
+pub mod idle;
 pub mod radio;
-pub mod sam;
 mod statics;
 mod traits;
+#[cfg(feature = "tts")]
+pub mod tts;
 pub mod video;
 pub mod youtube;
 use std::sync::Arc;
@@ -15,6 +17,8 @@ pub use anyhow;
 pub use chrono;
 pub use chrono_tz;
 pub use config::{get_config, Config};
+#[cfg(feature = "transcribe")]
+pub use config::SpeechToTextConfig;
 pub use lazy_static;
 pub use log;
 pub use nanoid;
@@ -2,7 +2,7 @@ use anyhow::Result;
 use reqwest::IntoUrl;
 use serde::{Deserialize, Serialize};
 use serenity::futures::{channel::mpsc, SinkExt as _, StreamExt as _};
-use std::{ops::Deref, sync::Arc};
+use std::{ops::Deref, sync::Arc, time::Duration};
 use tokio::sync::broadcast;
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,17 +14,19 @@ pub struct AzuraCastData {
     pub now_playing: NowPlaying,
     #[serde(rename = "playing_next")]
     pub playing_next: PlayingNext,
-    // #[serde(rename = "song_history")]
-    // pub song_history: Vec<SongHistory>,
+    #[serde(rename = "song_history", default)]
+    pub song_history: Vec<SongHistory>,
     // #[serde(rename = "is_online")]
     // pub is_online: bool,
 }
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Station {
-    // pub id: i64,
+    #[serde(default)]
+    pub id: i64,
     pub name: String,
-    // pub shortcode: String,
+    #[serde(default)]
+    pub shortcode: String,
     // pub description: String,
     // pub frontend: String,
     // pub backend: String,
@@ -124,20 +126,20 @@ pub struct PlayingNext {
     // pub is_request: bool,
     pub song: Song,
 }
-// #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
-// #[serde(rename_all = "camelCase")]
-// pub struct SongHistory {
-//     #[serde(rename = "sh_id")]
-//     pub sh_id: i64,
-//     #[serde(rename = "played_at")]
-//     pub played_at: i64,
-//     pub duration: i64,
-//     pub playlist: String,
-//     pub streamer: String,
-//     #[serde(rename = "is_request")]
-//     pub is_request: bool,
-//     pub song: Song,
-// }
+#[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SongHistory {
+    // #[serde(rename = "sh_id")]
+    // pub sh_id: i64,
+    #[serde(rename = "played_at")]
+    pub played_at: i64,
+    // pub duration: i64,
+    // pub playlist: String,
+    // pub streamer: String,
+    // #[serde(rename = "is_request")]
+    // pub is_request: bool,
+    pub song: Song,
+}
 pub struct AzuraCastThread {
     kill: mpsc::Sender<()>,
     recv: broadcast::Receiver<Arc<OriginalOrCustom>>,
@@ -220,6 +222,9 @@ impl AzuraCastThread {
 pub enum RadioData {
     AzuraCast(AzuraCastData),
     IceCast(IceCastRoot),
+    // never deserialized, this comes from IcyThread instead of a data url
+    #[serde(skip)]
+    Icy(IcyData),
 }
 #[derive(Debug, PartialEq)]
 pub enum OriginalOrCustom {
@@ -244,6 +249,7 @@ impl OriginalOrCustom {
 pub enum RadioDataKind {
     AzuraCast,
     IceCast,
+    Icy,
 }
 impl RadioData {
     pub async fn get(url: impl IntoUrl) -> Result<OriginalOrCustom> {
@@ -259,6 +265,7 @@ impl RadioData {
         match self {
             Self::AzuraCast(_) => RadioDataKind::AzuraCast,
             Self::IceCast(_) => RadioDataKind::IceCast,
+            Self::Icy(_) => RadioDataKind::Icy,
         }
     }
     async fn get_original(url: impl IntoUrl) -> Result<OriginalOrCustom> {
@@ -281,6 +288,7 @@ impl RadioData {
                     .find(|s| s.listenurl.ends_with(search_for))
                     .map_or("Unknown station name", |s| &s.server_name)
             }
+            Self::Icy(data) => data.station_name.as_deref().unwrap_or("Unknown station name"),
         }
     }
     pub fn now_playing_title(&self, url: &str) -> &str {
@@ -295,6 +303,7 @@ impl RadioData {
                     .and_then(|s| s.title.as_deref())
                     .unwrap_or("Unknown title")
             }
+            Self::Icy(data) => &data.title,
         }
     }
     pub fn now_playing_artist(&self, url: &str) -> Option<&str> {
@@ -308,37 +317,55 @@ impl RadioData {
                     .find(|s| s.listenurl.ends_with(search_for))
                     .and_then(|s| s.artist.as_deref())
             }
+            Self::Icy(data) => data.artist.as_deref(),
         }
     }
     pub fn now_playing_album(&self, _url: &str) -> Option<&str> {
         match self {
             Self::AzuraCast(data) => Some(&data.now_playing.song.album),
-            Self::IceCast(_data) => None,
+            Self::IceCast(_) | Self::Icy(_) => None,
         }
     }
     pub fn now_playing_art(&self, _url: &str) -> Option<&str> {
         match self {
             Self::AzuraCast(data) => Some(&data.now_playing.song.art),
-            Self::IceCast(_data) => None,
+            Self::IceCast(_) | Self::Icy(_) => None,
+        }
+    }
+    // None if the source doesn't keep a history, the caller has to track it instead
+    pub fn history(&self) -> Option<Vec<RadioHistoryEntry>> {
+        match self {
+            Self::AzuraCast(data) => Some(
+                data.song_history
+                    .iter()
+                    .map(|h| RadioHistoryEntry {
+                        played_at: h.played_at,
+                        title: h.song.title.clone(),
+                        artist: (!h.song.artist.is_empty()).then(|| h.song.artist.clone()),
+                        art: (!h.song.art.is_empty()).then(|| h.song.art.clone()),
+                    })
+                    .collect(),
+            ),
+            Self::IceCast(_) | Self::Icy(_) => None,
         }
     }
-    // icecast doesn't have a playing next dataset
+    // icecast and icy don't have a playing next dataset
     pub fn playing_next_title(&self, _url: &str) -> Option<&str> {
         match self {
             Self::AzuraCast(data) => Some(&data.playing_next.song.title),
-            Self::IceCast(_data) => None,
+            Self::IceCast(_) | Self::Icy(_) => None,
         }
     }
     pub fn playing_next_artist(&self, _url: &str) -> Option<&str> {
         match self {
             Self::AzuraCast(data) => Some(&data.playing_next.song.artist),
-            Self::IceCast(_data) => None,
+            Self::IceCast(_) | Self::Icy(_) => None,
         }
     }
     pub fn playing_next_album(&self, _url: &str) -> Option<&str> {
         match self {
             Self::AzuraCast(data) => Some(&data.playing_next.song.album),
-            Self::IceCast(_data) => None,
+            Self::IceCast(_) | Self::Icy(_) => None,
         }
     }
 }
@@ -381,3 +408,344 @@ pub struct IceCastSource {
     // pub subtype: Option<String>,
     // pub dummy: Option<serde_json::Value>,
 }
+// a song that was played on the radio, newest first wherever these are listed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioHistoryEntry {
+    // unix timestamp in seconds
+    pub played_at: i64,
+    pub title: String,
+    pub artist: Option<String>,
+    pub art: Option<String>,
+}
+// what the_lüüp knows about the radio it's playing, for /radio
+#[derive(Debug, Clone, Default)]
+pub struct RadioInfo {
+    // None means the default station from the config
+    pub data_url: Option<Arc<str>>,
+    pub history: Vec<RadioHistoryEntry>,
+}
+// the parts of the azuracast api used for song requests
+pub struct AzuraCastApi {
+    base_url: String,
+    station: String,
+}
+impl AzuraCastApi {
+    pub async fn new(data_url: Option<&str>) -> Result<Self> {
+        let config = crate::get_config();
+        let url = data_url.unwrap_or(&config.api_url);
+        let station = match *RadioData::get(url).await? {
+            RadioData::AzuraCast(ref data) => {
+                if data.station.shortcode.is_empty() {
+                    data.station.id.to_string()
+                } else {
+                    data.station.shortcode.clone()
+                }
+            }
+            _ => return Err(anyhow::anyhow!("This station does not take requests")),
+        };
+        let base_url = match (data_url, config.azuracast_url) {
+            (None, Some(base_url)) => base_url,
+            _ => url
+                .split("/api/")
+                .next()
+                .unwrap_or(url)
+                .to_owned(),
+        };
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            station,
+        })
+    }
+    pub async fn requestable(&self) -> Result<Vec<RequestableSong>> {
+        Ok(crate::WEB_CLIENT
+            .get(format!("{}/api/station/{}/requests", self.base_url, self.station))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<RequestableSong>>()
+            .await?)
+    }
+    // returns the station's response message
+    pub async fn request(&self, request_id: &str) -> Result<String> {
+        let response = crate::WEB_CLIENT
+            .post(format!(
+                "{}/api/station/{}/request/{}",
+                self.base_url,
+                self.station,
+                urlencoding::encode(request_id)
+            ))
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.json::<RequestResponse>().await.unwrap_or_default();
+        let message = body.formatted_message.unwrap_or(body.message);
+        if status.is_success() && body.success {
+            Ok(message)
+        } else if message.is_empty() {
+            Err(anyhow::anyhow!("Request failed with status {}", status))
+        } else {
+            Err(anyhow::anyhow!(message))
+        }
+    }
+}
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestableSong {
+    pub request_id: String,
+    pub song: Song,
+}
+#[derive(Default, Deserialize)]
+struct RequestResponse {
+    #[serde(default)]
+    success: bool,
+    #[serde(default)]
+    message: String,
+    formatted_message: Option<String>,
+}
+/// now playing data sent in-band by shoutcast/icecast streams, only has what fits in `StreamTitle`
+#[derive(Default, Debug, PartialEq, Clone)]
+pub struct IcyData {
+    pub station_name: Option<String>,
+    pub title: String,
+    pub artist: Option<String>,
+}
+impl IcyData {
+    // most stations send "Artist - Title", if there's no separator the whole thing is the title
+    fn new(station_name: Option<String>, stream_title: &str) -> Self {
+        let (artist, title) = match stream_title.split_once(" - ") {
+            Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+                (Some(artist.trim().to_owned()), title.trim().to_owned())
+            }
+            _ => (None, stream_title.trim().to_owned()),
+        };
+        Self {
+            station_name,
+            title,
+            artist,
+        }
+    }
+}
+// taps a radio audio url with `Icy-MetaData: 1` and sends every new title, this is a second connection to the stream so only use it when there is no data url
+pub struct IcyThread {
+    url: Arc<str>,
+    recv: tokio::sync::mpsc::UnboundedReceiver<Arc<OriginalOrCustom>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+impl IcyThread {
+    pub fn new(url: Arc<str>) -> Self {
+        let (send_data, recv) = tokio::sync::mpsc::unbounded_channel();
+        let handle = tokio::spawn({
+            let url = Arc::clone(&url);
+            async move {
+                let mut last_data = None;
+                loop {
+                    match read_icy(&url, &send_data, &mut last_data).await {
+                        Ok(true) => log::trace!("Icy stream ended, reconnecting"),
+                        Ok(false) => {
+                            log::trace!("{} does not send icy metadata", url);
+                            break;
+                        }
+                        Err(e) => log::warn!("Failed to read icy metadata: {:?}", e),
+                    }
+                    if send_data.is_closed() {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                }
+            }
+        });
+        Self { url, recv, handle }
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub async fn recv(&mut self) -> Option<Arc<OriginalOrCustom>> {
+        self.recv.recv().await
+    }
+}
+impl Drop for IcyThread {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+// returns false if the stream doesn't support icy metadata or nobody is listening anymore, true if the stream ended and should be retried
+async fn read_icy(
+    url: &str,
+    send_data: &tokio::sync::mpsc::UnboundedSender<Arc<OriginalOrCustom>>,
+    last_data: &mut Option<IcyData>,
+) -> Result<bool> {
+    let mut response = crate::WEB_CLIENT
+        .get(url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .await?
+        .error_for_status()?;
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+    };
+    let metaint = match header("icy-metaint").and_then(|v| v.parse::<usize>().ok()) {
+        Some(m) if m > 0 => m,
+        _ => return Ok(false),
+    };
+    let station_name = header("icy-name");
+    let mut parser = IcyParser::new(metaint);
+    while let Some(chunk) = response.chunk().await? {
+        for metadata in parser.push(&chunk) {
+            let title = match stream_title(&metadata) {
+                Some(t) => t,
+                None => continue,
+            };
+            let data = IcyData::new(station_name.clone(), title);
+            if last_data.as_ref() == Some(&data) {
+                continue;
+            }
+            *last_data = Some(data.clone());
+            if send_data
+                .send(Arc::new(OriginalOrCustom::Custom(RadioData::Icy(data))))
+                .is_err()
+            {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+// the stream is `metaint` bytes of audio, one length byte (in 16 byte blocks), then that much metadata, repeated
+struct IcyParser {
+    metaint: usize,
+    audio_left: usize,
+    metadata_len: Option<usize>,
+    metadata: Vec<u8>,
+}
+impl IcyParser {
+    fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            audio_left: metaint,
+            metadata_len: None,
+            metadata: Vec::new(),
+        }
+    }
+    fn push(&mut self, mut chunk: &[u8]) -> Vec<String> {
+        let mut found = Vec::new();
+        while !chunk.is_empty() {
+            match self.metadata_len {
+                None if self.audio_left > 0 => {
+                    let (_audio, rest) = chunk.split_at(self.audio_left.min(chunk.len()));
+                    self.audio_left -= chunk.len() - rest.len();
+                    chunk = rest;
+                }
+                None => {
+                    if let Some((len, rest)) = chunk.split_first() {
+                        self.metadata_len = Some(*len as usize * 16);
+                        chunk = rest;
+                    }
+                }
+                Some(len) => {
+                    let take = (len - self.metadata.len()).min(chunk.len());
+                    let (metadata, rest) = chunk.split_at(take);
+                    self.metadata.extend_from_slice(metadata);
+                    chunk = rest;
+                }
+            }
+            if self.metadata_len == Some(self.metadata.len()) {
+                if !self.metadata.is_empty() {
+                    found.push(
+                        String::from_utf8_lossy(&self.metadata)
+                            .trim_end_matches('\0')
+                            .to_owned(),
+                    );
+                }
+                self.metadata.clear();
+                self.metadata_len = None;
+                self.audio_left = self.metaint;
+            }
+        }
+        found
+    }
+}
+// StreamTitle='Artist - Title';StreamUrl='';
+fn stream_title(metadata: &str) -> Option<&str> {
+    const KEY: &str = "StreamTitle='";
+    let rest = metadata.get(metadata.find(KEY)? + KEY.len()..)?;
+    // titles can contain `';` themselves, only one followed by another field or nothing ends it
+    let end = rest.match_indices("';").map(|(i, _)| i).find(|&i| {
+        let after = rest[i + 2..].trim();
+        after.is_empty()
+            || after.split_once("='").is_some_and(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+    });
+    let title = match end {
+        Some(end) => rest.get(..end)?,
+        None => rest.trim_end_matches('\''),
+    };
+    (!title.trim().is_empty()).then_some(title)
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // `metaint` bytes of audio followed by a metadata block padded out to 16 bytes
+    fn block(metaint: usize, metadata: &str) -> Vec<u8> {
+        let mut bytes = vec![0xAA; metaint];
+        let blocks = metadata.len().div_ceil(16);
+        bytes.push(blocks as u8);
+        bytes.extend_from_slice(metadata.as_bytes());
+        bytes.resize(metaint + 1 + blocks * 16, 0);
+        bytes
+    }
+
+    #[test]
+    fn whole_blocks() {
+        let mut parser = IcyParser::new(32);
+        let mut stream = block(32, "StreamTitle='One';");
+        stream.extend(block(32, "StreamTitle='Two';"));
+        assert_eq!(parser.push(&stream), ["StreamTitle='One';", "StreamTitle='Two';"]);
+    }
+
+    #[test]
+    fn split_across_reads() {
+        let mut parser = IcyParser::new(32);
+        let stream = [block(32, "StreamTitle='One';"), block(32, "StreamTitle='Two';")].concat();
+        // splits inside the audio, on the length byte and inside the metadata
+        let mut found = Vec::new();
+        for chunk in [&stream[..20], &stream[20..32], &stream[32..33], &stream[33..40]] {
+            found.extend(parser.push(chunk));
+        }
+        assert!(found.is_empty());
+        found.extend(parser.push(&stream[40..70]));
+        assert_eq!(found, ["StreamTitle='One';"]);
+        found.extend(parser.push(&stream[70..]));
+        assert_eq!(found, ["StreamTitle='One';", "StreamTitle='Two';"]);
+    }
+
+    #[test]
+    fn empty_block() {
+        let mut parser = IcyParser::new(16);
+        let mut stream = block(16, "");
+        stream.extend(block(16, "StreamTitle='One';"));
+        assert_eq!(stream[16], 0);
+        assert_eq!(parser.push(&stream), ["StreamTitle='One';"]);
+    }
+
+    #[test]
+    fn titles() {
+        let title = "StreamTitle='Artist - Title';StreamUrl='';";
+        assert_eq!(stream_title(title), Some("Artist - Title"));
+        assert_eq!(stream_title("StreamTitle='';"), None);
+        assert_eq!(stream_title("StreamUrl='x';"), None);
+        assert_eq!(stream_title("StreamTitle='Unterminated"), Some("Unterminated"));
+    }
+
+    #[test]
+    fn title_containing_quote_semicolon() {
+        let title = "StreamTitle='Rock 'n';roll - Band';StreamUrl='https://a.com';";
+        assert_eq!(stream_title(title), Some("Rock 'n';roll - Band"));
+        assert_eq!(stream_title("StreamTitle='Tom';s Diner';"), Some("Tom';s Diner"));
+    }
+}
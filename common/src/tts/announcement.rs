@@ -0,0 +1,85 @@
+/// read out before a song starts when a guild hasn't written its own template
+pub const DEFAULT_TEMPLATE: &str = "Now playing... {title}";
+/// what a title announcement can fill in, see [`Announcement::render`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Announcement<'a> {
+    pub title: &'a str,
+    pub artist: Option<&'a str>,
+    // the display name of whoever queued the song
+    pub requester: Option<&'a str>,
+    // in seconds, None for live streams
+    pub duration: Option<f64>,
+}
+impl<'a> Announcement<'a> {
+    pub fn new(title: &'a str) -> Self {
+        Self {
+            title,
+            ..Default::default()
+        }
+    }
+    /// fills in `{title}`, `{artist}`, `{requester}` and `{duration}`. anything in square brackets
+    /// is left out when one of its placeholders has nothing to fill in, eg `[by {artist}]`
+    pub fn render(&self, template: &str) -> String {
+        let mut spoken = String::with_capacity(template.len() + self.title.len());
+        let mut rest = template;
+        loop {
+            let (before, optional, after) = match rest.split_once('[') {
+                Some((before, tail)) => match tail.split_once(']') {
+                    Some((optional, after)) => (before, Some(optional), after),
+                    // never closed, so the bracket is just part of the text
+                    None => (rest, None, ""),
+                },
+                None => (rest, None, ""),
+            };
+            spoken.push_str(&self.fill(before).0);
+            if let Some(optional) = optional {
+                let (filled, complete) = self.fill(optional);
+                if complete {
+                    spoken.push_str(&filled);
+                }
+            }
+            if after.is_empty() {
+                break;
+            }
+            rest = after;
+        }
+        // placeholders with nothing to fill in leave gaps behind
+        spoken.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+    // the text with its placeholders filled in, and whether every one of them had a value
+    fn fill(&self, text: &str) -> (String, bool) {
+        let mut filled = text.to_owned();
+        let mut complete = true;
+        // the title goes last so one with braces in it is read as it is
+        for (placeholder, value) in [
+            ("{artist}", self.artist.map(str::to_owned)),
+            ("{requester}", self.requester.map(str::to_owned)),
+            ("{duration}", self.duration.map(spoken_duration)),
+            ("{title}", Some(self.title.to_owned())),
+        ] {
+            if filled.contains(placeholder) {
+                complete &= value.is_some();
+                filled = filled.replace(placeholder, value.as_deref().unwrap_or_default());
+            }
+        }
+        (filled, complete)
+    }
+}
+// eg "3 minutes 25 seconds", which reads out better than 3:25
+fn spoken_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let spoken = [
+        (total / 3600, "hour"),
+        (total / 60 % 60, "minute"),
+        (total % 60, "second"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, unit)| format!("{} {}{}", n, unit, if *n == 1 { "" } else { "s" }))
+    .collect::<Vec<_>>();
+    if spoken.is_empty() {
+        "0 seconds".to_owned()
+    } else {
+        spoken.join(" ")
+    }
+}
@@ -0,0 +1,262 @@
+use super::TtsBackendKind;
+use crate::video::Video;
+use crate::youtube::TTSVoice;
+use anyhow::Result;
+use sha2::{Digest as _, Sha256};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+const DEFAULT_MAX_MB: u64 = 256;
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+// queued tts points straight at its cache entry without owning it, so anything used this recently
+// is left alone in case it hasn't been played yet
+const IN_USE_GRACE: Duration = Duration::from_secs(15 * 60);
+// old entries still get cleared out this often even if the cache never fills up
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// whatever the backends write out
+const EXTENSIONS: [&str; 3] = ["ogg", "wav", "mp3"];
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+// roughly how big the cache is, what was left at the last prune plus everything stored since
+static CACHED_BYTES: AtomicU64 = AtomicU64::new(0);
+// seconds since the epoch of the last prune, 0 until the first one
+static LAST_PRUNE: AtomicU64 = AtomicU64::new(0);
+static PRUNING: AtomicBool = AtomicBool::new(false);
+lazy_static::lazy_static!(
+    static ref CACHE_PATH: PathBuf = crate::get_config().data_path.join("tts_cache");
+);
+/// tts cache hits and misses since startup, take one away from another to get them for a stretch of time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+impl CacheStats {
+    pub fn now() -> Self {
+        Self {
+            hits: HITS.load(Ordering::Relaxed),
+            misses: MISSES.load(Ordering::Relaxed),
+        }
+    }
+    pub fn since(self, earlier: Self) -> Self {
+        Self {
+            hits: self.hits.saturating_sub(earlier.hits),
+            misses: self.misses.saturating_sub(earlier.misses),
+        }
+    }
+    pub fn hit_rate(&self) -> Option<f64> {
+        match self.hits + self.misses {
+            0 => None,
+            total => Some(self.hits as f64 / total as f64),
+        }
+    }
+}
+impl std::fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.hit_rate() {
+            Some(rate) => write!(
+                f,
+                "{} hits, {} misses ({:.0}% hit rate)",
+                self.hits,
+                self.misses,
+                rate * 100.0
+            ),
+            None => write!(f, "nothing spoken"),
+        }
+    }
+}
+/// returns the audio for `input` from the cache, synthesizing and storing it if it isn't there,
+/// along with whether it had to be synthesized.
+/// cached videos aren't deleted when dropped, old entries get pruned to the configured limits
+pub(crate) async fn cached<F>(
+    backend: TtsBackendKind,
+    voice: Option<TTSVoice>,
+    input: &str,
+    ssml: bool,
+    synthesize: F,
+) -> Result<(Video, bool)>
+where
+    F: Future<Output = Result<Video>>,
+{
+    let key = key(backend, voice, input, ssml);
+    if let Some(path) = find(&key) {
+        match Video::from_cache(path.clone(), backend.name().to_owned()) {
+            Ok(video) => {
+                HITS.fetch_add(1, Ordering::Relaxed);
+                touch(&path);
+                return Ok((video, false));
+            }
+            Err(e) => {
+                log::warn!("Unreadable tts cache entry {}: {:?}", path.display(), e);
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    log::warn!("Failed to remove tts cache entry: {}", e);
+                }
+            }
+        }
+    }
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let video = synthesize.await?;
+    let stored = store(&key, &video).await.and_then(|(path, len)| {
+        let total = CACHED_BYTES.fetch_add(len, Ordering::Relaxed) + len;
+        maybe_prune(total);
+        Video::from_cache(path, backend.name().to_owned())
+    });
+    match stored {
+        // the uncached copy gets deleted as it drops
+        Ok(cached) => Ok((cached, true)),
+        Err(e) => {
+            log::warn!("Failed to cache tts: {:?}", e);
+            Ok((video, true))
+        }
+    }
+}
+pub(crate) fn contains(
+    backend: TtsBackendKind,
+    voice: Option<TTSVoice>,
+    input: &str,
+    ssml: bool,
+) -> bool {
+    find(&key(backend, voice, input, ssml)).is_some()
+}
+// the same thing said with different spacing sounds the same, case is kept since backends read
+// acronyms differently
+fn key(backend: TtsBackendKind, voice: Option<TTSVoice>, input: &str, ssml: bool) -> String {
+    let normalized = input.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mut hasher = Sha256::new();
+    for part in [
+        backend.name(),
+        voice.map(|v| v.name).unwrap_or("default"),
+        if ssml { "ssml" } else { "text" },
+        normalized.as_str(),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+fn find(key: &str) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|extension| CACHE_PATH.join(format!("{}.{}", key, extension)))
+        .find(|path| path.exists())
+}
+// where the entry ended up and how big it is
+async fn store(key: &str, video: &Video) -> Result<(PathBuf, u64)> {
+    let source = video.path();
+    let extension = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| EXTENSIONS.contains(e))
+        .ok_or_else(|| anyhow::anyhow!("Can't cache {}", source.display()))?;
+    tokio::fs::create_dir_all(CACHE_PATH.as_path()).await?;
+    let path = CACHE_PATH.join(format!("{}.{}", key, extension));
+    // copied under another name first so a half written entry is never found, unique so two
+    // misses for the same thing at once don't write over each other
+    let partial = CACHE_PATH.join(format!("{}.{}.partial", key, nanoid::nanoid!()));
+    let len = match tokio::fs::copy(&source, &partial).await {
+        Ok(len) => len,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e.into());
+        }
+    };
+    tokio::fs::rename(&partial, &path).await?;
+    Ok((path, len))
+}
+// entries are evicted least recently used first, so hits bump the modified time
+fn touch(path: &Path) {
+    let res = std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()));
+    if let Err(e) = res {
+        log::warn!("Failed to touch tts cache entry: {}", e);
+    }
+}
+// the size and age entries are kept to
+fn limits() -> (u64, Duration) {
+    let config = crate::get_config();
+    let max_bytes = config.tts_cache_max_mb.unwrap_or(DEFAULT_MAX_MB) * 1024 * 1024;
+    let max_age = Duration::from_secs(
+        config
+            .tts_cache_max_age_days
+            .unwrap_or(DEFAULT_MAX_AGE_DAYS)
+            * 24
+            * 60
+            * 60,
+    );
+    (max_bytes, max_age)
+}
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+// scanning the whole directory after every miss adds up, so it's only done once the cache might
+// be over its size limit or it's been a while, and never twice at once
+fn maybe_prune(total: u64) {
+    let (max_bytes, _) = limits();
+    let last = LAST_PRUNE.load(Ordering::Relaxed);
+    let due = last == 0 || unix_now().saturating_sub(last) >= PRUNE_INTERVAL.as_secs();
+    if total <= max_bytes && !due {
+        return;
+    }
+    if PRUNING
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+        .is_err()
+    {
+        return;
+    }
+    tokio::task::spawn_blocking(|| {
+        if let Err(e) = prune() {
+            log::warn!("Failed to prune the tts cache: {:?}", e);
+        }
+        LAST_PRUNE.store(unix_now(), Ordering::Relaxed);
+        PRUNING.store(false, Ordering::Release);
+    });
+}
+fn prune() -> Result<()> {
+    let (max_bytes, max_age) = limits();
+    let now = SystemTime::now();
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(CACHE_PATH.as_path())? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified()?;
+        let age = now.duration_since(modified).unwrap_or_default();
+        if age < IN_USE_GRACE {
+            // still counts towards the size, it just can't be evicted yet
+            entries.push((modified, metadata.len(), None));
+            continue;
+        }
+        if age > max_age {
+            remove(&entry.path());
+            continue;
+        }
+        entries.push((modified, metadata.len(), Some(entry.path())));
+    }
+    let mut total = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if let Some(path) = path {
+            remove(&path);
+            total = total.saturating_sub(len);
+        }
+    }
+    CACHED_BYTES.store(total, Ordering::Relaxed);
+    Ok(())
+}
+fn remove(path: &Path) {
+    log::trace!("Evicting tts cache entry {}", path.display());
+    if let Err(e) = std::fs::remove_file(path) {
+        log::warn!("Failed to evict tts cache entry: {}", e);
+    }
+}
@@ -0,0 +1,240 @@
+// dectalk's say binary, dectalk_path in the config or {module path}/files/say
+// say -fo stdout:raw -a "[:np][:rate 180]hello" | ffmpeg -f s16le -ar 11025 -ac 1 -i - -f wav /tmp/{nanoid as long as it doesn't exist}.wav
+use super::{TtsBackend, TtsBackendKind};
+use crate::video::{MediaType, Video};
+use crate::youtube::TTSVoice;
+use anyhow::{anyhow, Result};
+use serenity::async_trait;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::process::Command;
+const DEFAULT_BINARY: &str = "/git/alrightguysnewprojecttime/dectalk/files/say";
+/// dectalk's built in speakers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DectalkVoice {
+    Paul,
+    Betty,
+    Harry,
+    Frank,
+    Dennis,
+    Kit,
+    Ursula,
+    Rita,
+    Wendy,
+}
+impl DectalkVoice {
+    pub const ALL: [DectalkVoice; 9] = [
+        DectalkVoice::Paul,
+        DectalkVoice::Betty,
+        DectalkVoice::Harry,
+        DectalkVoice::Frank,
+        DectalkVoice::Dennis,
+        DectalkVoice::Kit,
+        DectalkVoice::Ursula,
+        DectalkVoice::Rita,
+        DectalkVoice::Wendy,
+    ];
+    // kit is a child, so nobody gets them without asking
+    const MALE: [DectalkVoice; 4] = [
+        DectalkVoice::Paul,
+        DectalkVoice::Harry,
+        DectalkVoice::Frank,
+        DectalkVoice::Dennis,
+    ];
+    const FEMALE: [DectalkVoice; 4] = [
+        DectalkVoice::Betty,
+        DectalkVoice::Ursula,
+        DectalkVoice::Rita,
+        DectalkVoice::Wendy,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            DectalkVoice::Paul => "paul",
+            DectalkVoice::Betty => "betty",
+            DectalkVoice::Harry => "harry",
+            DectalkVoice::Frank => "frank",
+            DectalkVoice::Dennis => "dennis",
+            DectalkVoice::Kit => "kit",
+            DectalkVoice::Ursula => "ursula",
+            DectalkVoice::Rita => "rita",
+            DectalkVoice::Wendy => "wendy",
+        }
+    }
+    /// a speaker of the same gender as a google voice, the same one every time so people who were
+    /// given different google voices still sound different
+    pub fn for_voice(voice: TTSVoice) -> Self {
+        let speakers = match voice.gender {
+            "MALE" => &Self::MALE,
+            _ => &Self::FEMALE,
+        };
+        let pick = voice.name.bytes().map(usize::from).sum::<usize>() % speakers.len();
+        speakers.get(pick).copied().unwrap_or(DectalkVoice::Paul)
+    }
+}
+impl Display for DectalkVoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DectalkVoice::Paul => write!(f, "Perfect Paul"),
+            DectalkVoice::Betty => write!(f, "Beautiful Betty"),
+            DectalkVoice::Harry => write!(f, "Huge Harry"),
+            DectalkVoice::Frank => write!(f, "Frail Frank"),
+            DectalkVoice::Dennis => write!(f, "Doctor Dennis"),
+            DectalkVoice::Kit => write!(f, "Kit the Kid"),
+            DectalkVoice::Ursula => write!(f, "Uppity Ursula"),
+            DectalkVoice::Rita => write!(f, "Rough Rita"),
+            DectalkVoice::Wendy => write!(f, "Whispering Wendy"),
+        }
+    }
+}
+impl FromStr for DectalkVoice {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        DectalkVoice::ALL
+            .into_iter()
+            .find(|v| v.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow!("Unknown dectalk voice `{}`", s))
+    }
+}
+/// how text gets handed to dectalk, a guild's settings or something specific like voice feedback
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DectalkOptions {
+    // picked to match the google voice when unset
+    pub voice: Option<DectalkVoice>,
+    // words per minute, dectalk's own default when unset
+    pub rate: Option<u16>,
+    // let `[:...]` commands in the text through instead of stripping them
+    pub allow_commands: bool,
+}
+impl DectalkOptions {
+    /// slowest and fastest dectalk will speak, in words per minute. it defaults to 180
+    pub const MIN_RATE: u16 = 75;
+    pub const MAX_RATE: u16 = 600;
+    /// the commands that go in front of everything read out, to set the voice and rate
+    pub fn preamble(&self, voice: Option<TTSVoice>) -> String {
+        let speaker = self
+            .voice
+            .or_else(|| voice.map(DectalkVoice::for_voice))
+            .unwrap_or(DectalkVoice::Paul);
+        // every dectalk voice is selected by the first letter of its name, eg [:np] for paul
+        let initial = speaker.name().chars().next().unwrap_or('p');
+        let mut preamble = format!("[:n{}]", initial);
+        if let Some(rate) = self.rate {
+            preamble.push_str(&format!("[:rate {}]", rate.clamp(Self::MIN_RATE, Self::MAX_RATE)));
+        }
+        preamble
+    }
+    /// the text with any inline commands stripped out, unless they're allowed
+    pub fn sanitize(&self, text: &str) -> String {
+        if self.allow_commands {
+            return text.to_owned();
+        }
+        let mut clean = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                // the whole command, to the end of the text if it's never closed
+                '[' if chars.peek() == Some(&':') => {
+                    chars.find(|&c| c == ']');
+                    clean.push(' ');
+                }
+                // text is sanitized in pieces, so stray brackets could be put back together into a
+                // command, and on their own they switch dectalk to reading phonemes
+                '[' | ']' => clean.push(' '),
+                c => clean.push(c),
+            }
+        }
+        clean
+    }
+}
+pub struct Dectalk;
+impl Dectalk {
+    fn binary() -> PathBuf {
+        crate::config::get_config()
+            .dectalk_path
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_BINARY))
+    }
+}
+#[async_trait]
+impl TtsBackend for Dectalk {
+    fn kind(&self) -> TtsBackendKind {
+        TtsBackendKind::Dectalk
+    }
+    fn available(&self) -> bool {
+        Self::binary().exists()
+    }
+    async fn speak(&self, text: &str, _voice: Option<TTSVoice>) -> Result<Video> {
+        let id = format!("{}-dectalk", nanoid::nanoid!(10));
+        let path = crate::TEMP_PATH.join(format!("{}.wav", id));
+
+        let raw_bytes = {
+            let mut dectalk = Command::new(Self::binary())
+                .args(["-fo", "stdout:raw"])
+                .args(["-a", text])
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+
+            let stdout = dectalk
+                .stdout
+                .as_mut()
+                .ok_or_else(|| anyhow!("Failed to get dectalk stdout"))?;
+
+            let mut dectalk_result = Vec::new();
+            stdout.read_to_end(&mut dectalk_result).await?;
+
+            let status = dectalk.wait().await?;
+            if !status.success() {
+                let stderr = dectalk
+                    .stderr
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("Failed to get dectalk stderr"))?;
+
+                let mut out = String::new();
+                stderr.read_to_string(&mut out).await?;
+                return Err(anyhow!("Failed to run dectalk: {}", out));
+            }
+
+            dectalk_result
+        };
+
+        // the output is raw 16-bit signed little-endian PCM, 11025 Hz, mono audio
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-f", "s16le"])
+            .args(["-ar", "11025"])
+            .args(["-ac", "1"])
+            .args(["-i", "-"])
+            .args(["-f", "wav"])
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        {
+            let stdin = ffmpeg
+                .stdin
+                .as_mut()
+                .ok_or_else(|| anyhow!("Failed to get ffmpeg stdin"))?;
+
+            stdin.write_all(&raw_bytes).await?;
+        }
+
+        let status = ffmpeg.wait().await?;
+        if !status.success() {
+            let stderr = ffmpeg
+                .stderr
+                .as_mut()
+                .ok_or_else(|| anyhow!("Failed to get ffmpeg stderr"))?;
+
+            let mut out = String::new();
+            stderr.read_to_string(&mut out).await?;
+            return Err(anyhow!("Failed to run ffmpeg: {}", out));
+        }
+
+        Video::from_path(path, "n/a".to_owned(), MediaType::Audio, id)
+    }
+}
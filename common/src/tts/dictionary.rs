@@ -0,0 +1,83 @@
+use super::TtsBackendKind;
+use anyhow::Result;
+use regex::{NoExpand, Regex};
+/// a single rewrite applied to text before it's spoken
+#[derive(Debug, Clone)]
+pub struct Pronunciation {
+    pattern: Regex,
+    replacement: String,
+    // plain words are replaced literally, regex rules can use $1 style captures
+    expand: bool,
+    backend: Option<TtsBackendKind>,
+}
+impl Pronunciation {
+    /// matches the word on its own, ignoring case
+    pub fn word(word: &str, replacement: &str, backend: Option<TtsBackendKind>) -> Result<Self> {
+        let word = word.trim();
+        if word.is_empty() {
+            return Err(anyhow::anyhow!("Can't replace an empty word"));
+        }
+        // \b only means something next to a word character, so `c++` still matches
+        let boundary = |c: Option<char>| match c {
+            Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+            _ => "",
+        };
+        let pattern = format!(
+            "(?i){}{}{}",
+            boundary(word.chars().next()),
+            regex::escape(word),
+            boundary(word.chars().last())
+        );
+        Ok(Self {
+            pattern: Regex::new(&pattern)?,
+            replacement: replacement.to_owned(),
+            expand: false,
+            backend,
+        })
+    }
+    pub fn regex(
+        pattern: &str,
+        replacement: &str,
+        backend: Option<TtsBackendKind>,
+    ) -> Result<Self> {
+        let pattern = Regex::new(pattern)?;
+        // it would slip the replacement in between every single character
+        if pattern.is_match("") {
+            return Err(anyhow::anyhow!("The pattern can't match empty text"));
+        }
+        Ok(Self {
+            pattern,
+            replacement: replacement.to_owned(),
+            expand: true,
+            backend,
+        })
+    }
+    fn applies_to(&self, backend: TtsBackendKind) -> bool {
+        self.backend.map(|b| b == backend).unwrap_or(true)
+    }
+    fn apply(&self, text: &str) -> String {
+        let replaced = if self.expand {
+            self.pattern.replace_all(text, self.replacement.as_str())
+        } else {
+            self.pattern.replace_all(text, NoExpand(self.replacement.as_str()))
+        };
+        replaced.into_owned()
+    }
+}
+/// a guild's pronunciation rules, applied in order
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary(Vec<Pronunciation>);
+impl Dictionary {
+    pub fn new(rules: Vec<Pronunciation>) -> Self {
+        Self(rules)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn apply(&self, text: &str, backend: TtsBackendKind) -> String {
+        self.0
+            .iter()
+            .filter(|r| r.applies_to(backend))
+            .fold(text.to_owned(), |text, rule| rule.apply(&text))
+    }
+}
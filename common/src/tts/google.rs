@@ -0,0 +1,32 @@
+use super::{TtsBackend, TtsBackendKind};
+use crate::video::Video;
+use crate::youtube::TTSVoice;
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use serenity::async_trait;
+pub struct Google;
+#[async_trait]
+impl TtsBackend for Google {
+    fn kind(&self) -> TtsBackendKind {
+        TtsBackendKind::Google
+    }
+    fn available(&self) -> bool {
+        !crate::config::get_config().gcloud_script.trim().is_empty()
+    }
+    async fn speak(&self, text: &str, voice: Option<TTSVoice>) -> Result<Video> {
+        crate::youtube::get_tts(text, voice).await
+    }
+    fn resolve_voice(&self, voice: Option<TTSVoice>) -> Option<TTSVoice> {
+        voice.or_else(|| crate::youtube::VOICES.choose(&mut rand::thread_rng()).copied())
+    }
+    fn supports_ssml(&self, voice: Option<TTSVoice>) -> bool {
+        // journey voices only take plain text
+        match voice {
+            Some(v) => v.engine() != "Journey",
+            None => !cfg!(feature = "google-journey-tts"),
+        }
+    }
+    async fn speak_ssml(&self, ssml: &str, voice: Option<TTSVoice>) -> Result<Video> {
+        crate::youtube::get_ssml_tts(ssml, voice).await
+    }
+}
@@ -0,0 +1,51 @@
+use crate::youtube::{TTSVoice, ALL_VOICES};
+use whatlang::Lang;
+// below this whatlang is mostly guessing, short messages and mixed languages land here
+const MIN_CONFIDENCE: f64 = 0.6;
+// too little to go on, "ok" and "lol" aren't worth switching voices over
+const MIN_LETTERS: usize = 12;
+/// the language `text` is written in as the first part of a google language code (eg `es`),
+/// None when it's too short or detection isn't confident enough to act on
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    if text.chars().filter(|c| c.is_alphabetic()).count() < MIN_LETTERS {
+        return None;
+    }
+    let info = whatlang::detect(text)?;
+    log::trace!(
+        "Detected {:?} with confidence {:.2}",
+        info.lang(),
+        info.confidence()
+    );
+    if !info.is_reliable() || info.confidence() < MIN_CONFIDENCE {
+        return None;
+    }
+    Some(match info.lang() {
+        Lang::Eng => "en",
+        Lang::Spa => "es",
+        Lang::Deu => "de",
+        Lang::Fra => "fr",
+        Lang::Ita => "it",
+        Lang::Por => "pt",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        Lang::Tha => "th",
+        Lang::Vie => "vi",
+        Lang::Tgl => "fil",
+        // nothing to read it with
+        _ => return None,
+    })
+}
+/// the voice to read something in `language` with, `assigned` if it already speaks it or there's
+/// no voice that does. otherwise one of the same gender if there is one, the same one every time
+pub fn voice_for_language(language: &str, assigned: TTSVoice) -> TTSVoice {
+    if assigned.language() == language {
+        return assigned;
+    }
+    let mut speakers = ALL_VOICES.iter().filter(|v| v.language() == language);
+    speakers
+        .clone()
+        .find(|v| v.gender == assigned.gender)
+        .or_else(|| speakers.next())
+        .copied()
+        .unwrap_or(assigned)
+}
@@ -0,0 +1,73 @@
+// offline tts, piper when a voice model is configured, otherwise espeak-ng
+// echo "hello" | piper --model voice.onnx --output_file /tmp/{nanoid}.wav
+// echo "hello" | espeak-ng --stdin -v en-us+f3 -w /tmp/{nanoid}.wav
+use super::{TtsBackend, TtsBackendKind};
+use crate::video::{MediaType, Video};
+use crate::youtube::TTSVoice;
+use anyhow::{anyhow, Result};
+use serenity::async_trait;
+use std::process::Stdio;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::process::Command;
+pub struct Local;
+#[async_trait]
+impl TtsBackend for Local {
+    fn kind(&self) -> TtsBackendKind {
+        TtsBackendKind::Local
+    }
+    fn available(&self) -> bool {
+        // espeak-ng is the fallback, if it's not installed spawning it fails and the next backend gets a go
+        match crate::config::get_config().piper_model {
+            Some(model) => model.exists(),
+            None => true,
+        }
+    }
+    async fn speak(&self, text: &str, voice: Option<TTSVoice>) -> Result<Video> {
+        let id = format!("{}-local", nanoid::nanoid!(10));
+        let path = crate::TEMP_PATH.join(format!("{}.wav", id));
+        let mut cmd = match crate::config::get_config().piper_model {
+            Some(model) => {
+                let mut cmd = Command::new("piper");
+                cmd.arg("--model").arg(model).arg("--output_file").arg(&path);
+                cmd
+            }
+            None => {
+                let mut cmd = Command::new("espeak-ng");
+                cmd.arg("--stdin")
+                    .args([
+                        "-v",
+                        match voice.map(|v| v.gender) {
+                            Some("MALE") => "en-us+m3",
+                            _ => "en-us+f3",
+                        },
+                    ])
+                    .arg("-w")
+                    .arg(&path);
+                cmd
+            }
+        };
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        {
+            let stdin = child
+                .stdin
+                .as_mut()
+                .ok_or_else(|| anyhow!("Failed to get tts stdin"))?;
+            stdin.write_all(text.as_bytes()).await?;
+        }
+        let status = child.wait().await?;
+        if !status.success() {
+            let stderr = child
+                .stderr
+                .as_mut()
+                .ok_or_else(|| anyhow!("Failed to get tts stderr"))?;
+            let mut out = String::new();
+            stderr.read_to_string(&mut out).await?;
+            return Err(anyhow!("Failed to run offline tts: {}", out));
+        }
+        Video::from_path(path, "n/a".to_owned(), MediaType::Audio, id)
+    }
+}
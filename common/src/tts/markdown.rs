@@ -0,0 +1,367 @@
+use super::speech::{Segment, Speech};
+use chrono::{TimeZone as _, Utc};
+use chrono_tz::Tz;
+use serenity::all::{Cache, ChannelId, Message, RoleId, UserId};
+/// what to do with the parts of a message that aren't plain text
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub read_spoilers: bool,
+    // timestamps are read out in this timezone
+    pub timezone: Tz,
+}
+/// turns a message's discord markdown into something that sounds right read out
+pub fn render(msg: &Message, cache: &Cache, options: &RenderOptions) -> Speech {
+    let renderer = Renderer {
+        msg,
+        cache,
+        options,
+    };
+    let mut speech = renderer.blocks(&msg.content);
+    let mut extras = msg
+        .attachments
+        .iter()
+        .map(|a| {
+            let kind = a.content_type.as_deref().unwrap_or_default();
+            if kind.starts_with("image/") {
+                "an image".to_owned()
+            } else if kind.starts_with("video/") {
+                "a video".to_owned()
+            } else if kind.starts_with("audio/") {
+                "an audio clip".to_owned()
+            } else {
+                format!("a file called {}", a.filename)
+            }
+        })
+        .collect::<Vec<String>>();
+    extras.extend(msg.sticker_items.iter().map(|s| format!("a {} sticker", s.name)));
+    if !extras.is_empty() {
+        speech.push(Segment::Pause);
+        speech.push_text(&format!("Attached {}", list(&extras)));
+    }
+    speech
+}
+struct Renderer<'a> {
+    msg: &'a Message,
+    cache: &'a Cache,
+    options: &'a RenderOptions,
+}
+impl Renderer<'_> {
+    // code blocks first since nothing inside them is markdown, then everything else line by line
+    fn blocks(&self, content: &str) -> Speech {
+        let mut speech = Speech::new();
+        let mut rest = content;
+        loop {
+            let (before, after) = match rest.split_once("```") {
+                Some(split) => split,
+                None => {
+                    speech.extend(self.lines(rest));
+                    return speech;
+                }
+            };
+            speech.extend(self.lines(before));
+            match after.split_once("```") {
+                Some((_code, after)) => {
+                    speech.push(Segment::Pause);
+                    speech.push_text("code block omitted");
+                    speech.push(Segment::Pause);
+                    rest = after;
+                }
+                // an unclosed block is just backticks
+                None => {
+                    speech.extend(self.lines(after));
+                    return speech;
+                }
+            }
+        }
+    }
+    fn lines(&self, text: &str) -> Speech {
+        let mut speech = Speech::new();
+        for line in text.lines() {
+            let mut line = line.trim_start();
+            for prefix in [">>> ", "> ", "-# ", "- ", "* "] {
+                if let Some(stripped) = line.strip_prefix(prefix) {
+                    line = stripped.trim_start();
+                }
+            }
+            let header = ["### ", "## ", "# "]
+                .iter()
+                .find_map(|h| line.strip_prefix(h));
+            match header {
+                Some(header) => {
+                    speech.extend(self.inline(header, true));
+                    speech.push(Segment::Pause);
+                }
+                None => {
+                    speech.extend(self.inline(line, false));
+                    speech.push_text("\n");
+                }
+            }
+        }
+        speech
+    }
+    fn inline(&self, text: &str, emphasis: bool) -> Speech {
+        let mut speech = Speech::new();
+        let mut plain = String::new();
+        let flush = |speech: &mut Speech, plain: &mut String| {
+            if !plain.is_empty() {
+                let text = std::mem::take(plain);
+                speech.push(if emphasis {
+                    Segment::Emphasis(text)
+                } else {
+                    Segment::Text(text)
+                });
+            }
+        };
+        let mut rest = text;
+        let mut previous = ' ';
+        while let Some(c) = rest.chars().next() {
+            // paired delimiters, and whether what's inside gets emphasis
+            let paired = [
+                ("||", emphasis),
+                ("**", true),
+                ("__", true),
+                ("~~", emphasis),
+                ("*", true),
+                ("_", true),
+                ("`", emphasis),
+            ]
+            .into_iter()
+            .filter(|(d, _)| rest.starts_with(d))
+            // snake_case isn't italics
+            .filter(|(d, _)| *d != "_" || !previous.is_alphanumeric())
+            .find_map(|(d, emphasised)| {
+                let inner = rest.get(d.len()..)?;
+                let end = inner.find(d)?;
+                let content = inner.get(..end)?;
+                if content.trim().is_empty() {
+                    return None;
+                }
+                Some((d, emphasised, content, inner.get(end + d.len()..)?))
+            });
+            if let Some((delimiter, emphasised, content, after)) = paired {
+                flush(&mut speech, &mut plain);
+                match delimiter {
+                    "||" if !self.options.read_spoilers => speech.push_text("spoiler"),
+                    // inline code is read as is
+                    "`" => speech.push(if emphasised {
+                        Segment::Emphasis(content.to_owned())
+                    } else {
+                        Segment::Text(content.to_owned())
+                    }),
+                    _ => speech.extend(self.inline(content, emphasised)),
+                }
+                previous = delimiter.chars().last().unwrap_or(' ');
+                rest = after;
+                continue;
+            }
+            // [label](url) masked links only read the label
+            if c == '[' {
+                if let Some((label, after)) = rest
+                    .get(1..)
+                    .and_then(|r| r.split_once("]("))
+                    .and_then(|(label, r)| {
+                        r.split_once(')').map(|(_, after)| (label, after))
+                    })
+                {
+                    flush(&mut speech, &mut plain);
+                    speech.extend(self.inline(label, emphasis));
+                    previous = ')';
+                    rest = after;
+                    continue;
+                }
+            }
+            if c == '<' {
+                if let Some((inner, after)) = rest.get(1..).and_then(|r| r.split_once('>')) {
+                    if !inner.contains(char::is_whitespace) {
+                        if let Some(spoken) = self.tag(inner) {
+                            plain.push_str(&spoken);
+                            previous = '>';
+                            rest = after;
+                            continue;
+                        }
+                    }
+                }
+            }
+            if rest.starts_with("http://") || rest.starts_with("https://") {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest = rest.get(end..).unwrap_or_default();
+                continue;
+            }
+            plain.push(c);
+            previous = c;
+            rest = rest.get(c.len_utf8()..).unwrap_or_default();
+        }
+        flush(&mut speech, &mut plain);
+        speech
+    }
+    // the inside of <...>, mentions, emoji, timestamps, commands and hidden links
+    fn tag(&self, inner: &str) -> Option<String> {
+        let id = |s: &str| s.parse::<u64>().ok().filter(|id| *id != 0);
+        if inner.starts_with("http://") || inner.starts_with("https://") {
+            return Some(String::new());
+        }
+        if let Some(role) = inner.strip_prefix("@&") {
+            let role = RoleId::new(id(role)?);
+            return Some(
+                self.msg
+                    .guild_id
+                    .and_then(|g| self.cache.guild(g))
+                    .and_then(|g| g.roles.get(&role).map(|r| r.name.clone()))
+                    .unwrap_or_else(|| "a role".to_owned()),
+            );
+        }
+        if let Some(user) = inner.strip_prefix('@') {
+            let user = UserId::new(id(user.trim_start_matches('!'))?);
+            return Some(self.user_name(user));
+        }
+        if let Some(channel) = inner.strip_prefix('#') {
+            let channel = ChannelId::new(id(channel)?);
+            return Some(
+                self.msg
+                    .guild_id
+                    .and_then(|g| self.cache.guild(g))
+                    .and_then(|g| g.channels.get(&channel).map(|c| c.name.clone()))
+                    .map(|name| format!("the {} channel", name))
+                    .unwrap_or_else(|| "a channel".to_owned()),
+            );
+        }
+        if let Some(timestamp) = inner.strip_prefix("t:") {
+            let (seconds, style) = timestamp.split_once(':').unwrap_or((timestamp, "f"));
+            return Some(self.timestamp(seconds.parse().ok()?, style));
+        }
+        if let Some(command) = inner.strip_prefix('/') {
+            let (name, _) = command.split_once(':')?;
+            return Some(format!("slash {}", name));
+        }
+        // <:name:id> and <a:name:id>
+        let emoji = inner.strip_prefix('a').unwrap_or(inner).strip_prefix(':')?;
+        let (name, emoji_id) = emoji.split_once(':')?;
+        id(emoji_id)?;
+        Some(name.replace('_', " "))
+    }
+    fn user_name(&self, user: UserId) -> String {
+        if let Some(member) = self
+            .msg
+            .guild_id
+            .and_then(|g| self.cache.member(g, user))
+        {
+            return member.display_name().to_owned();
+        }
+        if let Some(mentioned) = self.msg.mentions.iter().find(|u| u.id == user) {
+            return mentioned.display_name().to_owned();
+        }
+        match self.cache.user(user) {
+            Some(cached) => cached.display_name().to_owned(),
+            None => "someone".to_owned(),
+        }
+    }
+    fn timestamp(&self, seconds: i64, style: &str) -> String {
+        let time = match Utc.timestamp_opt(seconds, 0).single() {
+            Some(t) => t.with_timezone(&self.options.timezone),
+            None => return "some time".to_owned(),
+        };
+        match style {
+            "t" | "T" => time.format("%-I:%M %p").to_string(),
+            "d" | "D" => time.format("%B %-d, %Y").to_string(),
+            "F" => time.format("%A, %B %-d, %Y at %-I:%M %p").to_string(),
+            "R" => relative(seconds - Utc::now().timestamp()),
+            _ => time.format("%B %-d, %Y at %-I:%M %p").to_string(),
+        }
+    }
+}
+// how discord shows <t:...:R>, "in 3 hours" or "2 days ago"
+fn relative(seconds: i64) -> String {
+    let units = [
+        ("year", 31_536_000),
+        ("month", 2_592_000),
+        ("day", 86_400),
+        ("hour", 3_600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let distance = seconds.abs();
+    let (unit, count) = units
+        .iter()
+        .find(|(_, size)| distance >= *size)
+        .map(|(unit, size)| (*unit, distance / size))
+        .unwrap_or(("second", 0));
+    let amount = format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    if seconds >= 0 {
+        format!("in {}", amount)
+    } else {
+        format!("{} ago", amount)
+    }
+}
+// a, b and c
+fn list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn speak(content: &str, read_spoilers: bool) -> Speech {
+        let mut msg = Message::default();
+        msg.content = content.to_owned();
+        let options = RenderOptions {
+            read_spoilers,
+            timezone: Tz::UTC,
+        };
+        render(&msg, &Cache::new(), &options)
+    }
+
+    #[test]
+    fn inline_delimiters() {
+        let mut expected = Speech::new();
+        expected.push_text("hello ");
+        expected.push(Segment::Emphasis("world".to_owned()));
+        expected.push_text(" and ");
+        expected.push(Segment::Emphasis("you".to_owned()));
+        expected.push_text("\n");
+        assert_eq!(speak("hello **world** and _you_", false), expected);
+        // underscores inside a word aren't italics
+        assert_eq!(speak("snake_case_name", false).to_text(), "snake_case_name");
+        assert_eq!(speak("run `cargo build` now", false).to_text(), "run cargo build now");
+    }
+
+    #[test]
+    fn spoilers() {
+        assert_eq!(speak("a ||secret|| b", false).to_text(), "a spoiler b");
+        assert_eq!(speak("a ||secret|| b", true).to_text(), "a secret b");
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(speak("<t:0:d>", false).to_text(), "January 1, 1970");
+        assert_eq!(speak("<t:0:t>", false).to_text(), "12:00 AM");
+        assert_eq!(speak("<t:0>", false).to_text(), "January 1, 1970 at 12:00 AM");
+        assert_eq!(relative(3 * 3_600), "in 3 hours");
+        assert_eq!(relative(-2 * 86_400), "2 days ago");
+        assert_eq!(relative(-60), "1 minute ago");
+        assert_eq!(relative(0), "in 0 seconds");
+    }
+
+    #[test]
+    fn masked_links() {
+        assert_eq!(
+            speak("see [the docs](https://example.com) now", false).to_text(),
+            "see the docs now"
+        );
+    }
+
+    #[test]
+    fn code_blocks() {
+        let spoken = speak("before ```let x = 1;``` after", false).to_text();
+        assert!(spoken.contains("code block omitted"));
+        assert!(!spoken.contains("let x"));
+        assert!(spoken.starts_with("before"));
+        assert!(spoken.ends_with("after"));
+        // an unclosed block is read as it is
+        assert!(speak("only ```half", false).to_text().contains("half"));
+    }
+}
@@ -0,0 +1,238 @@
+mod announcement;
+mod cache;
+mod dectalk;
+mod dictionary;
+mod google;
+mod language;
+mod local;
+mod markdown;
+mod sam;
+mod speech;
+pub use announcement::{Announcement, DEFAULT_TEMPLATE};
+pub use cache::CacheStats;
+pub use dectalk::{DectalkOptions, DectalkVoice};
+pub use dictionary::{Dictionary, Pronunciation};
+pub use language::{detect_language, voice_for_language};
+pub use markdown::{render, RenderOptions};
+pub use speech::{Segment, Speech};
+use crate::video::Video;
+use crate::youtube::TTSVoice;
+use anyhow::Result;
+use serenity::async_trait;
+use std::{fmt::Display, str::FromStr};
+/// something that can turn text into speech on disk
+#[async_trait]
+pub trait TtsBackend: Send + Sync {
+    fn kind(&self) -> TtsBackendKind;
+    // whether it's worth trying at all, eg the binary or credentials are there
+    fn available(&self) -> bool;
+    // voice is a google voice, other backends only go off of its gender if they use it at all
+    async fn speak(&self, text: &str, voice: Option<TTSVoice>) -> Result<Video>;
+    // backends that pick a voice at random do it here, so the cache is keyed on the one picked
+    fn resolve_voice(&self, voice: Option<TTSVoice>) -> Option<TTSVoice> {
+        voice
+    }
+    // backends that can't read ssml get the plain text instead
+    fn supports_ssml(&self, _voice: Option<TTSVoice>) -> bool {
+        false
+    }
+    async fn speak_ssml(&self, _ssml: &str, _voice: Option<TTSVoice>) -> Result<Video> {
+        Err(anyhow::anyhow!("{} can't read ssml", self.kind()))
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TtsBackendKind {
+    Google,
+    Dectalk,
+    Sam,
+    Local,
+}
+impl TtsBackendKind {
+    // the order backends are tried in when nobody has picked one, or the picked one fails
+    pub const ALL: [TtsBackendKind; 4] = [
+        TtsBackendKind::Google,
+        TtsBackendKind::Local,
+        TtsBackendKind::Dectalk,
+        TtsBackendKind::Sam,
+    ];
+    pub fn backend(&self) -> &'static dyn TtsBackend {
+        match self {
+            TtsBackendKind::Google => &google::Google,
+            TtsBackendKind::Dectalk => &dectalk::Dectalk,
+            TtsBackendKind::Sam => &sam::Sam,
+            TtsBackendKind::Local => &local::Local,
+        }
+    }
+    // billed per character, the rest run locally for free
+    pub fn is_paid(&self) -> bool {
+        matches!(self, TtsBackendKind::Google)
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            TtsBackendKind::Google => "google",
+            TtsBackendKind::Dectalk => "dectalk",
+            TtsBackendKind::Sam => "sam",
+            TtsBackendKind::Local => "local",
+        }
+    }
+}
+impl Display for TtsBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TtsBackendKind::Google => write!(f, "Google Cloud"),
+            TtsBackendKind::Dectalk => write!(f, "DECtalk"),
+            TtsBackendKind::Sam => write!(f, "SAM"),
+            TtsBackendKind::Local => write!(f, "offline (piper or espeak-ng)"),
+        }
+    }
+}
+impl FromStr for TtsBackendKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        TtsBackendKind::ALL
+            .into_iter()
+            .find(|k| k.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown tts backend `{}`", s))
+    }
+}
+/// speaks with the first preferred backend that works, then falls back through the rest in the default order
+pub async fn speak(
+    text: &str,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+) -> Result<Video> {
+    speak_with(
+        text,
+        voice,
+        preferred,
+        &Dictionary::default(),
+        DectalkOptions::default(),
+    )
+    .await
+}
+/// [`speak`], rewriting the text with a guild's dictionary for whichever backend ends up speaking
+/// it, with `dectalk` setting the voice and rate if that's dectalk
+pub async fn speak_with(
+    text: &str,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+    dictionary: &Dictionary,
+    dectalk: DectalkOptions,
+) -> Result<Video> {
+    speak_rendered(&Speech::text(text), voice, preferred, dictionary, dectalk).await
+}
+/// [`speak_with`] for rendered speech, using ssml with backends that support it
+pub async fn speak_rendered(
+    speech: &Speech,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+    dictionary: &Dictionary,
+    dectalk: DectalkOptions,
+) -> Result<Video> {
+    let (video, _) =
+        speak_metered(speech, voice, preferred, dictionary, dectalk, &Unmetered).await?;
+    Ok(video)
+}
+/// characters a backend was actually asked to synthesize, cache hits don't count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub backend: TtsBackendKind,
+    pub characters: u64,
+}
+/// keeps paid backends to a quota, see [`speak_metered`]
+#[async_trait]
+pub trait Meter: Send + Sync {
+    // claims the characters before they're synthesized, false when that would go over the quota
+    async fn reserve(&self, usage: Usage) -> bool;
+    // hands back a claim that didn't get synthesized after all
+    async fn release(&self, usage: Usage);
+}
+/// a [`Meter`] with no quota
+pub struct Unmetered;
+#[async_trait]
+impl Meter for Unmetered {
+    async fn reserve(&self, _usage: Usage) -> bool {
+        true
+    }
+    async fn release(&self, _usage: Usage) {}
+}
+/// [`speak_rendered`] that skips paid backends when `meter` won't reserve the characters, and
+/// reports what it synthesized so it can be counted. paid usage has already been counted by then
+pub async fn speak_metered(
+    speech: &Speech,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+    dictionary: &Dictionary,
+    dectalk: DectalkOptions,
+    meter: &dyn Meter,
+) -> Result<(Video, Option<Usage>)> {
+    let mut tried = Vec::with_capacity(TtsBackendKind::ALL.len());
+    let mut first_error = None;
+    for kind in preferred.iter().chain(TtsBackendKind::ALL.iter()) {
+        if tried.contains(kind) {
+            continue;
+        }
+        tried.push(*kind);
+        let backend = kind.backend();
+        if !backend.available() {
+            log::trace!("Skipping unavailable tts backend {}", kind);
+            continue;
+        }
+        let voice = backend.resolve_voice(voice);
+        let speech = match kind {
+            // before the dictionary, whose rules are allowed to use dectalk commands for phonetic
+            // spellings
+            TtsBackendKind::Dectalk => {
+                speech.rewrite(|text| dictionary.apply(&dectalk.sanitize(text), *kind))
+            }
+            _ => speech.rewrite(|text| dictionary.apply(text, *kind)),
+        };
+        let ssml = speech.has_markup() && backend.supports_ssml(voice);
+        let input = if ssml {
+            speech.to_ssml()
+        } else if *kind == TtsBackendKind::Dectalk {
+            // dectalk is told which voice and how fast to speak inline
+            format!("{}{}", dectalk.preamble(voice), speech.to_text())
+        } else {
+            speech.to_text()
+        };
+        // google bills ssml tags as characters too
+        let characters = input.chars().count() as u64;
+        let usage = Usage {
+            backend: *kind,
+            characters,
+        };
+        // anything already cached is free to play back
+        let reserved = kind.is_paid() && !cache::contains(*kind, voice, &input, ssml);
+        if reserved && !meter.reserve(usage).await {
+            log::info!("Skipping {} for {} characters, over quota", kind, characters);
+            continue;
+        }
+        let spoken = if ssml {
+            cache::cached(*kind, voice, &input, true, backend.speak_ssml(&input, voice)).await
+        } else {
+            cache::cached(*kind, voice, &input, false, backend.speak(&input, voice)).await
+        };
+        match spoken {
+            Ok((video, synthesized)) => {
+                if reserved && !synthesized {
+                    // someone else cached it in the meantime
+                    meter.release(usage).await;
+                }
+                return Ok((video, synthesized.then_some(usage)));
+            }
+            Err(e) => {
+                if reserved {
+                    meter.release(usage).await;
+                }
+                log::warn!("Tts backend {} failed, trying the next one: {:?}", kind, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| anyhow::anyhow!("No tts backends are available")))
+}
+/// what gets read out before a song starts, for guilds with no announcer settings to go off
+pub fn now_playing(title: &str) -> String {
+    Announcement::new(title).render(DEFAULT_TEMPLATE)
+}
@@ -0,0 +1,47 @@
+use super::{TtsBackend, TtsBackendKind};
+use crate::video::{MediaType, Video};
+use crate::youtube::TTSVoice;
+use anyhow::Result;
+use serenity::async_trait;
+use std::path::PathBuf;
+pub struct Sam;
+impl Sam {
+    // sam lives alongside the transcription config
+    #[cfg(feature = "transcribe")]
+    fn path() -> Option<PathBuf> {
+        Some(crate::config::get_config().sam_path)
+    }
+    #[cfg(not(feature = "transcribe"))]
+    fn path() -> Option<PathBuf> {
+        None
+    }
+}
+#[async_trait]
+impl TtsBackend for Sam {
+    fn kind(&self) -> TtsBackendKind {
+        TtsBackendKind::Sam
+    }
+    fn available(&self) -> bool {
+        Self::path().map(|p| p.exists()).unwrap_or(false)
+    }
+    async fn speak(&self, text: &str, _voice: Option<TTSVoice>) -> Result<Video> {
+        let sam_path = Self::path().ok_or_else(|| anyhow::anyhow!("No sam path configured"))?;
+        let output = tokio::process::Command::new("node")
+            .arg(sam_path)
+            .arg(text)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to run command: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let mut path = crate::TEMP_PATH.clone();
+        let id = nanoid::nanoid!(10);
+        let name = format!("{}-tts.wav", id);
+        path.push(&name);
+        tokio::fs::write(&path, output.stdout).await?;
+        Video::from_path(path, "n/a".to_owned(), MediaType::Audio, id)
+    }
+}
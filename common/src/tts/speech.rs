@@ -0,0 +1,125 @@
+/// a piece of something to be read out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    // bold and italics, read with emphasis by backends that take ssml
+    Emphasis(String),
+    // a short break, between blocks and around things we skipped
+    Pause,
+}
+/// text to speak with just enough structure to turn into ssml for backends that support it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Speech(Vec<Segment>);
+impl Speech {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+    pub fn text(text: &str) -> Self {
+        Self(vec![Segment::Text(text.to_owned())])
+    }
+    pub fn push(&mut self, segment: Segment) {
+        match (self.0.last_mut(), segment) {
+            (Some(Segment::Text(last)), Segment::Text(next)) => last.push_str(&next),
+            (Some(Segment::Emphasis(last)), Segment::Emphasis(next)) => last.push_str(&next),
+            (Some(Segment::Pause), Segment::Pause) | (None, Segment::Pause) => {}
+            (_, segment) => self.0.push(segment),
+        }
+    }
+    pub fn push_text(&mut self, text: &str) {
+        self.push(Segment::Text(text.to_owned()))
+    }
+    pub fn extend(&mut self, other: Speech) {
+        for segment in other.0 {
+            self.push(segment);
+        }
+    }
+    /// true when there's nothing worth reading out
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|s| match s {
+            Segment::Text(t) | Segment::Emphasis(t) => t.trim().is_empty(),
+            Segment::Pause => true,
+        })
+    }
+    /// cuts everything after `max` characters, at a word boundary when there is one.
+    /// true if anything was cut
+    pub fn truncate(&mut self, max: usize) -> bool {
+        let mut left = max;
+        let mut cut_at = None;
+        for (i, segment) in self.0.iter().enumerate() {
+            let text = match segment {
+                Segment::Text(t) | Segment::Emphasis(t) => t,
+                Segment::Pause => continue,
+            };
+            let length = text.chars().count();
+            if length <= left {
+                left -= length;
+                continue;
+            }
+            let cut = text.char_indices().nth(left).map(|(at, _)| at).unwrap_or(text.len());
+            let cut = match text.get(..cut).and_then(|t| t.rfind(char::is_whitespace)) {
+                Some(space) if space > 0 => space,
+                _ => cut,
+            };
+            cut_at = Some((i, cut));
+            break;
+        }
+        let (index, cut) = match cut_at {
+            Some(c) => c,
+            None => return false,
+        };
+        self.0.truncate(index + 1);
+        if let Some(Segment::Text(t) | Segment::Emphasis(t)) = self.0.last_mut() {
+            t.truncate(cut);
+        }
+        true
+    }
+    pub fn has_markup(&self) -> bool {
+        self.0.iter().any(|s| !matches!(s, Segment::Text(_)))
+    }
+    /// runs every bit of text through `f`, eg to apply a dictionary
+    pub fn rewrite(&self, f: impl Fn(&str) -> String) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|s| match s {
+                    Segment::Text(t) => Segment::Text(f(t)),
+                    Segment::Emphasis(t) => Segment::Emphasis(f(t)),
+                    Segment::Pause => Segment::Pause,
+                })
+                .collect(),
+        )
+    }
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(t) | Segment::Emphasis(t) => text.push_str(t),
+                Segment::Pause => text.push_str(".\n"),
+            }
+        }
+        text.trim().to_owned()
+    }
+    pub fn to_ssml(&self) -> String {
+        let mut ssml = String::from("<speak>");
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(t) => ssml.push_str(&escape(t)),
+                Segment::Emphasis(t) => {
+                    ssml.push_str("<emphasis level=\"strong\">");
+                    ssml.push_str(&escape(t));
+                    ssml.push_str("</emphasis>");
+                }
+                Segment::Pause => ssml.push_str("<break time=\"400ms\"/>"),
+            }
+        }
+        ssml.push_str("</speak>");
+        ssml
+    }
+}
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
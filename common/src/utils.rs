@@ -74,9 +74,15 @@ impl songbird::EventHandler for DeleteAfterFinish {
     async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
         if let EventContext::Track(t) = ctx {
             if t.iter().any(|(state, _)| state.playing.is_done()) {
-                log::trace!("Track finished, deleting audio");
-                if let Some(audio) = self.audio.write().await.take() {
-                    drop(audio);
+                match self.audio.write().await.take() {
+                    Some(VideoType::Disk(video)) if video.is_cached() => {
+                        log::trace!("Track finished, leaving cached audio on disk");
+                    }
+                    Some(audio) => {
+                        log::trace!("Track finished, deleting audio");
+                        drop(audio);
+                    }
+                    None => {}
                 }
             } else {
                 log::trace!("Track not finished");
@@ -146,9 +152,36 @@ pub fn friendly_duration(dur: &std::time::Duration) -> String {
     }
     s.trim().to_owned()
 }
+// parses `ss`, `mm:ss` or `hh:mm:ss`, seconds may have a fractional part
+pub fn parse_timestamp(s: &str) -> anyhow::Result<Duration> {
+    let mut parts = s.trim().rsplit(':');
+    let seconds = parts
+        .next()
+        .ok_or(anyhow::anyhow!("Empty timestamp"))?
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid seconds in `{}`", s))?;
+    let mut total = seconds;
+    for multiplier in [60.0, 60.0 * 60.0] {
+        if let Some(part) = parts.next() {
+            total += part
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("Invalid timestamp `{}`", s))? as f64
+                * multiplier;
+        }
+    }
+    if parts.next().is_some() || !total.is_finite() || seconds < 0.0 {
+        return Err(anyhow::anyhow!(
+            "Invalid timestamp `{}`, expected `mm:ss` or `hh:mm:ss`",
+            s
+        ));
+    }
+    Ok(Duration::from_secs_f64(total))
+}
 #[derive(Debug)]
 pub enum TranscriptionMessage {
     Stop,
+    // whether the voice channel the bot is in has live captions turned on
+    Captions(bool),
 }
 
 pub fn full_datetime_format(
@@ -171,3 +204,57 @@ pub fn full_datetime_format(
         .replace("  ", " ")
         .replace("  ", " ")
 }
+/// answers a deferred interaction with an ephemeral followup, logging rather than returning failures
+pub async fn respond(
+    ctx: &serenity::all::Context,
+    interaction: &serenity::all::CommandInteraction,
+    content: &str,
+) {
+    if let Err(e) = interaction
+        .create_followup(
+            &ctx.http,
+            serenity::all::CreateInteractionResponseFollowup::new()
+                .content(content)
+                .ephemeral(true),
+        )
+        .await
+    {
+        log::error!("Failed to send response: {}", e);
+    }
+}
+/// replaces the deferred response to an interaction with `content`, logging rather than returning
+/// failures
+pub async fn edit_response(
+    ctx: &serenity::all::Context,
+    interaction: &serenity::all::CommandInteraction,
+    content: &str,
+) {
+    if let Err(e) = interaction
+        .edit_response(
+            &ctx.http,
+            serenity::all::EditInteractionResponse::new().content(content),
+        )
+        .await
+    {
+        log::error!("Failed to edit original interaction response: {:?}", e);
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_timestamp("1:30").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_timestamp(" 1:02:03 ").unwrap(), Duration::from_secs(3723));
+        assert_eq!(parse_timestamp("0:01.5").unwrap(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn bad_timestamps() {
+        for s in ["", "1:2:3:4", "a:30", "1:-30", "-5", "1.5:00", "NaN", "inf", "1::30"] {
+            assert!(parse_timestamp(s).is_err(), "{} was accepted", s);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use serenity::{
     async_trait, futures::StreamExt as _,
 };
 use songbird::{input::File, tracks::Track};
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use tokio::{sync::RwLock, task::JoinHandle};
 use ytd_rs::Arg;
 #[derive(Debug, Clone)]
@@ -136,6 +136,10 @@ impl Video {
     pub fn playlist_index(&self) -> usize {
         self.inner.playlist_index
     }
+    /// cached files belong to the tts cache and outlive the video
+    pub fn is_cached(&self) -> bool {
+        self.inner.cached
+    }
     pub fn to_songbird(&self) -> Track {
         Track::new(File::new(self.path()).into())
     }
@@ -159,6 +163,7 @@ impl Video {
                     v.title.clone().into(),
                     v.url.clone().into(),
                     v.duration,
+                    v.uploader.clone().map(Into::into),
                 ))
             })
             .collect::<Vec<VideoType>>())
@@ -168,6 +173,7 @@ impl Video {
         media_type: MediaType,
         spoiler: bool,
         max_filesize: &str,
+        clip: ClipRange,
     ) -> Result<VideoType> {
         let v = Self::get_video(url, false, false).await?;
         let v = v.first().ok_or(anyhow::anyhow!("No videos found"))?;
@@ -203,6 +209,10 @@ impl Video {
                         },
                     ));
                 }
+                if let Some(section) = clip.to_section() {
+                    args.push(Arg::new_with_arg("--download-sections", section.as_str()));
+                    args.push(Arg::new("--force-keyframes-at-cuts"));
+                }
                 match media_type {
                     MediaType::Audio => {
                         args.push(Arg::new("-x"));
@@ -247,6 +257,11 @@ impl Video {
                             .ok_or(anyhow::anyhow!("No Path"))?;
                         if file_name.starts_with(id.as_str()) {
                             run_preprocessor(&path).await?;
+                            if let Err(e) = fit_to_size(&path, media_type, max_filesize).await {
+                                // it never became a Video, so nothing else will clean it up
+                                let _ = std::fs::remove_file(&path);
+                                return Err(e);
+                            }
                             videos.push(Self::from_path(
                                 path,
                                 url.to_owned(),
@@ -293,6 +308,7 @@ impl Video {
                 duration,
                 media_type: MediaType::Video,
                 playlist_index: 0,
+                cached: false,
             }),
         })
     }
@@ -301,6 +317,20 @@ impl Video {
         url: String,
         media_type: MediaType,
         id: String,
+    ) -> Result<Self> {
+        Self::probe(path, url, media_type, id, false)
+    }
+    // an entry in the tts cache, which is left on disk when this drops
+    #[cfg(feature = "tts")]
+    pub(crate) fn from_cache(path: PathBuf, url: String) -> Result<Self> {
+        Self::probe(path, url.clone(), MediaType::Audio, url, true)
+    }
+    fn probe(
+        path: PathBuf,
+        url: String,
+        media_type: MediaType,
+        id: String,
+        cached: bool,
     ) -> Result<Self> {
         let file_name = match path.file_name().and_then(|f| f.to_str()) {
             Some(f) => f,
@@ -332,9 +362,33 @@ impl Video {
                 duration,
                 media_type,
                 playlist_index,
+                cached,
             }),
         })
     }
+    /// human readable summary of the file on disk, eg `7.6 MB | 720p @ 1150 kbps`
+    pub fn size_and_quality(&self) -> Result<String> {
+        let size = std::fs::metadata(self.path())?.len();
+        let probe = ffprobe::ffprobe(self.path())?;
+        let mut quality = format!("{:.1} MB", size as f64 / (1024.0 * 1024.0));
+        if let Some(height) = probe
+            .streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some("video"))
+            .and_then(|s| s.height)
+        {
+            quality.push_str(&format!(" | {}p", height));
+        }
+        if let Some(bitrate) = probe
+            .format
+            .bit_rate
+            .as_ref()
+            .and_then(|b| b.parse::<u64>().ok())
+        {
+            quality.push_str(&format!(" @ {} kbps", bitrate / 1000));
+        }
+        Ok(quality)
+    }
     pub async fn delete_when_finished(self, handle: songbird::tracks::TrackHandle) -> Result<()> {
         handle.add_event(
             songbird::events::Event::Track(songbird::events::TrackEvent::End),
@@ -343,6 +397,34 @@ impl Video {
         Ok(())
     }
 }
+/// an optional start and end point to only download part of a video
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClipRange {
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+}
+impl ClipRange {
+    pub fn new(start: Option<Duration>, end: Option<Duration>) -> Result<Self> {
+        if let (Some(start), Some(end)) = (start, end) {
+            if end <= start {
+                return Err(anyhow::anyhow!("The end of the clip must be after the start"));
+            }
+        }
+        Ok(Self { start, end })
+    }
+    // yt-dlp --download-sections syntax, None if the whole thing should be downloaded
+    fn to_section(self) -> Option<String> {
+        match (self.start, self.end) {
+            (None, None) => None,
+            (start, end) => Some(format!(
+                "*{}-{}",
+                start.map(|s| s.as_secs_f64()).unwrap_or(0.0),
+                end.map(|e| e.as_secs_f64().to_string())
+                    .unwrap_or_else(|| "inf".to_owned())
+            )),
+        }
+    }
+}
 #[derive(Debug)]
 struct InnerVideo {
     pub url: Arc<str>,
@@ -351,10 +433,14 @@ struct InnerVideo {
     pub duration: f64,
     pub media_type: MediaType,
     pub playlist_index: usize,
+    pub cached: bool,
 }
 impl Drop for InnerVideo {
     fn drop(&mut self) {
         log::trace!("Dropping video: {}", self.title);
+        if self.cached {
+            return;
+        }
         if let Err(e) = std::fs::remove_file(&self.path) {
             log::error!("Failed to delete video: {}", e);
         }
@@ -370,13 +456,21 @@ pub struct VideoInfo {
     pub(crate) title: Arc<str>,
     pub(crate) url: Arc<str>,
     pub(crate) duration: Option<f64>,
+    // the uploader, which is the artist for music
+    pub(crate) artist: Option<Arc<str>>,
 }
 impl VideoInfo {
-    pub fn new(title: Arc<str>, url: Arc<str>, duration: Option<f64>) -> Self {
+    pub fn new(
+        title: Arc<str>,
+        url: Arc<str>,
+        duration: Option<f64>,
+        artist: Option<Arc<str>>,
+    ) -> Self {
         Self {
             title,
             url,
             duration,
+            artist,
         }
     }
     pub fn title(&self) -> Arc<str> {
@@ -388,6 +482,9 @@ impl VideoInfo {
     pub fn duration(&self) -> Option<f64> {
         self.duration
     }
+    pub fn artist(&self) -> Option<Arc<str>> {
+        self.artist.as_ref().map(Arc::clone)
+    }
     pub fn to_songbird(&self) -> Track {
         Track::new(
             songbird::input::YoutubeDl::new(crate::WEB_CLIENT.clone(), self.url().to_string())
@@ -408,10 +505,9 @@ impl VideoInfo {
         #[cfg(feature = "tts")]
         return Ok(MetaVideo {
             video: v,
-            ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn(crate::youtube::get_tts(
-                Arc::clone(&title),
-                None,
-            )))),
+            ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn(async move {
+                crate::tts::speak(&crate::tts::now_playing(&title), None, &[]).await
+            }))),
             // title,
             author: None,
         });
@@ -491,6 +587,7 @@ pub struct RawVideo {
     pub url: String,
     pub title: String,
     pub duration: Option<f64>,
+    pub uploader: Option<String>,
 }
 async fn run_preprocessor(filepath: &PathBuf) -> Result<()> {
     let mut path = crate::config::get_config().data_path.clone();
@@ -503,6 +600,123 @@ async fn run_preprocessor(filepath: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+// heights we are willing to step down to, the first one that gets at least the paired video bitrate (kbps) wins
+const RESOLUTION_LADDER: &[(i64, u64)] = &[(1080, 4000), (720, 2000), (480, 900), (360, 500), (240, 0)];
+// anything below this and the result is not worth watching
+const MIN_VIDEO_KBPS: u64 = 100;
+const MIN_AUDIO_KBPS: u64 = 32;
+fn parse_filesize(max_filesize: &str) -> Result<u64> {
+    let max_filesize = max_filesize.trim();
+    let (number, multiplier) = match max_filesize.chars().last() {
+        Some('K' | 'k') => (&max_filesize[..max_filesize.len() - 1], 1024),
+        Some('M' | 'm') => (&max_filesize[..max_filesize.len() - 1], 1024 * 1024),
+        Some('G' | 'g') => (&max_filesize[..max_filesize.len() - 1], 1024 * 1024 * 1024),
+        _ => (max_filesize, 1),
+    };
+    Ok(number.parse::<u64>()? * multiplier)
+}
+// re-encodes the file in place if it is bigger than max_filesize, stepping the resolution down when the bitrate budget gets too small
+async fn fit_to_size(filepath: &PathBuf, media_type: MediaType, max_filesize: &str) -> Result<()> {
+    let max_bytes = parse_filesize(max_filesize)?;
+    if std::fs::metadata(filepath)?.len() <= max_bytes {
+        return Ok(());
+    }
+    let probe = ffprobe::ffprobe(filepath)?;
+    let duration = probe
+        .format
+        .duration
+        .as_ref()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or(anyhow::anyhow!("Could not get duration to compute bitrate"))?;
+    let source_height = probe
+        .streams
+        .iter()
+        .find(|s| s.codec_type.as_deref() == Some("video"))
+        .and_then(|s| s.height);
+    let file_name = filepath
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or(anyhow::anyhow!("No file name"))?;
+    // must not start with the download id or it would get picked up as another download
+    let tmp_path = RemoveOnDrop(filepath.with_file_name(format!("fit_{}", file_name)));
+    // leave some headroom for container overhead, and shrink it further every time we miss
+    for headroom in [0.92, 0.8, 0.65] {
+        let total_kbps = ((max_bytes as f64 * 8.0 * headroom) / duration / 1000.0) as u64;
+        let mut cmd = tokio::process::Command::new("ffmpeg");
+        cmd.arg("-y").arg("-i").arg(filepath);
+        match media_type {
+            MediaType::Audio => {
+                if total_kbps < MIN_AUDIO_KBPS {
+                    return Err(anyhow::anyhow!("Audio is too long to fit in {}", max_filesize));
+                }
+                cmd.arg("-vn").arg("-b:a").arg(format!("{}k", total_kbps.min(320)));
+            }
+            MediaType::Video => {
+                let audio_kbps = match total_kbps {
+                    t if t >= 1500 => 128,
+                    t if t >= 600 => 96,
+                    _ => 64,
+                };
+                let video_kbps = total_kbps.saturating_sub(audio_kbps);
+                if video_kbps < MIN_VIDEO_KBPS {
+                    return Err(anyhow::anyhow!("Video is too long to fit in {}", max_filesize));
+                }
+                let height = RESOLUTION_LADDER
+                    .iter()
+                    .find(|(_, kbps)| video_kbps >= *kbps)
+                    .map(|(h, _)| *h)
+                    .unwrap_or(240);
+                let height = source_height.map(|s| s.min(height)).unwrap_or(height);
+                cmd.arg("-c:v")
+                    .arg("libx264")
+                    .arg("-preset")
+                    .arg("veryfast")
+                    .arg("-b:v")
+                    .arg(format!("{}k", video_kbps))
+                    .arg("-maxrate")
+                    .arg(format!("{}k", video_kbps))
+                    .arg("-bufsize")
+                    .arg(format!("{}k", video_kbps * 2))
+                    .arg("-vf")
+                    .arg(format!("scale=-2:{}", height))
+                    .arg("-c:a")
+                    .arg("aac")
+                    .arg("-b:a")
+                    .arg(format!("{}k", audio_kbps));
+            }
+        }
+        cmd.arg(&tmp_path.0);
+        cmd.stdout(std::process::Stdio::null());
+        cmd.stderr(std::process::Stdio::null());
+        let status = cmd.spawn()?.wait().await?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("ffmpeg failed to transcode: {}", status));
+        }
+        let size = std::fs::metadata(&tmp_path.0)?.len();
+        if size <= max_bytes {
+            std::fs::rename(&tmp_path.0, filepath)?;
+            return Ok(());
+        }
+        log::info!(
+            "Transcode came out to {} bytes, over the {} byte limit, retrying",
+            size,
+            max_bytes
+        );
+    }
+    Err(anyhow::anyhow!("Could not shrink the file to fit in {}", max_filesize))
+}
+// deletes a scratch file however the function using it returns, renaming it away keeps it
+struct RemoveOnDrop(PathBuf);
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            if let Err(e) = std::fs::remove_file(&self.0) {
+                log::error!("Failed to remove {}: {}", self.0.display(), e);
+            }
+        }
+    }
+}
+
 // https://open.spotify.com/playlist/{playlist_id} IGNORE QUERY PARAMS -> https://api.spotify.com/v1/playlists/{playlist_id}/tracks
 // https://open.spotify.com/track/{track_id} IGNORE QUERY PARAMS -> https://api.spotify.com/v1/tracks/{track_id}
 // https://open.spotify.com/album/{album_id} IGNORE QUERY PARAMS -> https://api.spotify.com/v1/albums/{album_id}/tracks
@@ -562,6 +776,7 @@ pub async fn get_spotify_shiz(url: String) -> Result<Vec<RawVideo>> {
                             url: vid.url.clone(),
                             title: vid.title.clone(),
                             duration: Some(vid.duration.unwrap_or(0.0)),
+                            uploader: vid.uploader.clone(),
                         }])
                     }
                 }
@@ -814,6 +1029,7 @@ async fn get_all_youtube(names: Vec<String>) -> Result<Vec<RawVideo>> {
                             url: v[0].url.clone(),
                             title: v[0].title.clone(),
                             duration: v[0].duration,
+                            uploader: v[0].uploader.clone(),
                         })
                     } else {
                         log::warn!("No video found for {}", name);
@@ -1141,4 +1357,27 @@ mod spotify_types {
 //             log::info!("spoofydata: {:?}", spoofydata);
 //             Err(anyhow::anyhow!("Could not get spotify song title"))
 //         }
-//     }
\ No newline at end of file
+//     }
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clip_sections() {
+        let secs = |s| Some(Duration::from_secs(s));
+        assert_eq!(ClipRange::default().to_section(), None);
+        let range = ClipRange::new(secs(30), None).unwrap();
+        assert_eq!(range.to_section().as_deref(), Some("*30-inf"));
+        let range = ClipRange::new(None, secs(90)).unwrap();
+        assert_eq!(range.to_section().as_deref(), Some("*0-90"));
+        let range = ClipRange::new(secs(30), Some(Duration::from_millis(90500))).unwrap();
+        assert_eq!(range.to_section().as_deref(), Some("*30-90.5"));
+    }
+
+    #[test]
+    fn clip_end_before_start() {
+        let secs = |s| Some(Duration::from_secs(s));
+        assert!(ClipRange::new(secs(30), secs(30)).is_err());
+        assert!(ClipRange::new(secs(90), secs(30)).is_err());
+    }
+}
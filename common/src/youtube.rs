@@ -176,12 +176,13 @@ pub async fn get_video_info(url: String) -> Result<VideoInfo> {
         title: info.title.into(),
         url: url.into(),
         duration: info.duration,
+        artist: info.uploader.map(Into::into),
     })
 }
 pub async fn get_url_video_info(url: &str) -> Result<RawVidInfo> {
     let dl = ytd_rs::YoutubeDL::new(
         &std::path::PathBuf::from("/dev/null"),
-        vec![ytd_rs::Arg::new_with_arg("-O", "%(.{title,duration})#j")],
+        vec![ytd_rs::Arg::new_with_arg("-O", "%(.{title,duration,uploader})#j")],
         url,
     )?;
     let info = dl.download()?;
@@ -195,6 +196,8 @@ pub struct RawVidInfo {
     pub title: String,
     #[serde(default)]
     pub duration: Option<f64>,
+    #[serde(default)]
+    pub uploader: Option<String>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, Copy)]
 pub struct TTSVoice {
@@ -208,20 +211,125 @@ impl Default for TTSVoice {
     }
 }
 impl TTSVoice {
-    pub fn new(language_code: &'static str, name: &'static str, gender: &'static str) -> Self {
+    pub const fn new(
+        language_code: &'static str,
+        name: &'static str,
+        gender: &'static str,
+    ) -> Self {
         Self {
             language_code,
             name,
             gender,
         }
     }
+    /// looks a voice up in [`ALL_VOICES`] by its google name
+    pub fn by_name(name: &str) -> Option<Self> {
+        ALL_VOICES
+            .iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+    // the language without the region, eg `en` for en-GB
+    pub fn language(&self) -> &'static str {
+        self.language_code
+            .split('-')
+            .next()
+            .unwrap_or(self.language_code)
+    }
+    // google names are {language}-{engine}-{letter}
+    pub fn engine(&self) -> &'static str {
+        self.name.split('-').nth(2).unwrap_or("Standard")
+    }
+    pub fn accent(&self) -> &'static str {
+        match self.language_code {
+            "en-US" => "English (US)",
+            "en-GB" => "English (UK)",
+            "en-AU" => "English (Australia)",
+            "en-IN" => "English (India)",
+            "fil-PH" => "Filipino",
+            "de-DE" => "German",
+            "ja-JP" => "Japanese",
+            "es-ES" => "Spanish (Spain)",
+            "fr-FR" => "French",
+            "it-IT" => "Italian",
+            "pt-BR" => "Portuguese (Brazil)",
+            "ko-KR" => "Korean",
+            "th-TH" => "Thai",
+            "vi-VN" => "Vietnamese",
+            other => other,
+        }
+    }
+    /// eg `English (UK), Neural2 D, male`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {} {}, {}",
+            self.accent(),
+            self.engine(),
+            self.name.rsplit('-').next().unwrap_or_default(),
+            self.gender.to_lowercase()
+        )
+    }
 }
+/// every voice someone can pick for themselves, whatever the bot rotates through by default
+pub const ALL_VOICES: &[TTSVoice] = &[
+    TTSVoice::new("en-US", "en-US-Journey-D", "MALE"),
+    TTSVoice::new("en-US", "en-US-Journey-F", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Journey-O", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Studio-Q", "MALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-A", "MALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-C", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-D", "MALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-E", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-F", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-G", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-H", "FEMALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-I", "MALE"),
+    TTSVoice::new("en-US", "en-US-Neural2-J", "MALE"),
+    TTSVoice::new("en-GB", "en-GB-Neural2-A", "FEMALE"),
+    TTSVoice::new("en-GB", "en-GB-Neural2-B", "MALE"),
+    TTSVoice::new("en-GB", "en-GB-Neural2-C", "FEMALE"),
+    TTSVoice::new("en-GB", "en-GB-Neural2-D", "MALE"),
+    TTSVoice::new("en-GB", "en-GB-Neural2-F", "FEMALE"),
+    TTSVoice::new("en-AU", "en-AU-Neural2-A", "FEMALE"),
+    TTSVoice::new("en-AU", "en-AU-Neural2-B", "MALE"),
+    TTSVoice::new("en-AU", "en-AU-Neural2-C", "FEMALE"),
+    TTSVoice::new("en-AU", "en-AU-Neural2-D", "MALE"),
+    TTSVoice::new("en-IN", "en-IN-Neural2-A", "FEMALE"),
+    TTSVoice::new("en-IN", "en-IN-Neural2-B", "MALE"),
+    TTSVoice::new("en-IN", "en-IN-Neural2-C", "MALE"),
+    TTSVoice::new("en-IN", "en-IN-Neural2-D", "FEMALE"),
+    TTSVoice::new("fil-PH", "fil-ph-Neural2-D", "MALE"),
+    TTSVoice::new("de-DE", "de-DE-Neural2-D", "MALE"),
+    TTSVoice::new("de-DE", "de-DE-Neural2-A", "FEMALE"),
+    TTSVoice::new("ja-JP", "ja-JP-Neural2-D", "MALE"),
+    TTSVoice::new("ja-JP", "ja-JP-Neural2-B", "FEMALE"),
+    TTSVoice::new("es-ES", "es-ES-Neural2-F", "MALE"),
+    TTSVoice::new("es-ES", "es-ES-Neural2-A", "FEMALE"),
+    TTSVoice::new("fr-FR", "fr-FR-Neural2-A", "FEMALE"),
+    TTSVoice::new("fr-FR", "fr-FR-Neural2-B", "MALE"),
+    TTSVoice::new("it-IT", "it-IT-Neural2-A", "FEMALE"),
+    TTSVoice::new("it-IT", "it-IT-Neural2-C", "MALE"),
+    TTSVoice::new("pt-BR", "pt-BR-Neural2-A", "FEMALE"),
+    TTSVoice::new("pt-BR", "pt-BR-Neural2-B", "MALE"),
+    TTSVoice::new("ko-KR", "ko-KR-Neural2-B", "FEMALE"),
+    TTSVoice::new("ko-KR", "ko-KR-Neural2-C", "MALE"),
+    TTSVoice::new("th-TH", "th-TH-Neural2-C", "FEMALE"),
+    TTSVoice::new("vi-VN", "vi-VN-Neural2-A", "FEMALE"),
+];
 #[cfg(feature = "tts")]
 pub async fn get_tts<F>(title: F, specificvoice: Option<TTSVoice>) -> Result<Video>
 where
     F: AsRef<str>,
 {
-    let mut title = title.as_ref().to_owned();
+    synthesize(serde_json::json!({ "text": title.as_ref() }), specificvoice).await
+}
+/// like [`get_tts`] but reads SSML markup, which journey voices don't support
+#[cfg(feature = "tts")]
+pub async fn get_ssml_tts(ssml: &str, specificvoice: Option<TTSVoice>) -> Result<Video> {
+    synthesize(serde_json::json!({ "ssml": ssml }), specificvoice).await
+}
+#[cfg(feature = "tts")]
+async fn synthesize(input: serde_json::Value, specificvoice: Option<TTSVoice>) -> Result<Video> {
     use crate::video::Video;
     use rand::seq::SliceRandom;
     let backup_voice = VOICES
@@ -232,14 +340,9 @@ where
         Some(v) => v,
         None => backup_voice?,
     };
-    if specificvoice.is_none() {
-        title = format!("Now playing... {}", title);
-    }
     let body = serde_json::json!(
         {
-            "input":{
-                "text": title,
-            },
+            "input": input,
             "voice":{
                 "languageCode": voice.language_code,
                 "name": voice.name,
@@ -397,6 +500,7 @@ impl YoutubeMedia {
             url: self.url.clone(),
             title: self.title.clone(),
             duration: self.duration,
+            uploader: self.uploader.clone(),
         }
     }
 }
@@ -0,0 +1,241 @@
+use common::anyhow::Result;
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand};
+use common::serenity::{
+    all::*,
+    futures::{stream::FuturesUnordered, StreamExt as _},
+};
+use common::tts::{Announcement, TtsBackendKind, DEFAULT_TEMPLATE};
+use common::utils::respond;
+use common::youtube::{TTSVoice, ALL_VOICES};
+use common::{log, tokio, SubCommandTrait};
+use long_term_storage::{Announcer, Guild};
+use std::sync::Arc;
+// titles are read before every song, anything longer gets in the way of the music
+const MAX_TEMPLATE_LENGTH: u16 = 200;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        let mut backend = CreateCommandOption::new(
+            CommandOptionType::String,
+            "backend",
+            "The TTS engine to read announcements with first",
+        )
+        .add_string_choice("Same as messages", "default");
+        for kind in TtsBackendKind::ALL {
+            backend = backend.add_string_choice(kind.to_string(), kind.name());
+        }
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "How song titles are announced, leave everything empty to see the current settings",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "voice",
+                "The voice to announce songs with",
+            )
+            .set_autocomplete(true),
+            backend,
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "template",
+                "What to say: {title} {artist} {requester} {duration}, [by {artist}] if known, or default",
+            )
+            .max_length(MAX_TEMPLATE_LENGTH),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "queue_finished",
+                "Say so when the last song in the queue ends",
+            ),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "added_by",
+                "Say who added each song after its title",
+            ),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let mut voice = None;
+        let mut backend = None;
+        let mut template = None;
+        let mut queue_finished = None;
+        let mut added_by = None;
+        for option in options {
+            match (option.name, &option.value) {
+                ("voice", ResolvedValue::String(s)) => voice = Some(*s),
+                ("backend", ResolvedValue::String(s)) => backend = Some(*s),
+                ("template", ResolvedValue::String(s)) => template = Some(*s),
+                ("queue_finished", ResolvedValue::Boolean(b)) => queue_finished = Some(*b),
+                ("added_by", ResolvedValue::Boolean(b)) => added_by = Some(*b),
+                _ => {}
+            }
+        }
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let announcer = &mut config.announcer;
+        let changed = voice.is_some()
+            || backend.is_some()
+            || template.is_some()
+            || queue_finished.is_some()
+            || added_by.is_some();
+        match voice {
+            None => {}
+            Some("random") => announcer.voice = None,
+            Some(name) => match TTSVoice::by_name(name) {
+                Some(v) => announcer.voice = Some(v),
+                None => {
+                    respond(ctx, interaction, "That voice doesn't exist").await;
+                    return Ok(());
+                }
+            },
+        }
+        match backend {
+            None => {}
+            Some("default") => announcer.backend = None,
+            Some(s) => match s.parse::<TtsBackendKind>() {
+                Ok(b) => announcer.backend = Some(b),
+                Err(e) => {
+                    log::error!("Invalid tts backend: {:?}", e);
+                    respond(ctx, interaction, "Invalid backend").await;
+                    return Ok(());
+                }
+            },
+        }
+        if let Some(template) = template.map(str::trim) {
+            announcer.title_template = (!template.is_empty()
+                && !template.eq_ignore_ascii_case("default"))
+            .then(|| template.to_owned());
+        }
+        if let Some(queue_finished) = queue_finished {
+            announcer.queue_finished = queue_finished;
+        }
+        if let Some(added_by) = added_by {
+            announcer.added_by = added_by;
+        }
+        let description = describe(announcer);
+        if !changed {
+            respond(ctx, interaction, &description).await;
+            return Ok(());
+        }
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+            return Ok(());
+        }
+        respond(ctx, interaction, &description).await;
+        // songs being added look it up themselves, but every connection in this guild keeps its own
+        let connection_handler = {
+            let data = ctx.data.read().await;
+            match data.get::<AudioCommandHandler>() {
+                Some(v) => Arc::clone(v),
+                None => {
+                    log::error!("Failed to get audio command handler");
+                    return Ok(());
+                }
+            }
+        };
+        tokio::task::spawn(async move {
+            let mut map = connection_handler.write().await;
+            let mut res = FuturesUnordered::new();
+            for sender in map.values_mut() {
+                if sender.guild_id != guild_id {
+                    continue;
+                }
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                let _ = sender.send((
+                    tx,
+                    AudioPromiseCommand::MetaCommand(MetaCommand::ReloadAnnouncer),
+                ));
+                res.push(rx);
+            }
+            while let Some(r) = res.next().await {
+                if let Err(e) = r {
+                    log::error!("Failed to reload announcer: {:?}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "announcer"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let partial = match interaction.data.autocomplete() {
+            Some(o) if o.name == "voice" => o.value.to_lowercase(),
+            _ => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        if "random".contains(&partial) {
+            completions = completions.add_string_choice("A random voice for each song", "random");
+        }
+        for voice in ALL_VOICES
+            .iter()
+            .filter(|v| {
+                v.name.to_lowercase().contains(&partial)
+                    || v.describe().to_lowercase().contains(&partial)
+            })
+            .take(24)
+        {
+            completions = completions.add_string_choice(voice.describe(), voice.name);
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
+fn describe(announcer: &Announcer) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let example = Announcement {
+        title: "Never Gonna Give You Up",
+        artist: Some("Rick Astley"),
+        requester: Some("someone"),
+        duration: Some(213.0),
+    };
+    format!(
+        "Voice: {}\nBackend: {}\nTemplate: `{}`\nAnnounce the end of the queue: {}\nAnnounce who added songs: {}\nSounds like: {}",
+        announcer
+            .voice
+            .map(|v| v.describe())
+            .unwrap_or_else(|| "a random one".to_owned()),
+        announcer
+            .backend
+            .map(|b| b.to_string())
+            .unwrap_or_else(|| "same as messages".to_owned()),
+        announcer.title_template.as_deref().unwrap_or(DEFAULT_TEMPLATE),
+        yes_no(announcer.queue_finished),
+        yes_no(announcer.added_by),
+        announcer.announce(&example),
+    )
+}
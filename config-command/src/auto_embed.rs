@@ -0,0 +1,360 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::{AutoEmbed, DEFAULT_AUTO_EMBED_DOMAINS};
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![Box::new(Channel), Box::new(Domain), Box::new(List)],
+        }
+    }
+}
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommandGroup,
+            self.command_name(),
+            "Automatically embed media from links posted in chosen channels",
+        )
+        .set_sub_options(self.subcommands.iter().map(|sc| sc.register_command()))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let member = match interaction.member {
+            Some(ref member) => member,
+            None => {
+                respond(ctx, interaction, "This command can only be run in a guild").await;
+                return Ok(());
+            }
+        };
+        let (subcommand, opts) = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::SubCommand(ref opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                respond(ctx, interaction, "Invalid subcommand").await;
+                return Ok(());
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                if member
+                    .permissions(&ctx.cache)
+                    .map(|p| p.contains(sc.permissions()))
+                    .unwrap_or(false)
+                {
+                    return sc.run(ctx, interaction, opts).await;
+                } else {
+                    respond(
+                        ctx,
+                        interaction,
+                        "You do not have permission to run this command",
+                    )
+                    .await;
+                    return Ok(());
+                }
+            }
+        }
+        respond(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "auto_embed"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty() // checked in the subcommands
+    }
+}
+// /config auto_embed channel <channel> <optional enabled> - Turn automatic embedding on or off for a channel
+struct Channel;
+#[async_trait]
+impl SubCommandTrait for Channel {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Turn automatic embedding on or off for a channel",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "channel",
+                "The channel to watch for links",
+            )
+            .channel_types(vec![ChannelType::Text, ChannelType::Voice])
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "enabled",
+                "Whether links in this channel should be embedded, toggles if not provided",
+            )
+            .required(false),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let mut config = match load(ctx, interaction).await {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        let channel = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Channel(c) if o.name == "channel" => Some(c.id),
+            _ => None,
+        }) {
+            Some(c) => c,
+            None => {
+                respond(ctx, interaction, "You must provide a channel").await;
+                return Ok(());
+            }
+        };
+        let enabled = options
+            .iter()
+            .find_map(|o| match o.value {
+                ResolvedValue::Boolean(b) if o.name == "enabled" => Some(b),
+                _ => None,
+            })
+            .unwrap_or(!config.channels.contains(&channel));
+        if enabled {
+            config.channels.insert(channel);
+        } else {
+            config.channels.remove(&channel);
+        }
+        match config.save().await {
+            Ok(()) => {
+                respond(
+                    ctx,
+                    interaction,
+                    &format!(
+                        "Automatic embedding is now {} in <#{}>",
+                        if enabled { "on" } else { "off" },
+                        channel
+                    ),
+                )
+                .await
+            }
+            Err(e) => {
+                log::error!("Failed to save new value: {:?}", e);
+                respond(ctx, interaction, "Failed to save new value").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "channel"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config auto_embed domain <domain> <optional enabled> <optional max_size_mb> <optional per_minute> - Configure a domain, if nothing is provided the current settings are displayed
+struct Domain;
+#[async_trait]
+impl SubCommandTrait for Domain {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Configure which domains get embedded and how",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "domain",
+                "The domain, eg x.com (subdomains are included)",
+            )
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "enabled",
+                "Whether links from this domain should be embedded",
+            )
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "max_size_mb",
+                "The largest file to upload in megabytes, 0 to use the server's upload limit",
+            )
+            .min_int_value(0)
+            .max_int_value(100)
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "per_minute",
+                "How many links from this domain to embed per channel per minute",
+            )
+            .min_int_value(0)
+            .max_int_value(60)
+            .required(false),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let mut config = match load(ctx, interaction).await {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        let domain_name = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "domain" => Some(s),
+            _ => None,
+        }) {
+            Some(d) => d
+                .trim()
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_start_matches("www.")
+                .trim_end_matches('/'),
+            None => {
+                respond(ctx, interaction, "You must provide a domain").await;
+                return Ok(());
+            }
+        };
+        if domain_name.is_empty() || domain_name.contains('/') || !domain_name.contains('.') {
+            respond(ctx, interaction, "That does not look like a domain").await;
+            return Ok(());
+        }
+        let mut changed = false;
+        {
+            let domain = config.domain_mut(domain_name);
+            for o in options {
+                match (o.name, &o.value) {
+                    ("enabled", ResolvedValue::Boolean(b)) => {
+                        domain.enabled = *b;
+                        changed = true;
+                    }
+                    ("max_size_mb", ResolvedValue::Integer(i)) => {
+                        domain.max_size_mb = (*i > 0).then_some(*i as u32);
+                        changed = true;
+                    }
+                    ("per_minute", ResolvedValue::Integer(i)) => {
+                        domain.per_minute = (*i).max(0) as u32;
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let description = describe(config.domain_mut(domain_name));
+        if !changed {
+            respond(ctx, interaction, &description).await;
+            return Ok(());
+        }
+        match config.save().await {
+            Ok(()) => respond(ctx, interaction, &format!("Updated {}", description)).await,
+            Err(e) => {
+                log::error!("Failed to save new value: {:?}", e);
+                respond(ctx, interaction, "Failed to save new value").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "domain"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config auto_embed list - Show the channels and domains that are configured
+struct List;
+#[async_trait]
+impl SubCommandTrait for List {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Show the automatic embedding settings",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let config = match load(ctx, interaction).await {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        let channels = if config.channels.is_empty() {
+            "No channels, use `/config auto_embed channel` to add one".to_owned()
+        } else {
+            config
+                .channels
+                .iter()
+                .map(|c| format!("<#{}>", c))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let domains = config
+            .domains
+            .iter()
+            .map(|d| format!("- {}", describe(d)))
+            .collect::<Vec<String>>()
+            .join("\n");
+        respond(
+            ctx,
+            interaction,
+            &format!("Channels: {}\nDomains:\n{}", channels, domains),
+        )
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "list"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+fn describe(domain: &long_term_storage::AutoEmbedDomain) -> String {
+    format!(
+        "`{}`{}: {}, {} per minute, {}",
+        domain.domain,
+        if DEFAULT_AUTO_EMBED_DOMAINS.contains(&domain.domain.as_ref()) {
+            ""
+        } else {
+            " (custom)"
+        },
+        if domain.enabled { "enabled" } else { "disabled" },
+        domain.per_minute,
+        match domain.max_size_mb {
+            Some(mb) => format!("up to {}MB", mb),
+            None => "up to the server upload limit".to_owned(),
+        }
+    )
+}
+async fn load(ctx: &Context, interaction: &CommandInteraction) -> Option<AutoEmbed> {
+    let guild_id = match interaction.guild_id {
+        Some(g) => g,
+        None => {
+            respond(ctx, interaction, "This command can only be used in a server").await;
+            return None;
+        }
+    };
+    match AutoEmbed::load(guild_id).await {
+        Ok(c) => Some(c),
+        Err(e) => {
+            log::error!("Failed to load auto embed settings: {:?}", e);
+            respond(ctx, interaction, "Failed to load auto embed settings").await;
+            None
+        }
+    }
+}
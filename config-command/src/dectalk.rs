@@ -0,0 +1,136 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::tts::{DectalkOptions, DectalkVoice};
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::Guild;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        let mut voice = CreateCommandOption::new(
+            CommandOptionType::String,
+            "voice",
+            "Which DECtalk speaker reads things out",
+        )
+        .add_string_choice("Match each person's voice", "match");
+        for v in DectalkVoice::ALL {
+            voice = voice.add_string_choice(v.to_string(), v.name());
+        }
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "How DECtalk speaks in this server, leave everything empty to see the current settings",
+        )
+        .set_sub_options(vec![
+            voice,
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "rate",
+                "Words per minute, 0 for DECtalk's default of 180",
+            )
+            .min_int_value(0)
+            .max_int_value(u64::from(DectalkOptions::MAX_RATE)),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "allow_commands",
+                "Obey [:...] commands in messages instead of stripping them, anyone can abuse these",
+            ),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let mut voice = None;
+        let mut rate = None;
+        let mut allow_commands = None;
+        for option in options {
+            match (option.name, &option.value) {
+                ("voice", ResolvedValue::String(s)) => voice = Some(*s),
+                ("rate", ResolvedValue::Integer(i)) => {
+                    rate = Some((*i > 0).then(|| {
+                        (*i).clamp(
+                            DectalkOptions::MIN_RATE as i64,
+                            DectalkOptions::MAX_RATE as i64,
+                        ) as u16
+                    }))
+                }
+                ("allow_commands", ResolvedValue::Boolean(b)) => allow_commands = Some(*b),
+                _ => {}
+            }
+        }
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        if voice.is_none() && rate.is_none() && allow_commands.is_none() {
+            respond(ctx, interaction, &describe(&config.dectalk)).await;
+            return Ok(());
+        }
+        match voice {
+            None => {}
+            Some("match") => config.dectalk.voice = None,
+            Some(s) => match s.parse::<DectalkVoice>() {
+                Ok(v) => config.dectalk.voice = Some(v),
+                Err(e) => {
+                    log::error!("Invalid dectalk voice: {:?}", e);
+                    respond(ctx, interaction, "Invalid voice").await;
+                    return Ok(());
+                }
+            },
+        }
+        if let Some(rate) = rate {
+            config.dectalk.rate = rate;
+        }
+        if let Some(allow_commands) = allow_commands {
+            config.dectalk.allow_commands = allow_commands;
+        }
+        let description = describe(&config.dectalk);
+        // options are looked up with the dictionary whenever something is spoken
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+            return Ok(());
+        }
+        respond(ctx, interaction, &description).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "dectalk"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+fn describe(options: &DectalkOptions) -> String {
+    format!(
+        "Voice: {}\nRate: {}\nInline commands: {}",
+        options
+            .voice
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "matches each person's voice".to_owned()),
+        options
+            .rate
+            .map(|r| format!("{} words per minute", r))
+            .unwrap_or_else(|| "default".to_owned()),
+        if options.allow_commands {
+            "obeyed"
+        } else {
+            "stripped"
+        },
+    )
+}
@@ -0,0 +1,192 @@
+use common::anyhow::Result;
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand};
+use common::idle::IdlePlaylist;
+use common::serenity::{
+    all::*,
+    futures::{stream::FuturesUnordered, StreamExt as _},
+};
+use common::utils::respond;
+use common::{log, tokio, SubCommandTrait};
+use long_term_storage::Guild;
+use std::sync::Arc;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Shuffle through a folder or playlist when nothing is queued",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "source",
+                "A folder or playlist in the bot's idle folder, or a url to a playlist",
+            )
+            .set_autocomplete(true),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "clear",
+            "Go back to the radio",
+        ))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let mut source = None;
+        let mut clear = false;
+        for option in options {
+            match (option.name, &option.value) {
+                ("source", ResolvedValue::String(s)) => source = Some(s.trim()),
+                ("clear", ResolvedValue::Boolean(b)) => clear = *b,
+                _ => {}
+            }
+        }
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let new_value: Option<Arc<str>> = match (source, clear) {
+            (Some(_), true) => {
+                respond(ctx, interaction, "Pick either a source or clear, not both").await;
+                return Ok(());
+            }
+            (None, false) => {
+                respond(
+                    ctx,
+                    interaction,
+                    &match config.idle_playlist {
+                        Some(ref source) => format!("The idle playlist is `{}`", source),
+                        None => "There is no idle playlist, the radio plays when nothing is queued"
+                            .to_owned(),
+                    },
+                )
+                .await;
+                return Ok(());
+            }
+            (None, true) => None,
+            (Some(source), false) => match IdlePlaylist::load(source).await {
+                Ok(playlist) => {
+                    respond(
+                        ctx,
+                        interaction,
+                        &format!(
+                            "The idle playlist is now `{}` with {} tracks{}",
+                            source,
+                            playlist.len(),
+                            match config.radio_audio_url {
+                                Some(_) => ", it will play once the custom radio is reset",
+                                None => "",
+                            }
+                        ),
+                    )
+                    .await;
+                    Some(source.into())
+                }
+                Err(e) => {
+                    respond(
+                        ctx,
+                        interaction,
+                        &format!("Couldn't load `{}`: {}", source, e),
+                    )
+                    .await;
+                    return Ok(());
+                }
+            },
+        };
+        if new_value.is_none() {
+            respond(ctx, interaction, "Cleared the idle playlist").await;
+        }
+        config.idle_playlist = new_value.as_ref().map(Arc::clone);
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+        }
+        // every connection in this guild needs to know about the new value
+        let connection_handler = {
+            let data = ctx.data.read().await;
+            match data.get::<AudioCommandHandler>() {
+                Some(v) => Arc::clone(v),
+                None => {
+                    log::error!("Failed to get audio command handler");
+                    return Ok(());
+                }
+            }
+        };
+        tokio::task::spawn(async move {
+            let mut map = connection_handler.write().await;
+            let mut res = FuturesUnordered::new();
+            for sender in map.values_mut() {
+                if sender.guild_id != guild_id {
+                    continue;
+                }
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                let _ = sender.send((
+                    tx,
+                    AudioPromiseCommand::MetaCommand(MetaCommand::ChangeIdlePlaylist(
+                        new_value.as_ref().map(Arc::clone),
+                    )),
+                ));
+                res.push(rx);
+            }
+            while let Some(r) = res.next().await {
+                if let Err(e) = r {
+                    log::error!("Failed to change idle playlist: {:?}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "idle_playlist"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let partial = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } if o.name == "source" => Some(value),
+            _ => None,
+        }) {
+            Some(p) => p.to_lowercase(),
+            None => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        for name in IdlePlaylist::available()
+            .await
+            .iter()
+            .filter(|n| n.to_lowercase().contains(&partial))
+            .take(25)
+        {
+            completions = completions.add_string_choice(name, name);
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,23 @@
 #![feature(try_blocks)]
 use common::anyhow::Result;
 use common::serenity::all::*;
+mod announcer;
+mod auto_embed;
+mod dectalk;
 mod default_volume;
 mod empty_channel_timeout;
+mod idle_playlist;
+mod radio_announcements;
 mod radio_source;
 use common::{log, CommandTrait, SubCommandTrait};
+mod read_spoilers;
 mod read_titles;
 mod transcribe;
+mod transitions;
+mod tts_backend;
+mod tts_dictionary;
+mod tts_filter;
+mod tts_quota;
 pub struct Command {
     subcommands: Vec<Box<dyn SubCommandTrait>>,
 }
@@ -17,8 +28,19 @@ impl Command {
                 Box::new(empty_channel_timeout::Command),
                 Box::new(default_volume::Command::new()),
                 Box::new(read_titles::Command),
+                Box::new(announcer::Command),
+                Box::new(read_spoilers::Command),
+                Box::new(radio_announcements::Command),
+                Box::new(transitions::Command),
+                Box::new(tts_backend::Command),
+                Box::new(dectalk::Command),
+                Box::new(tts_dictionary::Command::new()),
+                Box::new(tts_filter::Command::new()),
+                Box::new(tts_quota::Command),
                 Box::new(transcribe::Command::new()),
                 Box::new(radio_source::Command::new()),
+                Box::new(idle_playlist::Command),
+                Box::new(auto_embed::Command::new()),
             ],
         }
     }
@@ -113,4 +135,21 @@ impl CommandTrait for Command {
     fn command_name(&self) -> &str {
         "config"
     }
+    async fn autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        for option in interaction.data.options() {
+            for sc in &self.subcommands {
+                if sc.command_name() == option.name {
+                    match option.value {
+                        ResolvedValue::SubCommand(opts) | ResolvedValue::SubCommandGroup(opts) => {
+                            return sc.autocomplete(ctx, interaction, &opts).await;
+                        }
+                        _ => {
+                            return Err(common::anyhow::anyhow!("Invalid option type"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,154 @@
+use common::anyhow::Result;
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand, RadioAnnouncements};
+use common::serenity::{
+    all::*,
+    futures::{stream::FuturesUnordered, StreamExt as _},
+};
+use common::{log, tokio, SubCommandTrait};
+use long_term_storage::Guild;
+use std::sync::Arc;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Whether to announce what the radio is playing over TTS",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "new_value", "The new value")
+                .add_string_choice("Off", "off")
+                .add_string_choice("Titles only", "titles")
+                .add_string_choice("Station, title and artist on every change", "every"),
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content("This command can only be used in a server")
+                            .ephemeral(true),
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+        let announcements = options
+            .iter()
+            .find(|o| o.name == "new_value")
+            .and_then(|o| match o.value {
+                ResolvedValue::String("off") => Some(RadioAnnouncements::Off),
+                ResolvedValue::String("titles") => Some(RadioAnnouncements::TitlesOnly),
+                ResolvedValue::String("every") => Some(RadioAnnouncements::EveryChange),
+                _ => None,
+            });
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content("Failed to load guild")
+                            .ephemeral(true),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        match announcements {
+            None => {
+                interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content(format!(
+                                "Radio announcements are currently {}",
+                                config.radio_announcements
+                            ))
+                            .ephemeral(true),
+                    )
+                    .await?;
+            }
+            Some(value) => {
+                config.radio_announcements = value;
+                interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content(format!("Radio announcements are now {}", value))
+                            .ephemeral(true),
+                    )
+                    .await?;
+                if let Err(e) = config.save().await {
+                    log::error!("Failed to save new value: {:?}", e);
+                    if let Err(e) = interaction
+                        .create_followup(
+                            &ctx.http,
+                            CreateInteractionResponseFollowup::new()
+                                .content("Failed to save new value")
+                                .ephemeral(true),
+                        )
+                        .await
+                    {
+                        log::error!("Failed to send response: {}", e);
+                    }
+                }
+                // every connection in this guild needs to know about the new value
+                let connection_handler = {
+                    let data = ctx.data.read().await;
+                    match data.get::<AudioCommandHandler>() {
+                        Some(v) => Arc::clone(v),
+                        None => {
+                            log::error!("Failed to get audio command handler");
+                            return Ok(());
+                        }
+                    }
+                };
+                tokio::task::spawn(async move {
+                    let mut map = connection_handler.write().await;
+                    let mut res = FuturesUnordered::new();
+                    for sender in map.values_mut() {
+                        if sender.guild_id != guild_id {
+                            continue;
+                        }
+                        let (tx, rx) = tokio::sync::oneshot::channel();
+                        let _ = sender.send((
+                            tx,
+                            AudioPromiseCommand::MetaCommand(MetaCommand::ChangeRadioAnnouncements(
+                                value,
+                            )),
+                        ));
+                        res.push(rx);
+                    }
+                    while let Some(r) = res.next().await {
+                        if let Err(e) = r {
+                            log::error!("Failed to change radio announcements: {:?}", e);
+                        }
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "radio_announcements"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
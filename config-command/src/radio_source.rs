@@ -5,8 +5,9 @@ use common::serenity::{
     all::*,
     futures::{stream::FuturesUnordered, StreamExt},
 };
+use common::utils::respond;
 use common::{log, tokio, SubCommandTrait};
-use long_term_storage::Guild;
+use long_term_storage::{Guild, RadioPreset};
 use serde::Deserialize;
 use std::sync::Arc;
 pub struct Command {
@@ -15,7 +16,13 @@ pub struct Command {
 impl Command {
     pub fn new() -> Self {
         Self {
-            subcommands: vec![Box::new(StreamUrl), Box::new(DataUrl), Box::new(Reset)],
+            subcommands: vec![
+                Box::new(StreamUrl),
+                Box::new(DataUrl),
+                Box::new(Reset),
+                Box::new(PresetAdd),
+                Box::new(PresetRemove),
+            ],
         }
     }
 }
@@ -568,6 +575,195 @@ impl SubCommandTrait for Reset {
         Permissions::MANAGE_GUILD
     }
 }
+// /config radio_source preset_add <name> <stream_url> <optional data_url> <optional art_url> - Add a station that can be switched to with /radio switch
+struct PresetAdd;
+#[async_trait]
+impl SubCommandTrait for PresetAdd {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Add or replace a radio station that can be switched to with /radio switch",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::String, "name", "The name of the station")
+                .max_length(RadioPreset::MAX_NAME_LENGTH as u16)
+                .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "stream_url",
+                "The stream url of the station",
+            )
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "data_url",
+                "An azuracast or icecast json endpoint for now playing data",
+            )
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "art_url",
+                "An image to show when there is no now playing art",
+            )
+            .required(false),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let option = |name: &str| -> Option<Arc<str>> {
+            options.iter().find_map(|o| match o.value {
+                ResolvedValue::String(s) if o.name == name => Some(s.trim().into()),
+                _ => None,
+            })
+        };
+        let (name, audio_url) = match (option("name"), option("stream_url")) {
+            (Some(name), Some(audio_url)) if !name.is_empty() => (name, audio_url),
+            _ => {
+                respond(ctx, interaction, "You must provide a name and a stream url").await;
+                return Ok(());
+            }
+        };
+        let data_url = option("data_url");
+        let art_url = option("art_url");
+        if let Err(e) = validate_stream(&audio_url).await {
+            respond(
+                ctx,
+                interaction,
+                &format!("Failed to get audio stream url: {}", e),
+            )
+            .await;
+            return Ok(());
+        }
+        if let Some(ref url) = data_url {
+            if let Err(e) = RadioData::get(url.as_ref()).await {
+                respond(
+                    ctx,
+                    interaction,
+                    &format!(
+                        "Failed to get radio data for that url\n{}\n```\n{}```",
+                        "Please ensure that the url is a valid azuracast or icecast json endpoint",
+                        e
+                    ),
+                )
+                .await;
+                return Ok(());
+            }
+        }
+        let preset = RadioPreset {
+            guild_id,
+            name: Arc::clone(&name),
+            audio_url,
+            data_url,
+            art_url,
+        };
+        match preset.save().await {
+            Ok(()) => {
+                respond(
+                    ctx,
+                    interaction,
+                    &format!("Added `{}`, use `/radio switch` to listen to it", name),
+                )
+                .await
+            }
+            Err(e) => {
+                log::error!("Failed to save radio preset: {:?}", e);
+                respond(ctx, interaction, "Failed to save new value").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "preset_add"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config radio_source preset_remove <name> - Remove a station preset
+struct PresetRemove;
+#[async_trait]
+impl SubCommandTrait for PresetRemove {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Remove a radio station preset",
+        )
+        .set_sub_options(vec![CreateCommandOption::new(
+            CommandOptionType::String,
+            "name",
+            "The name of the station",
+        )
+        .required(true)])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let name = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "name" => Some(s),
+            _ => None,
+        }) {
+            Some(n) => n,
+            None => {
+                respond(ctx, interaction, "You must provide a name").await;
+                return Ok(());
+            }
+        };
+        let preset = match RadioPreset::load_opt(guild_id, name).await {
+            Ok(Some(p)) => p,
+            Ok(None) => {
+                respond(
+                    ctx,
+                    interaction,
+                    &format!("There is no station called `{}`", name),
+                )
+                .await;
+                return Ok(());
+            }
+            Err(e) => {
+                log::error!("Failed to load radio preset: {:?}", e);
+                respond(ctx, interaction, "Failed to load that station").await;
+                return Ok(());
+            }
+        };
+        match preset.delete().await {
+            Ok(()) => respond(ctx, interaction, &format!("Removed `{}`", name)).await,
+            Err(e) => {
+                log::error!("Failed to delete radio preset: {:?}", e);
+                respond(ctx, interaction, "Failed to remove that station").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "preset_remove"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
 async fn validate_stream(url: &str) -> Result<()> {
     // use the yt-dlp cli to validate that the url is a valid audio stream
     let output = tokio::process::Command::new("yt-dlp")
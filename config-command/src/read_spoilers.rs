@@ -0,0 +1,86 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::Guild;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Whether TTS reads spoilers out loud instead of just saying \"spoiler\"",
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "new_value",
+            "Read spoilers out loud",
+        ))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let new_value = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("new_value", ResolvedValue::Boolean(b)) => Some(*b),
+            _ => None,
+        });
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let new_value = match new_value {
+            Some(v) => v,
+            None => {
+                respond(
+                    ctx,
+                    interaction,
+                    if config.read_spoilers {
+                        "TTS currently reads spoilers out loud"
+                    } else {
+                        "TTS currently skips spoilers"
+                    },
+                )
+                .await;
+                return Ok(());
+            }
+        };
+        config.read_spoilers = new_value;
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+            return Ok(());
+        }
+        respond(
+            ctx,
+            interaction,
+            if new_value {
+                "TTS will now read spoilers out loud"
+            } else {
+                "TTS will now skip spoilers"
+            },
+        )
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "read_spoilers"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
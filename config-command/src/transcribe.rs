@@ -1,7 +1,9 @@
 use common::anyhow::Result;
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand};
 use common::serenity::all::*;
-use common::{log, SubCommandTrait};
+use common::{log, tokio, SubCommandTrait};
 use long_term_storage::Channel;
+use std::sync::Arc;
 pub struct Command {
     subcommands: Vec<Box<dyn SubCommandTrait>>,
 }
@@ -13,6 +15,8 @@ impl Command {
                 Box::new(Add),
                 Box::new(Remove),
                 Box::new(Clear),
+                Box::new(MutedOnly),
+                Box::new(Captions),
                 Box::new(TalkOverEachother),
             ],
         }
@@ -678,6 +682,306 @@ impl SubCommandTrait for Clear {
     }
 }
 
+struct MutedOnly;
+#[async_trait]
+impl SubCommandTrait for MutedOnly {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Only read messages from people who are muted in a voice channel",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "voice_channel",
+                "The voice channel to change",
+            )
+            .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "value",
+                "Whether to only read messages from muted people",
+            )
+            .required(true),
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let voice_channel = match options.iter().find_map(|o| match o.name {
+            "voice_channel" => Some(&o.value),
+            _ => None,
+        }) {
+            Some(ResolvedValue::Channel(c)) => c,
+            _ => {
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .ephemeral(true)
+                            .content("Invalid voice channel"),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        let value = *match options.iter().find_map(|o| match o.name {
+            "value" => Some(&o.value),
+            _ => None,
+        }) {
+            Some(ResolvedValue::Boolean(b)) => b,
+            _ => {
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .ephemeral(true)
+                            .content("Invalid value"),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        // ensure it's actually a voice channel
+        if voice_channel.kind != ChannelType::Voice {
+            if let Err(e) = interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new()
+                        .ephemeral(true)
+                        .content("Invalid channel, it is not a voice channel"),
+                )
+                .await
+            {
+                log::error!("Failed to send response: {}", e);
+            }
+            return Ok(());
+        }
+        let mut channels = match Channel::load(voice_channel.id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load channel: {:?}", e);
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content("Failed to load channel")
+                            .ephemeral(true),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        channels.muted_only = value;
+        if let Err(e) = channels.save().await {
+            log::error!("Failed to save channel: {:?}", e);
+            if let Err(e) = interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new()
+                        .content("Failed to save channel")
+                        .ephemeral(true),
+                )
+                .await
+            {
+                log::error!("Failed to send response: {}", e);
+            }
+            return Ok(());
+        };
+        tell_running(ctx, voice_channel.id, MetaCommand::ChangeMutedOnly(value)).await;
+        let content = if value {
+            format!(
+                "Messages for {} will only be read for people who are muted in it",
+                voice_channel.id.mention()
+            )
+        } else {
+            format!(
+                "Messages for {} will be read for everyone again",
+                voice_channel.id.mention()
+            )
+        };
+        if let Err(e) = interaction
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .ephemeral(true)
+                    .content(content),
+            )
+            .await
+        {
+            log::error!("Failed to send response: {}", e);
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "muted_only"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS
+    }
+}
+
+// - captions (posts what consenting people say in a voice channel to a text channel) (requires manage channels)
+struct Captions;
+#[async_trait]
+impl SubCommandTrait for Captions {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Post live captions of what people who consented say in a voice channel",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "voice_channel",
+                "The voice channel to caption",
+            )
+            .channel_types(vec![ChannelType::Voice])
+            .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "caption_channel",
+                "The channel to post captions in, leave empty to turn captions off",
+            )
+            .channel_types(vec![ChannelType::Text, ChannelType::Voice]),
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let voice_channel = match options.iter().find_map(|o| match o.name {
+            "voice_channel" => Some(&o.value),
+            _ => None,
+        }) {
+            Some(ResolvedValue::Channel(c)) => c,
+            _ => {
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .ephemeral(true)
+                            .content("Invalid voice channel"),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        let caption_channel = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("caption_channel", ResolvedValue::Channel(c)) => Some(c.id),
+            _ => None,
+        });
+        // ensure it's actually a voice channel
+        if voice_channel.kind != ChannelType::Voice {
+            if let Err(e) = interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new()
+                        .ephemeral(true)
+                        .content("Invalid channel, it is not a voice channel"),
+                )
+                .await
+            {
+                log::error!("Failed to send response: {}", e);
+            }
+            return Ok(());
+        }
+        let mut channels = match Channel::load(voice_channel.id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load channel: {:?}", e);
+                if let Err(e) = interaction
+                    .create_followup(
+                        &ctx.http,
+                        CreateInteractionResponseFollowup::new()
+                            .content("Failed to load channel")
+                            .ephemeral(true),
+                    )
+                    .await
+                {
+                    log::error!("Failed to send response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        channels.caption_channel = caption_channel;
+        if let Err(e) = channels.save().await {
+            log::error!("Failed to save channel: {:?}", e);
+            if let Err(e) = interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new()
+                        .content("Failed to save channel")
+                        .ephemeral(true),
+                )
+                .await
+            {
+                log::error!("Failed to send response: {}", e);
+            }
+            return Ok(());
+        };
+        tell_running(
+            ctx,
+            voice_channel.id,
+            MetaCommand::ChangeCaptionChannel(caption_channel),
+        )
+        .await;
+        let content = match caption_channel {
+            Some(caption_channel) => format!(
+                "What's said in {} will be captioned in {} for everyone who consented to \
+                 having their microphone processed",
+                voice_channel.id.mention(),
+                caption_channel.mention()
+            ),
+            None => format!(
+                "What's said in {} will no longer be captioned",
+                voice_channel.id.mention()
+            ),
+        };
+        if let Err(e) = interaction
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .ephemeral(true)
+                    .content(content),
+            )
+            .await
+        {
+            log::error!("Failed to send response: {}", e);
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "captions"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_CHANNELS
+    }
+}
+
 struct TalkOverEachother;
 #[async_trait]
 impl SubCommandTrait for TalkOverEachother {
@@ -826,4 +1130,33 @@ impl SubCommandTrait for TalkOverEachother {
     fn permissions(&self) -> Permissions {
         Permissions::MANAGE_GUILD
     }
-}
\ No newline at end of file
+}
+// the bot keeps some of a voice channel's settings while it's in there, so it's told about changes
+async fn tell_running(ctx: &Context, voice_channel: ChannelId, command: MetaCommand) {
+    let connection_handler = {
+        let data = ctx.data.read().await;
+        match data.get::<AudioCommandHandler>() {
+            Some(v) => Arc::clone(v),
+            None => {
+                log::error!("Failed to get audio command handler");
+                return;
+            }
+        }
+    };
+    let rx = {
+        let map = connection_handler.read().await;
+        let sender = match map.get(&voice_channel) {
+            Some(s) => s,
+            None => return,
+        };
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        if let Err(e) = sender.send((tx, AudioPromiseCommand::MetaCommand(command))) {
+            log::error!("Failed to send command: {:?}", e);
+            return;
+        }
+        rx
+    };
+    if let Err(e) = rx.await {
+        log::error!("Failed to update channel settings: {:?}", e);
+    }
+}
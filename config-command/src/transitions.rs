@@ -0,0 +1,132 @@
+use common::anyhow::Result;
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand};
+use common::serenity::{
+    all::*,
+    futures::{stream::FuturesUnordered, StreamExt as _},
+};
+use common::utils::respond;
+use common::{log, tokio, SubCommandTrait};
+use long_term_storage::Guild;
+use std::sync::Arc;
+use std::time::Duration;
+// anything longer and short songs would spend most of their time fading
+const MAX_CROSSFADE_SECS: u64 = 12;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "How songs in the queue hand over to each other",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "crossfade",
+                "Seconds to fade between songs, 0 for hard cuts",
+            )
+            .min_int_value(0)
+            .max_int_value(MAX_CROSSFADE_SECS),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "gapless",
+            "Play songs added together from an album or playlist back to back without fading",
+        ))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let mut crossfade = None;
+        let mut gapless = None;
+        for option in options {
+            match (option.name, &option.value) {
+                ("crossfade", ResolvedValue::Integer(i)) => {
+                    crossfade = Some(Duration::from_secs(
+                        (*i).clamp(0, MAX_CROSSFADE_SECS as i64) as u64,
+                    ))
+                }
+                ("gapless", ResolvedValue::Boolean(b)) => gapless = Some(*b),
+                _ => {}
+            }
+        }
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        if crossfade.is_none() && gapless.is_none() {
+            respond(
+                ctx,
+                interaction,
+                &format!("Songs currently change with {}", config.transitions),
+            )
+            .await;
+            return Ok(());
+        }
+        if let Some(crossfade) = crossfade {
+            config.transitions.crossfade = crossfade;
+        }
+        if let Some(gapless) = gapless {
+            config.transitions.gapless = gapless;
+        }
+        let value = config.transitions;
+        respond(ctx, interaction, &format!("Songs will now change with {}", value)).await;
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+        }
+        // every connection in this guild needs to know about the new value
+        let connection_handler = {
+            let data = ctx.data.read().await;
+            match data.get::<AudioCommandHandler>() {
+                Some(v) => Arc::clone(v),
+                None => {
+                    log::error!("Failed to get audio command handler");
+                    return Ok(());
+                }
+            }
+        };
+        tokio::task::spawn(async move {
+            let mut map = connection_handler.write().await;
+            let mut res = FuturesUnordered::new();
+            for sender in map.values_mut() {
+                if sender.guild_id != guild_id {
+                    continue;
+                }
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                let _ = sender.send((
+                    tx,
+                    AudioPromiseCommand::MetaCommand(MetaCommand::ChangeTransitions(value)),
+                ));
+                res.push(rx);
+            }
+            while let Some(r) = res.next().await {
+                if let Err(e) = r {
+                    log::error!("Failed to change transitions: {:?}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "transitions"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
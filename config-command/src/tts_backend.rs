@@ -0,0 +1,99 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::tts::TtsBackendKind;
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::Guild;
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        let mut backend = CreateCommandOption::new(
+            CommandOptionType::String,
+            "backend",
+            "The TTS engine to try first, the others are still used if it fails",
+        )
+        .add_string_choice("Default order", "default");
+        for kind in TtsBackendKind::ALL {
+            backend = backend.add_string_choice(kind.to_string(), kind.name());
+        }
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Which TTS engine reads things out in this server",
+        )
+        .add_sub_option(backend)
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let backend = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("backend", ResolvedValue::String(s)) => Some(*s),
+            _ => None,
+        });
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let backend = match backend {
+            None => {
+                respond(
+                    ctx,
+                    interaction,
+                    &match config.tts_backend {
+                        Some(b) => format!("TTS currently tries {} first", b),
+                        None => "TTS currently uses the default order".to_owned(),
+                    },
+                )
+                .await;
+                return Ok(());
+            }
+            Some("default") => None,
+            Some(s) => match s.parse::<TtsBackendKind>() {
+                Ok(b) => Some(b),
+                Err(e) => {
+                    log::error!("Invalid tts backend: {:?}", e);
+                    respond(ctx, interaction, "Invalid backend").await;
+                    return Ok(());
+                }
+            },
+        };
+        config.tts_backend = backend;
+        // backends are looked up whenever something is spoken, so nothing running needs telling
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+            return Ok(());
+        }
+        respond(
+            ctx,
+            interaction,
+            &match backend {
+                Some(b) => format!("TTS will now try {} first", b),
+                None => "TTS will now use the default order".to_owned(),
+            },
+        )
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "tts_backend"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
@@ -0,0 +1,473 @@
+use common::anyhow::{self, Result};
+use common::serenity::all::*;
+use common::tts::{Dictionary, Pronunciation, TtsBackendKind};
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::{TtsRule, TtsRuleKind};
+// discord caps a message at 2000 characters
+const MAX_LIST_LENGTH: usize = 1900;
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![Box::new(Add), Box::new(Remove), Box::new(List), Box::new(Test)],
+        }
+    }
+}
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommandGroup,
+            self.command_name(),
+            "How TTS says words, acronyms and names in this server",
+        )
+        .set_sub_options(self.subcommands.iter().map(|sc| sc.register_command()))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let member = match interaction.member {
+            Some(ref member) => member,
+            None => {
+                respond(ctx, interaction, "This command can only be run in a guild").await;
+                return Ok(());
+            }
+        };
+        let (subcommand, opts) = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::SubCommand(ref opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                respond(ctx, interaction, "Invalid subcommand").await;
+                return Ok(());
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                if member
+                    .permissions(&ctx.cache)
+                    .map(|p| p.contains(sc.permissions()))
+                    .unwrap_or(false)
+                {
+                    return sc.run(ctx, interaction, opts).await;
+                } else {
+                    respond(ctx, interaction, "You do not have permission to run this command")
+                        .await;
+                    return Ok(());
+                }
+            }
+        }
+        respond(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "tts_dictionary"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty() // checked per subcommand, list and test are open to everyone
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        for option in options {
+            for sc in &self.subcommands {
+                if sc.command_name() == option.name {
+                    match option.value {
+                        ResolvedValue::SubCommand(ref opts) => {
+                            return sc.autocomplete(ctx, interaction, opts).await;
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!("Invalid option type"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+fn backend_option() -> CreateCommandOption {
+    let mut option = CreateCommandOption::new(
+        CommandOptionType::String,
+        "backend",
+        "Only for this TTS engine, since they need different phonetic spellings",
+    );
+    for kind in TtsBackendKind::ALL {
+        option = option.add_string_choice(kind.to_string(), kind.name());
+    }
+    option
+}
+fn backend_value(options: &[ResolvedOption]) -> Option<TtsBackendKind> {
+    options.iter().find_map(|o| match (o.name, &o.value) {
+        ("backend", ResolvedValue::String(s)) => s.parse().ok(),
+        _ => None,
+    })
+}
+// /config tts_dictionary add <say> <optional word> <optional regex> <optional user> <optional backend> - Add a pronunciation rule
+struct Add;
+#[async_trait]
+impl SubCommandTrait for Add {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Say a word, pattern or someone's name differently, pick one of word, regex or user",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::String, "say", "What to say instead")
+                .max_length(200)
+                .required(true),
+            CreateCommandOption::new(CommandOptionType::String, "word", "A word or acronym")
+                .max_length(100),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "regex",
+                "A regular expression, the replacement can use $1 for captures",
+            )
+            .max_length(200),
+            CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "Someone whose names should be said differently",
+            ),
+            backend_option(),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let string = |name: &str| {
+            options.iter().find_map(|o| match o.value {
+                ResolvedValue::String(s) if o.name == name => Some(s.trim().to_owned()),
+                _ => None,
+            })
+        };
+        let user = options.iter().find_map(|o| match o.value {
+            ResolvedValue::User(u, _) if o.name == "user" => Some(u.id),
+            _ => None,
+        });
+        let say = string("say").unwrap_or_default();
+        let backend = backend_value(options);
+        let kind = match (string("word"), string("regex"), user) {
+            (Some(word), None, None) => TtsRuleKind::Word(word),
+            (None, Some(regex), None) => TtsRuleKind::Regex(regex),
+            (None, None, Some(user)) => TtsRuleKind::Nickname(user),
+            _ => {
+                respond(ctx, interaction, "Pick exactly one of word, regex or user").await;
+                return Ok(());
+            }
+        };
+        // catch bad regexes and empty words before they're saved
+        let check = match kind {
+            TtsRuleKind::Word(ref word) => Pronunciation::word(word, &say, backend).map(|_| ()),
+            TtsRuleKind::Regex(ref regex) => Pronunciation::regex(regex, &say, backend).map(|_| ()),
+            TtsRuleKind::Nickname(_) => Ok(()),
+        };
+        if let Err(e) = check {
+            respond(ctx, interaction, &format!("That rule doesn't work: {}", e)).await;
+            return Ok(());
+        }
+        match TtsRule::new(guild_id, kind, say, backend).await {
+            Ok(rule) => respond(ctx, interaction, &format!("Added: {}", rule)).await,
+            Err(e) => {
+                log::error!("Failed to save tts rule: {:?}", e);
+                respond(ctx, interaction, "Failed to save new value").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "add"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config tts_dictionary remove <rule> - Remove a pronunciation rule
+struct Remove;
+#[async_trait]
+impl SubCommandTrait for Remove {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Remove a pronunciation rule",
+        )
+        .set_sub_options(vec![CreateCommandOption::new(
+            CommandOptionType::String,
+            "rule",
+            "The rule to remove",
+        )
+        .set_autocomplete(true)
+        .required(true)])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let id = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "rule" => Some(s),
+            _ => None,
+        }) {
+            Some(id) => id,
+            None => {
+                respond(ctx, interaction, "You must pick a rule").await;
+                return Ok(());
+            }
+        };
+        let rule = match TtsRule::from_id(guild_id, id).await {
+            Ok(Some(rule)) => rule,
+            Ok(None) | Err(_) => {
+                respond(ctx, interaction, "There is no rule like that").await;
+                return Ok(());
+            }
+        };
+        let description = rule.to_string();
+        match rule.delete().await {
+            Ok(()) => respond(ctx, interaction, &format!("Removed: {}", description)).await,
+            Err(e) => {
+                log::error!("Failed to delete tts rule: {:?}", e);
+                respond(ctx, interaction, "Failed to remove that rule").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "remove"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let partial = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } if o.name == "rule" => Some(value),
+            _ => None,
+        }) {
+            Some(p) => p.to_lowercase(),
+            None => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        let mut count = 0;
+        for rule in TtsRule::all(guild_id).await? {
+            let mut name = match rule.kind {
+                TtsRuleKind::Word(ref word) => format!("{} -> {}", word, rule.replacement),
+                TtsRuleKind::Regex(ref regex) => format!("/{}/ -> {}", regex, rule.replacement),
+                TtsRuleKind::Nickname(user_id) => {
+                    let name = ctx
+                        .cache
+                        .user(user_id)
+                        .map(|u| u.name.clone())
+                        .unwrap_or_else(|| user_id.to_string());
+                    format!("@{} -> {}", name, rule.replacement)
+                }
+            };
+            if !name.to_lowercase().contains(&partial) {
+                continue;
+            }
+            if let Some(backend) = rule.backend {
+                name = format!("{} ({})", name, backend.name());
+            }
+            if name.len() > 100 {
+                name = name.chars().take(97).collect::<String>() + "...";
+            }
+            completions = completions.add_string_choice(name, rule.id().to_string());
+            count += 1;
+            // discord only takes 25 choices
+            if count == 25 {
+                break;
+            }
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
+// /config tts_dictionary list - Show every pronunciation rule in this server
+struct List;
+#[async_trait]
+impl SubCommandTrait for List {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Show every pronunciation rule in this server",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let rules = match TtsRule::all(guild_id).await {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("Failed to load tts dictionary: {:?}", e);
+                respond(ctx, interaction, "Failed to load the dictionary").await;
+                return Ok(());
+            }
+        };
+        if rules.is_empty() {
+            respond(
+                ctx,
+                interaction,
+                "This server has no pronunciation rules, add one with `/config tts_dictionary add`",
+            )
+            .await;
+            return Ok(());
+        }
+        let mut list = String::new();
+        for (i, rule) in rules.iter().enumerate() {
+            let line = format!("- {}\n", rule);
+            if list.len() + line.len() > MAX_LIST_LENGTH {
+                list.push_str(&format!("...and {} more", rules.len() - i));
+                break;
+            }
+            list.push_str(&line);
+        }
+        respond(ctx, interaction, &list).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "list"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// /config tts_dictionary test <text> <optional backend> - Hear how some text comes out with this server's rules
+struct Test;
+#[async_trait]
+impl SubCommandTrait for Test {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Hear how some text comes out with this server's rules",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::String, "text", "The text to read")
+                .max_length(500)
+                .required(true),
+            backend_option(),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let text = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "text" => Some(s),
+            _ => None,
+        }) {
+            Some(t) => t,
+            None => {
+                respond(ctx, interaction, "You must provide some text").await;
+                return Ok(());
+            }
+        };
+        let guild = long_term_storage::tts_guild(interaction.guild_id).await;
+        let dictionary =
+            long_term_storage::tts_dictionary(guild.as_ref(), Some(ctx.cache.as_ref())).await;
+        let backends = match backend_value(options) {
+            Some(b) => vec![b],
+            None => long_term_storage::tts_backends(
+                guild.as_ref(),
+                long_term_storage::tts_user(Some(interaction.user.id)).await.as_ref(),
+            ),
+        };
+        let content = rewrite(&dictionary, text, &backends);
+        let mut followup = CreateInteractionResponseFollowup::new()
+            .content(content)
+            .ephemeral(true);
+        match long_term_storage::tts_speak_with(
+            text,
+            None,
+            &backends,
+            &dictionary,
+            guild.as_ref(),
+            Some(interaction.user.id),
+        )
+        .await
+        {
+            Ok(video) => match CreateAttachment::path(&video.path()).await {
+                Ok(file) => followup = followup.add_file(file),
+                Err(e) => log::error!("Failed to attach tts test: {:?}", e),
+            },
+            Err(e) => log::error!("Failed to speak tts test: {:?}", e),
+        }
+        if let Err(e) = interaction.create_followup(&ctx.http, followup).await {
+            log::error!("Failed to send response: {}", e);
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "test"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// what the backend that will most likely read it is actually handed, the one it's spoken with
+// unless that fails. cut short so the followup, and the audio attached to it, still sends
+fn rewrite(dictionary: &Dictionary, text: &str, backends: &[TtsBackendKind]) -> String {
+    let kind = backends.first().copied().unwrap_or(TtsBackendKind::ALL[0]);
+    // a code fence in the text would end the block early
+    let mut rewritten = dictionary.apply(text, kind).replace("```", "`\u{200b}``");
+    if rewritten.chars().count() > MAX_LIST_LENGTH {
+        rewritten = rewritten.chars().take(MAX_LIST_LENGTH).collect();
+        rewritten.push_str("...");
+    }
+    format!("{}:\n```\n{}\n```", kind, rewritten)
+}
@@ -0,0 +1,381 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::{Guild, TtsFilter};
+// discord caps a message at 2000 characters
+const MAX_LIST_LENGTH: usize = 1900;
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![Box::new(User), Box::new(Role), Box::new(Settings), Box::new(List)],
+        }
+    }
+}
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommandGroup,
+            self.command_name(),
+            "Choose whose messages TTS reads out in this server",
+        )
+        .set_sub_options(self.subcommands.iter().map(|sc| sc.register_command()))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let member = match interaction.member {
+            Some(ref member) => member,
+            None => {
+                respond(ctx, interaction, "This command can only be run in a guild").await;
+                return Ok(());
+            }
+        };
+        let (subcommand, opts) = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::SubCommand(ref opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                respond(ctx, interaction, "Invalid subcommand").await;
+                return Ok(());
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                if member
+                    .permissions(&ctx.cache)
+                    .map(|p| p.contains(sc.permissions()))
+                    .unwrap_or(false)
+                {
+                    return sc.run(ctx, interaction, opts).await;
+                } else {
+                    respond(ctx, interaction, "You do not have permission to run this command")
+                        .await;
+                    return Ok(());
+                }
+            }
+        }
+        respond(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "tts_filter"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty() // checked in the subcommands
+    }
+}
+// /config tts_filter user <user> <optional ignore> - Stop or start reading someone's messages
+struct User;
+#[async_trait]
+impl SubCommandTrait for User {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Stop or start reading someone's messages",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::User, "user", "Who to ignore")
+                .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "ignore",
+                "Whether to ignore them, defaults to true",
+            ),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let user = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::User(u, _) if o.name == "user" => Some(u.id),
+            _ => None,
+        }) {
+            Some(u) => u,
+            None => {
+                respond(ctx, interaction, "You must pick a user").await;
+                return Ok(());
+            }
+        };
+        let ignore = ignore_value(options);
+        update(ctx, interaction, |filter| {
+            filter.ignored_users.retain(|u| *u != user);
+            if ignore {
+                filter.ignored_users.push(user);
+                format!("<@{}>'s messages won't be read out anymore", user)
+            } else {
+                format!("<@{}>'s messages will be read out again", user)
+            }
+        })
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "user"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config tts_filter role <role> <optional ignore> - Stop or start reading messages from anyone with a role
+struct Role;
+#[async_trait]
+impl SubCommandTrait for Role {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Stop or start reading messages from anyone with a role",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::Role, "role", "Which role to ignore")
+                .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "ignore",
+                "Whether to ignore it, defaults to true",
+            ),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let role = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Role(r) if o.name == "role" => Some(r.id),
+            _ => None,
+        }) {
+            Some(r) => r,
+            None => {
+                respond(ctx, interaction, "You must pick a role").await;
+                return Ok(());
+            }
+        };
+        let ignore = ignore_value(options);
+        update(ctx, interaction, |filter| {
+            filter.ignored_roles.retain(|r| *r != role);
+            if ignore {
+                filter.ignored_roles.push(role);
+                format!("Messages from <@&{}> won't be read out anymore", role)
+            } else {
+                format!("Messages from <@&{}> will be read out again", role)
+            }
+        })
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "role"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config tts_filter settings <optional ignore_bots> <optional ignore_webhooks> <optional voice_members_only> <optional max_length> - Change which messages get read out
+struct Settings;
+#[async_trait]
+impl SubCommandTrait for Settings {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Change which messages get read out, leave everything empty to see the current ones",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "ignore_bots",
+                "Don't read messages from bots",
+            ),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "ignore_webhooks",
+                "Don't read messages sent by webhooks",
+            ),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "voice_members_only",
+                "Only read messages from people in the voice channel",
+            ),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "max_length",
+                "Cut messages off after this many characters, 0 for no limit",
+            )
+            .min_int_value(0)
+            .max_int_value(2000),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let boolean = |name: &str| {
+            options.iter().find_map(|o| match o.value {
+                ResolvedValue::Boolean(b) if o.name == name => Some(b),
+                _ => None,
+            })
+        };
+        let ignore_bots = boolean("ignore_bots");
+        let ignore_webhooks = boolean("ignore_webhooks");
+        let voice_members_only = boolean("voice_members_only");
+        let max_length = options.iter().find_map(|o| match o.value {
+            ResolvedValue::Integer(i) if o.name == "max_length" => {
+                Some((i > 0).then_some(i as usize))
+            }
+            _ => None,
+        });
+        update(ctx, interaction, |filter| {
+            if let Some(ignore_bots) = ignore_bots {
+                filter.ignore_bots = ignore_bots;
+            }
+            if let Some(ignore_webhooks) = ignore_webhooks {
+                filter.ignore_webhooks = ignore_webhooks;
+            }
+            if let Some(voice_members_only) = voice_members_only {
+                filter.voice_members_only = voice_members_only;
+            }
+            if let Some(max_length) = max_length {
+                filter.max_length = max_length;
+            }
+            describe(filter)
+        })
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "settings"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /config tts_filter list - Show who is ignored and what gets read out
+struct List;
+#[async_trait]
+impl SubCommandTrait for List {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Show who is ignored and what gets read out",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let filter = match Guild::load(guild_id).await {
+            Ok(c) => c.tts_filter,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let mut list = describe(&filter);
+        let ignored = filter
+            .ignored_users
+            .iter()
+            .map(|u| format!("- <@{}>\n", u))
+            .chain(filter.ignored_roles.iter().map(|r| format!("- <@&{}>\n", r)))
+            .collect::<Vec<String>>();
+        if ignored.is_empty() {
+            list.push_str("\nNobody is ignored");
+        } else {
+            list.push_str("\nIgnored:\n");
+            for (i, line) in ignored.iter().enumerate() {
+                if list.len() + line.len() > MAX_LIST_LENGTH {
+                    list.push_str(&format!("...and {} more", ignored.len() - i));
+                    break;
+                }
+                list.push_str(line);
+            }
+        }
+        respond(ctx, interaction, &list).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "list"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+fn ignore_value(options: &[ResolvedOption]) -> bool {
+    options
+        .iter()
+        .find_map(|o| match o.value {
+            ResolvedValue::Boolean(b) if o.name == "ignore" => Some(b),
+            _ => None,
+        })
+        .unwrap_or(true)
+}
+// loads the guild, lets `change` edit its filter and describe what it did, then saves it
+async fn update(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    change: impl FnOnce(&mut TtsFilter) -> String,
+) {
+    let guild_id = match interaction.guild_id {
+        Some(g) => g,
+        None => {
+            respond(ctx, interaction, "This command can only be used in a server").await;
+            return;
+        }
+    };
+    let mut config = match Guild::load(guild_id).await {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Failed to load guild: {:?}", e);
+            respond(ctx, interaction, "Failed to load guild").await;
+            return;
+        }
+    };
+    let message = change(&mut config.tts_filter);
+    // filters are checked for every message, so nothing running needs telling
+    if let Err(e) = config.save().await {
+        log::error!("Failed to save new value: {:?}", e);
+        respond(ctx, interaction, "Failed to save new value").await;
+        return;
+    }
+    respond(ctx, interaction, &message).await;
+}
+fn describe(filter: &TtsFilter) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    format!(
+        "Ignore bots: {}\nIgnore webhooks: {}\nOnly people in the voice channel: {}\nMax length: {}",
+        yes_no(filter.ignore_bots),
+        yes_no(filter.ignore_webhooks),
+        yes_no(filter.voice_members_only),
+        filter
+            .max_length
+            .map(|l| format!("{} characters", l))
+            .unwrap_or_else(|| "none".to_owned()),
+    )
+}
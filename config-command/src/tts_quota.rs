@@ -0,0 +1,103 @@
+use common::anyhow::Result;
+use common::serenity::all::*;
+use common::utils::respond;
+use common::{log, SubCommandTrait};
+use long_term_storage::{Guild, TtsQuota};
+pub struct Command;
+#[async_trait]
+impl SubCommandTrait for Command {
+    fn register_command(&self) -> CreateCommandOption {
+        let limit = |name: &str, description: &str| {
+            CreateCommandOption::new(CommandOptionType::Integer, name, description).min_int_value(0)
+        };
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Limit characters read with paid TTS engines, free ones take over past it",
+        )
+        .add_sub_option(limit("daily", "Characters a day for the whole server, 0 for no limit"))
+        .add_sub_option(limit("monthly", "Characters a month for the whole server, 0 for no limit"))
+        .add_sub_option(limit("per_user_daily", "Characters a day for each user, 0 for no limit"))
+        .add_sub_option(limit(
+            "per_user_monthly",
+            "Characters a month for each user, 0 for no limit",
+        ))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let limit = |name: &str| {
+            options.iter().find_map(|o| match &o.value {
+                ResolvedValue::Integer(i) if o.name == name => Some((*i > 0).then_some(*i as u64)),
+                _ => None,
+            })
+        };
+        let daily = limit("daily");
+        let monthly = limit("monthly");
+        let user_daily = limit("per_user_daily");
+        let user_monthly = limit("per_user_monthly");
+        let mut config = match Guild::load(guild_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                respond(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        if daily.is_none() && monthly.is_none() && user_daily.is_none() && user_monthly.is_none() {
+            respond(
+                ctx,
+                interaction,
+                &format!("The current TTS quota is {}", describe(&config.tts_quota)),
+            )
+            .await;
+            return Ok(());
+        }
+        let quota = TtsQuota {
+            daily: daily.unwrap_or(config.tts_quota.daily),
+            monthly: monthly.unwrap_or(config.tts_quota.monthly),
+            user_daily: user_daily.unwrap_or(config.tts_quota.user_daily),
+            user_monthly: user_monthly.unwrap_or(config.tts_quota.user_monthly),
+        };
+        config.tts_quota = quota;
+        // quotas are checked whenever something is spoken, so nothing running needs telling
+        if let Err(e) = config.save().await {
+            log::error!("Failed to save new value: {:?}", e);
+            respond(ctx, interaction, "Failed to save new value").await;
+            return Ok(());
+        }
+        respond(ctx, interaction, &format!("The TTS quota is now {}", describe(&quota))).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "tts_quota"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+fn describe(quota: &TtsQuota) -> String {
+    if quota.is_unlimited() {
+        return "unlimited".to_owned();
+    }
+    [
+        (quota.daily, "a day"),
+        (quota.monthly, "a month"),
+        (quota.user_daily, "a day for each user"),
+        (quota.user_monthly, "a month for each user"),
+    ]
+    .into_iter()
+    .filter_map(|(limit, what)| limit.map(|l| format!("{} characters {}", l, what)))
+    .collect::<Vec<String>>()
+    .join(", ")
+}
@@ -0,0 +1,23 @@
+-- Add migration script here
+-- text channels that have automatic link embedding turned on
+CREATE TABLE IF NOT EXISTS auto_embed_channels (
+    -- The text channel ID
+    channel_id BIGINT PRIMARY KEY,
+    -- The guild the channel belongs to
+    guild_id BIGINT NOT NULL
+);
+
+-- per guild settings for every domain that can be automatically embedded
+CREATE TABLE IF NOT EXISTS auto_embed_domains (
+    -- Discord guild ID for querying
+    guild_id BIGINT NOT NULL,
+    -- The domain, eg "x.com", subdomains are matched as well
+    domain TEXT NOT NULL,
+    -- Whether links from this domain should be embedded
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Maximum file size in megabytes, NULL means the guild's upload limit
+    max_size_mb INTEGER,
+    -- How many links from this domain will be embedded per channel per minute
+    per_minute INTEGER NOT NULL DEFAULT 5,
+    PRIMARY KEY (guild_id, domain)
+);
@@ -0,0 +1,15 @@
+-- Add migration script here
+-- named radio stations a guild can switch between
+CREATE TABLE IF NOT EXISTS radio_presets (
+    -- Discord guild ID for querying
+    guild_id BIGINT NOT NULL,
+    -- The name of the station, unique per guild
+    name TEXT NOT NULL,
+    -- The audio stream url
+    audio_url TEXT NOT NULL,
+    -- Optional AzuraCast or IceCast json endpoint for now playing data
+    data_url TEXT,
+    -- Optional art to show when the data url has none
+    art_url TEXT,
+    PRIMARY KEY (guild_id, name)
+);
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- add a radio_announcements column to the guilds table, 0 = off, 1 = titles only, 2 = every change
+ALTER TABLE guilds
+    ADD COLUMN IF NOT EXISTS radio_announcements SMALLINT NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- add an idle_playlist column to the guilds table, a directory or playlist inside the idle folder, or a playlist url
+ALTER TABLE guilds
+    ADD COLUMN IF NOT EXISTS idle_playlist TEXT;
@@ -0,0 +1,25 @@
+-- Add migration script here
+
+-- Schedules are audio actions (play something, switch the radio) that run at a set time in a voice channel
+CREATE TABLE IF NOT EXISTS schedules (
+    -- uuid for the schedule
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- discord user id of whoever created it, their timezone is used for repeats
+    user_id BIGINT NOT NULL,
+    -- discord guild id
+    guild_id BIGINT NOT NULL,
+    -- discord voice channel id to play in
+    channel_id BIGINT NOT NULL,
+    -- what to do, 0 = play a url or search, 1 = switch the radio to a preset
+    action SMALLINT NOT NULL,
+    -- the url, search or preset name for the action
+    argument TEXT NOT NULL,
+    -- the next time the schedule runs
+    run_at TIMESTAMP NOT NULL,
+    -- how often it repeats, 0 = never, 1 = daily, 2 = weekly
+    repeat SMALLINT NOT NULL DEFAULT 0,
+    -- created at
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- run attempt count
+    run_attempt_count INT NOT NULL DEFAULT 0
+);
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- add crossfade (milliseconds, 0 = hard cuts) and gapless columns to the guilds table
+ALTER TABLE guilds
+    ADD COLUMN IF NOT EXISTS crossfade INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS gapless BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- add a tts_backend column to the guilds and users tables, the backend name (google, dectalk, sam, local) or null for the default order
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_backend TEXT DEFAULT NULL;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tts_backend TEXT DEFAULT NULL;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- add a voice column to the users table, the google voice name they picked (eg en-GB-Neural2-D) or null to be assigned one
+ALTER TABLE users ADD COLUMN IF NOT EXISTS voice TEXT DEFAULT NULL;
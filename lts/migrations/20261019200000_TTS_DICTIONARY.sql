@@ -0,0 +1,19 @@
+-- Add migration script here
+-- per guild pronunciation rules applied to text before it's spoken
+CREATE TABLE IF NOT EXISTS tts_dictionary (
+    -- uuid for the rule
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- discord guild id
+    guild_id BIGINT NOT NULL,
+    -- what to match, 0 = a plain word, 1 = a regex, 2 = every name a user goes by
+    kind SMALLINT NOT NULL,
+    -- the word, the regex, or the user id for nicknames
+    pattern TEXT NOT NULL,
+    -- what to say instead
+    replacement TEXT NOT NULL,
+    -- only apply for this tts backend (google, dectalk, sam, local), null for all of them
+    backend TEXT DEFAULT NULL,
+    -- created at, rules apply oldest first
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS tts_dictionary_guild_id ON tts_dictionary (guild_id);
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- add a read_spoilers column to the guilds table, whether tts reads spoilers or just says "spoiler"
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS read_spoilers BOOLEAN NOT NULL DEFAULT FALSE;
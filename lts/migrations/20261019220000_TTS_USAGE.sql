@@ -0,0 +1,21 @@
+-- Add migration script here
+-- characters synthesized per guild, user, backend and day, for quotas and /tts usage
+CREATE TABLE IF NOT EXISTS tts_usage (
+    -- discord guild id, 0 outside of a guild
+    guild_id BIGINT NOT NULL,
+    -- who it was said for, 0 when nobody asked for it
+    user_id BIGINT NOT NULL,
+    -- the tts backend that synthesized it (google, dectalk, sam, local)
+    backend TEXT NOT NULL,
+    -- the day it was synthesized
+    day DATE NOT NULL DEFAULT CURRENT_DATE,
+    -- characters synthesized, cache hits aren't counted
+    characters BIGINT NOT NULL DEFAULT 0,
+    -- how many requests those characters came from
+    requests BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (guild_id, user_id, backend, day)
+);
+-- limits on characters synthesized with paid backends, null for no limit
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_daily_quota BIGINT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_monthly_quota BIGINT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_user_daily_quota BIGINT;
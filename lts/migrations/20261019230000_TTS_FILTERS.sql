@@ -0,0 +1,10 @@
+-- Add migration script here
+-- per guild controls over which messages tts reads, and a per user opt out
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_ignored_users BIGINT[] NOT NULL DEFAULT '{}';
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_ignored_roles BIGINT[] NOT NULL DEFAULT '{}';
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_ignore_bots BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_ignore_webhooks BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_voice_members_only BOOLEAN NOT NULL DEFAULT FALSE;
+-- messages longer than this many characters are cut off, null for no limit
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_max_length INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tts_opt_out BOOLEAN NOT NULL DEFAULT FALSE;
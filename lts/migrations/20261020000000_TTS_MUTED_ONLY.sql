@@ -0,0 +1,4 @@
+-- Add migration script here
+-- read messages only from people sitting muted in the voice channel, per voice channel or per user
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS muted_only BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tts_muted_only BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- how song titles and queue events are read out, per guild
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS announcer_voice TEXT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS announcer_backend TEXT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS title_template TEXT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS announce_queue_finished BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS announce_added_by BOOLEAN NOT NULL DEFAULT FALSE;
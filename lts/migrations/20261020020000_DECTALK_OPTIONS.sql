@@ -0,0 +1,5 @@
+-- Add migration script here
+-- per guild dectalk speaker, speaking rate and whether inline [:...] commands are obeyed
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS dectalk_voice TEXT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS dectalk_rate SMALLINT;
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS dectalk_commands BOOLEAN NOT NULL DEFAULT FALSE;
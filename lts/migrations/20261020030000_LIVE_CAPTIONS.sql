@@ -0,0 +1,3 @@
+-- Add migration script here
+-- the text channel transcribed speech in a voice channel is posted to, captions are off when null
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS caption_channel BIGINT;
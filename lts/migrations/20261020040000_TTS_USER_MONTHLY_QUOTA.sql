@@ -0,0 +1,3 @@
+-- Add migration script here
+-- characters each user can have synthesized with paid TTS backends per month, null for no limit
+ALTER TABLE guilds ADD COLUMN IF NOT EXISTS tts_user_monthly_quota BIGINT;
@@ -0,0 +1,33 @@
+use common::tts::{Announcement, TtsBackendKind, DEFAULT_TEMPLATE};
+use common::youtube::TTSVoice;
+
+/// how song titles and queue events are read out in a guild, stored on the guild
+#[derive(Debug, Clone, Default)]
+pub struct Announcer {
+    // read with a random voice when unset
+    pub voice: Option<TTSVoice>,
+    // tried before whatever the requester or the guild picked for messages
+    pub backend: Option<TtsBackendKind>,
+    // the placeholders announcements fill in, `Now playing... {title}` when unset
+    pub title_template: Option<String>,
+    // say so once the last song in the queue ends
+    pub queue_finished: bool,
+    // say who queued a song after its title, unless the template already does
+    pub added_by: bool,
+}
+
+impl Announcer {
+    /// what to read out before a song starts
+    pub fn announce(&self, announcement: &Announcement<'_>) -> String {
+        let template = self.title_template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+        if self.added_by && !template.contains("{requester}") {
+            announcement.render(&format!("{}[, added by {{requester}}]", template))
+        } else {
+            announcement.render(template)
+        }
+    }
+    /// the backends to read announcements with, `preferred` being the ones picked for messages
+    pub fn backends(&self, preferred: Vec<TtsBackendKind>) -> Vec<TtsBackendKind> {
+        self.backend.into_iter().chain(preferred).collect()
+    }
+}
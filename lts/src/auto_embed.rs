@@ -0,0 +1,229 @@
+// CREATE TABLE IF NOT EXISTS auto_embed_channels (
+//     -- The text channel ID
+//     channel_id BIGINT PRIMARY KEY,
+//     -- The guild the channel belongs to
+//     guild_id BIGINT NOT NULL
+// );
+// CREATE TABLE IF NOT EXISTS auto_embed_domains (
+//     guild_id BIGINT NOT NULL,
+//     domain TEXT NOT NULL,
+//     enabled BOOLEAN NOT NULL DEFAULT TRUE,
+//     -- Maximum file size in megabytes, NULL means the guild's upload limit
+//     max_size_mb INTEGER,
+//     per_minute INTEGER NOT NULL DEFAULT 5,
+//     PRIMARY KEY (guild_id, domain)
+// );
+
+use std::{collections::HashSet, sync::Arc};
+
+use common::{
+    anyhow::Result,
+    serenity::all::{ChannelId, GuildId},
+};
+
+// the domains every guild gets settings for, more can be added with /config auto_embed domain
+pub const DEFAULT_DOMAINS: &[&str] = &[
+    "x.com",
+    "twitter.com",
+    "tiktok.com",
+    "instagram.com",
+    "reddit.com",
+];
+const DEFAULT_PER_MINUTE: u32 = 5;
+
+#[derive(Debug, Clone)]
+pub struct AutoEmbed {
+    pub guild_id: GuildId,
+    pub channels: HashSet<ChannelId>,
+    pub domains: Vec<AutoEmbedDomain>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AutoEmbedDomain {
+    pub domain: Arc<str>,
+    pub enabled: bool,
+    pub max_size_mb: Option<u32>,
+    pub per_minute: u32,
+}
+
+impl AutoEmbedDomain {
+    pub fn new(domain: &str) -> Self {
+        Self {
+            domain: domain.to_lowercase().into(),
+            enabled: true,
+            max_size_mb: None,
+            per_minute: DEFAULT_PER_MINUTE,
+        }
+    }
+    /// whether the host is this domain or one of its subdomains
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        host == *self.domain
+            || host
+                .strip_suffix(self.domain.as_ref())
+                .map(|h| h.ends_with('.'))
+                .unwrap_or(false)
+    }
+}
+
+impl AutoEmbed {
+    pub async fn load(guild_id: GuildId) -> Result<Self> {
+        let mut conn = crate::get_connection().await?;
+        get::full(guild_id, &mut conn).await
+    }
+    /// the settings for the guild the channel is in, None if the channel does not have auto embedding turned on
+    pub async fn for_channel(channel_id: ChannelId) -> Result<Option<Self>> {
+        let mut conn = crate::get_connection().await?;
+        match get::guild_for_channel(channel_id, &mut conn).await? {
+            Some(guild_id) => Ok(Some(get::full(guild_id, &mut conn).await?)),
+            None => Ok(None),
+        }
+    }
+    pub async fn save(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::full(self, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    pub fn domain_for(&self, host: &str) -> Option<&AutoEmbedDomain> {
+        self.domains.iter().find(|d| d.matches(host))
+    }
+    pub fn domain_mut(&mut self, domain: &str) -> &mut AutoEmbedDomain {
+        let domain = domain.to_lowercase();
+        let index = match self.domains.iter().position(|d| *d.domain == *domain) {
+            Some(i) => i,
+            None => {
+                self.domains.push(AutoEmbedDomain::new(&domain));
+                self.domains.len() - 1
+            }
+        };
+        &mut self.domains[index]
+    }
+}
+
+struct RawChannel {
+    channel_id: i64,
+}
+
+struct RawDomain {
+    domain: String,
+    enabled: bool,
+    max_size_mb: Option<i32>,
+    per_minute: i32,
+}
+
+impl From<RawDomain> for AutoEmbedDomain {
+    fn from(raw: RawDomain) -> Self {
+        Self {
+            domain: raw.domain.into(),
+            enabled: raw.enabled,
+            max_size_mb: raw.max_size_mb.map(|m| m.max(0) as u32),
+            per_minute: raw.per_minute.max(0) as u32,
+        }
+    }
+}
+
+mod get {
+    use super::{
+        AutoEmbed, AutoEmbedDomain, ChannelId, GuildId, RawChannel, RawDomain, Result,
+        DEFAULT_DOMAINS,
+    };
+
+    struct Guild {
+        guild_id: i64,
+    }
+
+    pub async fn guild_for_channel(
+        channel_id: ChannelId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<GuildId>> {
+        Ok(sqlx::query_as!(
+            Guild,
+            "SELECT guild_id FROM auto_embed_channels WHERE channel_id = $1",
+            channel_id.get() as i64
+        )
+        .fetch_optional(&mut **conn)
+        .await?
+        .map(|g| GuildId::new(g.guild_id as u64)))
+    }
+
+    pub async fn full(
+        guild_id: GuildId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<AutoEmbed> {
+        let channels = sqlx::query_as!(
+            RawChannel,
+            "SELECT channel_id FROM auto_embed_channels WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .map(|c| ChannelId::new(c.channel_id as u64))
+        .collect();
+        let mut domains: Vec<AutoEmbedDomain> = sqlx::query_as!(
+            RawDomain,
+            "SELECT domain, enabled, max_size_mb, per_minute FROM auto_embed_domains WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+        for default in DEFAULT_DOMAINS {
+            if !domains.iter().any(|d| *d.domain == **default) {
+                domains.push(AutoEmbedDomain::new(default));
+            }
+        }
+        Ok(AutoEmbed {
+            guild_id,
+            channels,
+            domains,
+        })
+    }
+}
+
+mod set {
+    use super::{AutoEmbed, Result};
+
+    pub async fn full(
+        auto_embed: AutoEmbed,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        let AutoEmbed {
+            guild_id,
+            channels,
+            domains,
+        } = auto_embed;
+        let guild_id = guild_id.get() as i64;
+        sqlx::query!(
+            "DELETE FROM auto_embed_channels WHERE guild_id = $1",
+            guild_id
+        )
+        .execute(&mut **conn)
+        .await?;
+        for channel in channels {
+            sqlx::query!(
+                "INSERT INTO auto_embed_channels (channel_id, guild_id) VALUES ($1, $2) ON CONFLICT (channel_id) DO UPDATE SET guild_id = $2",
+                channel.get() as i64,
+                guild_id
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        for domain in domains {
+            sqlx::query!(
+                "INSERT INTO auto_embed_domains (guild_id, domain, enabled, max_size_mb, per_minute) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (guild_id, domain) DO UPDATE SET enabled = $3, max_size_mb = $4, per_minute = $5",
+                guild_id,
+                domain.domain.as_ref(),
+                domain.enabled,
+                domain.max_size_mb.map(|m| m as i32),
+                domain.per_minute as i32
+            )
+            .execute(&mut **conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
@@ -2,11 +2,16 @@
 //     -- The voice channel ID
 //     voice_id BIGINT PRIMARY KEY,
 //     -- The text channel IDs
-//     text_ids BIGINT[] NOT NULL DEFAULT '{}'
+//     text_ids BIGINT[] NOT NULL DEFAULT '{}',
+//     -- Only read messages from people in the voice channel who are muted
+//     muted_only BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- Where transcribed speech is posted as live captions, off when null
+//     caption_channel BIGINT
 // );
 
 use std::{
     collections::{HashMap, HashSet},
+    ops::Deref,
     sync::Arc,
 };
 
@@ -15,7 +20,7 @@ use common::{
     lazy_static::lazy_static,
     log,
     serenity::{
-        all::{ChannelId, Message},
+        all::{Cache, ChannelId, Message},
         futures::{stream::FuturesUnordered, StreamExt as _},
     },
     tokio::sync::{broadcast, RwLock},
@@ -24,6 +29,10 @@ use common::{
 pub struct Channel {
     pub voice_id: ChannelId,
     pub text_ids: HashSet<ChannelId>,
+    // read messages only when their author is sitting muted in the voice channel
+    pub muted_only: bool,
+    // where consenting people's transcribed speech is posted, no captions when unset
+    pub caption_channel: Option<ChannelId>,
 }
 
 impl Channel {
@@ -40,6 +49,8 @@ impl Channel {
                     Self {
                         voice_id,
                         text_ids: HashSet::new(),
+                        muted_only: false,
+                        caption_channel: None,
                     },
                     &mut conn,
                 )
@@ -66,6 +77,8 @@ impl Channel {
 struct RawChannel {
     voice_id: i64,
     text_ids: Vec<i64>,
+    muted_only: bool,
+    caption_channel: Option<i64>,
 }
 
 impl From<RawChannel> for Channel {
@@ -77,6 +90,8 @@ impl From<RawChannel> for Channel {
                 .into_iter()
                 .map(|i| ChannelId::new(i as u64))
                 .collect(),
+            muted_only: raw.muted_only,
+            caption_channel: raw.caption_channel.map(|i| ChannelId::new(i as u64)),
         }
     }
 }
@@ -112,7 +127,8 @@ mod get {
     ) -> Result<Option<Channel>> {
         Ok(query_as!(
             RawChannel,
-            "SELECT voice_id, text_ids FROM channels WHERE voice_id = $1",
+            "SELECT voice_id, text_ids, muted_only, caption_channel FROM channels
+             WHERE voice_id = $1",
             voice_id.get() as i64
         )
         .fetch_optional(&mut **conn)
@@ -130,14 +146,18 @@ mod set {
         conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<()> {
         query!(
-            "INSERT INTO channels (voice_id, text_ids) VALUES ($1, $2)
-             ON CONFLICT (voice_id) DO UPDATE SET text_ids = $2",
+            "INSERT INTO channels (voice_id, text_ids, muted_only, caption_channel)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (voice_id) DO UPDATE
+             SET text_ids = $2, muted_only = $3, caption_channel = $4",
             channel.voice_id.get() as i64,
             &channel
                 .text_ids
                 .iter()
                 .map(|id| id.get() as i64)
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>(),
+            channel.muted_only,
+            channel.caption_channel.map(|id| id.get() as i64)
         )
         .execute(&mut **conn)
         .await?;
@@ -154,6 +174,8 @@ pub async fn migrate_data_from_json(
             Channel {
                 voice_id: id,
                 text_ids: channels.into_iter().collect(),
+                muted_only: false,
+                caption_channel: None,
             },
             conn,
         )
@@ -166,21 +188,33 @@ lazy_static! {
     static ref CHANNELS: RwLock<HashMap<ChannelId, Arc<RwLock<MessageBroadcast>>>> =
         RwLock::new(HashMap::new());
 }
+/// a message to read out, along with the guild settings it was filtered with so reading it doesn't
+/// load them again
+#[derive(Debug)]
+pub struct TtsMessage {
+    pub message: Message,
+    pub guild: Option<crate::Guild>,
+}
+impl Deref for TtsMessage {
+    type Target = Message;
+    fn deref(&self) -> &Message {
+        &self.message
+    }
+}
 struct MessageBroadcast {
-    sender: broadcast::Sender<Arc<Message>>,
-    receiver: broadcast::Receiver<Arc<Message>>,
+    sender: broadcast::Sender<Arc<TtsMessage>>,
+    receiver: broadcast::Receiver<Arc<TtsMessage>>,
 }
 impl Default for MessageBroadcast {
     fn default() -> Self {
         // limit the size of the channel to 1mb of memory
         let (sender, receiver) = broadcast::channel(
-            (1024usize * 1024usize).saturating_div(std::mem::size_of::<Arc<Message>>()),
+            (1024usize * 1024usize).saturating_div(std::mem::size_of::<Arc<TtsMessage>>()),
         );
         Self { sender, receiver }
     }
 }
-pub async fn send_message(message: Message) {
-    let message = Arc::new(message);
+pub async fn send_message(message: Message, cache: &Cache) {
     // we want to get the sender for every voice channel that contains the text channel.
     // this is okay being a read lock because we're not going to modify the map, we will just trace for debugging and silently ignore if the channel is not found
     // let mut send_to = Vec::new();
@@ -199,9 +233,27 @@ pub async fn send_message(message: Message) {
             return;
         }
     };
-    let send_to = get::all_ids_that_contain(message.channel_id, &mut conn)
+    let mut send_to = get::all_ids_that_contain(message.channel_id, &mut conn)
         .await
         .unwrap_or_default();
+    if send_to.is_empty() {
+        return;
+    }
+    // loaded and filtered here, once per message, rather than in every call's loop where it'd hold
+    // up audio
+    let guild = crate::tts_guild(message.guild_id).await;
+    if let Some(ref guild) = guild {
+        send_to.retain(
+            |voice_channel| match guild.tts_filter.reject(&message, cache, *voice_channel) {
+                Some(reason) => {
+                    log::trace!("Not reading message {}: {}", message.id, reason);
+                    false
+                }
+                None => true,
+            },
+        );
+    }
+    let message = Arc::new(TtsMessage { message, guild });
     let mut broadcasters = Vec::new();
     {
         let channels = CHANNELS.read().await;
@@ -231,7 +283,7 @@ pub async fn send_message(message: Message) {
         }
     }
 }
-pub async fn get_receiver(channel: ChannelId) -> Result<broadcast::Receiver<Arc<Message>>> {
+pub async fn get_receiver(channel: ChannelId) -> Result<broadcast::Receiver<Arc<TtsMessage>>> {
     // ensure the voice channel exists in the voice channel to text channel map, if not default it to a list only containing the voice channel
     {
         let mut conn = crate::get_connection().await?;
@@ -240,6 +292,8 @@ pub async fn get_receiver(channel: ChannelId) -> Result<broadcast::Receiver<Arc<
                 Channel {
                     voice_id: channel,
                     text_ids: vec![channel].into_iter().collect(),
+                    muted_only: false,
+                    caption_channel: None,
                 },
                 &mut conn,
             )
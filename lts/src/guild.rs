@@ -12,18 +12,68 @@
 //     -- Custom Radio Data URL for the guild
 //     radio_data_url TEXT,
 //     -- Empty channel timeout in milliseconds (between 0 and 600000, enforced by an ON INSERT OR UPDATE trigger), defaults to 30 seconds: 30000
-//     empty_channel_timeout INTEGER NOT NULL DEFAULT 30000
+//     empty_channel_timeout INTEGER NOT NULL DEFAULT 30000,
+//     -- Whether to talk over other TTS messages
+//     talk_over_eachother BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- Radio track change announcements, 0 = off, 1 = titles only, 2 = every change
+//     radio_announcements SMALLINT NOT NULL DEFAULT 0,
+//     -- Idle playlist, a directory or playlist file name inside the idle folder, or a url to a playlist
+//     idle_playlist TEXT,
+//     -- Crossfade between songs in milliseconds, 0 for hard cuts
+//     crossfade INTEGER NOT NULL DEFAULT 0,
+//     -- Whether songs from the same album or playlist play gapless
+//     gapless BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- TTS backend to try first (google, dectalk, sam, local), null for the default order
+//     tts_backend TEXT,
+//     -- Whether TTS reads out spoilers, or just says "spoiler"
+//     read_spoilers BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- Characters that can be synthesized with paid TTS backends per day and per month, null for no limit
+//     tts_daily_quota BIGINT,
+//     tts_monthly_quota BIGINT,
+//     -- Same as tts_daily_quota and tts_monthly_quota but for each user
+//     tts_user_daily_quota BIGINT,
+//     tts_user_monthly_quota BIGINT,
+//     -- Users and roles whose messages are never read out
+//     tts_ignored_users BIGINT[] NOT NULL DEFAULT '{}',
+//     tts_ignored_roles BIGINT[] NOT NULL DEFAULT '{}',
+//     -- Whether messages from bots and webhooks are skipped
+//     tts_ignore_bots BOOLEAN NOT NULL DEFAULT TRUE,
+//     tts_ignore_webhooks BOOLEAN NOT NULL DEFAULT TRUE,
+//     -- Only read messages from people in the voice channel
+//     tts_voice_members_only BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- Messages longer than this many characters are cut off, null for no limit
+//     tts_max_length INTEGER,
+//     -- Google voice and TTS backend song titles are read with, null for a random voice and the usual backends
+//     announcer_voice TEXT,
+//     announcer_backend TEXT,
+//     -- What gets read before a song, with {title}, {artist}, {requester} and {duration} filled in, null for "Now playing... {title}"
+//     title_template TEXT,
+//     -- Whether to say so when the queue runs out, and who added each song
+//     announce_queue_finished BOOLEAN NOT NULL DEFAULT FALSE,
+//     announce_added_by BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- DECtalk speaker (paul, betty, harry, frank, dennis, kit, ursula, rita, wendy), null to match each google voice
+//     dectalk_voice TEXT,
+//     -- DECtalk speaking rate in words per minute (75 to 600), null for its default
+//     dectalk_rate SMALLINT,
+//     -- Whether inline [:...] DECtalk commands in messages are obeyed instead of stripped
+//     dectalk_commands BOOLEAN NOT NULL DEFAULT FALSE
 // );
 
 use std::sync::Arc;
 
 use common::{
     anyhow::{anyhow, Result},
-    serenity::all::GuildId,
+    audio::{RadioAnnouncements, Transitions},
+    log,
+    serenity::all::{GuildId, RoleId, UserId},
     tokio::time::Duration,
+    tts::{DectalkOptions, TtsBackendKind},
+    youtube::TTSVoice,
 };
 
-#[derive(Debug)]
+use crate::{Announcer, TtsFilter, TtsQuota};
+
+#[derive(Debug, Clone)]
 pub struct Guild {
     pub id: GuildId,
     pub default_song_volume: f32,
@@ -33,6 +83,15 @@ pub struct Guild {
     pub radio_data_url: Option<Arc<str>>,
     pub empty_channel_timeout: Duration,
     pub talk_over_eachother: bool,
+    pub radio_announcements: RadioAnnouncements,
+    pub idle_playlist: Option<Arc<str>>,
+    pub transitions: Transitions,
+    pub tts_backend: Option<TtsBackendKind>,
+    pub read_spoilers: bool,
+    pub tts_quota: TtsQuota,
+    pub tts_filter: TtsFilter,
+    pub announcer: Announcer,
+    pub dectalk: DectalkOptions,
 }
 
 impl Guild {
@@ -74,6 +133,30 @@ struct RawGuild {
     radio_data_url: Option<String>,
     empty_channel_timeout: i32,
     talk_over_eachother: bool,
+    radio_announcements: i16,
+    idle_playlist: Option<String>,
+    crossfade: i32,
+    gapless: bool,
+    tts_backend: Option<String>,
+    read_spoilers: bool,
+    tts_daily_quota: Option<i64>,
+    tts_monthly_quota: Option<i64>,
+    tts_user_daily_quota: Option<i64>,
+    tts_ignored_users: Vec<i64>,
+    tts_ignored_roles: Vec<i64>,
+    tts_ignore_bots: bool,
+    tts_ignore_webhooks: bool,
+    tts_voice_members_only: bool,
+    tts_max_length: Option<i32>,
+    announcer_voice: Option<String>,
+    announcer_backend: Option<String>,
+    title_template: Option<String>,
+    announce_queue_finished: bool,
+    announce_added_by: bool,
+    dectalk_voice: Option<String>,
+    dectalk_rate: Option<i16>,
+    dectalk_commands: bool,
+    tts_user_monthly_quota: Option<i64>,
 }
 
 impl From<RawGuild> for Guild {
@@ -87,6 +170,69 @@ impl From<RawGuild> for Guild {
             radio_data_url: raw.radio_data_url.map(Into::into),
             empty_channel_timeout: Duration::from_millis(raw.empty_channel_timeout as u64),
             talk_over_eachother: raw.talk_over_eachother,
+            radio_announcements: match raw.radio_announcements {
+                1 => RadioAnnouncements::TitlesOnly,
+                2 => RadioAnnouncements::EveryChange,
+                _ => RadioAnnouncements::Off,
+            },
+            idle_playlist: raw.idle_playlist.map(Into::into),
+            transitions: Transitions {
+                crossfade: Duration::from_millis(raw.crossfade as u64),
+                gapless: raw.gapless,
+            },
+            tts_backend: raw.tts_backend.and_then(|b| {
+                b.parse()
+                    .inspect_err(|e| log::warn!("Failed to parse tts backend: {}", e))
+                    .ok()
+            }),
+            read_spoilers: raw.read_spoilers,
+            tts_quota: TtsQuota {
+                daily: raw.tts_daily_quota.map(|q| q as u64),
+                monthly: raw.tts_monthly_quota.map(|q| q as u64),
+                user_daily: raw.tts_user_daily_quota.map(|q| q as u64),
+                user_monthly: raw.tts_user_monthly_quota.map(|q| q as u64),
+            },
+            tts_filter: TtsFilter {
+                ignored_users: raw
+                    .tts_ignored_users
+                    .into_iter()
+                    .map(|id| UserId::new(id as u64))
+                    .collect(),
+                ignored_roles: raw
+                    .tts_ignored_roles
+                    .into_iter()
+                    .map(|id| RoleId::new(id as u64))
+                    .collect(),
+                ignore_bots: raw.tts_ignore_bots,
+                ignore_webhooks: raw.tts_ignore_webhooks,
+                voice_members_only: raw.tts_voice_members_only,
+                max_length: raw.tts_max_length.map(|l| l as usize),
+            },
+            announcer: Announcer {
+                voice: raw.announcer_voice.and_then(|v| {
+                    TTSVoice::by_name(&v).or_else(|| {
+                        log::warn!("Unknown announcer voice: {}", v);
+                        None
+                    })
+                }),
+                backend: raw.announcer_backend.and_then(|b| {
+                    b.parse()
+                        .inspect_err(|e| log::warn!("Failed to parse announcer backend: {}", e))
+                        .ok()
+                }),
+                title_template: raw.title_template,
+                queue_finished: raw.announce_queue_finished,
+                added_by: raw.announce_added_by,
+            },
+            dectalk: DectalkOptions {
+                voice: raw.dectalk_voice.and_then(|v| {
+                    v.parse()
+                        .inspect_err(|e| log::warn!("Failed to parse dectalk voice: {}", e))
+                        .ok()
+                }),
+                rate: raw.dectalk_rate.map(|r| r as u16),
+                allow_commands: raw.dectalk_commands,
+            },
         }
     }
 }
@@ -110,7 +256,10 @@ mod get {
 }
 
 mod set {
-    use super::{Guild, GuildId, Result};
+    use super::{
+        Announcer, DectalkOptions, Guild, GuildId, RadioAnnouncements, Result, Transitions,
+        TtsFilter, TtsQuota,
+    };
 
     pub async fn full(
         guild: Guild,
@@ -125,9 +274,40 @@ mod set {
             radio_data_url,
             empty_channel_timeout,
             talk_over_eachother,
+            radio_announcements,
+            idle_playlist,
+            transitions: Transitions { crossfade, gapless },
+            tts_backend,
+            read_spoilers,
+            tts_quota: TtsQuota {
+                daily,
+                monthly,
+                user_daily,
+                user_monthly,
+            },
+            tts_filter: TtsFilter {
+                ignored_users,
+                ignored_roles,
+                ignore_bots,
+                ignore_webhooks,
+                voice_members_only,
+                max_length,
+            },
+            announcer: Announcer {
+                voice,
+                backend,
+                title_template,
+                queue_finished,
+                added_by,
+            },
+            dectalk: DectalkOptions {
+                voice: dectalk_voice,
+                rate: dectalk_rate,
+                allow_commands: dectalk_commands,
+            },
         } = guild;
         sqlx::query!(
-            "INSERT INTO guilds (id, default_volume, radio_volume, read_titles, radio_url, radio_data_url, empty_channel_timeout, talk_over_eachother) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (id) DO UPDATE SET default_volume = $2, radio_volume = $3, read_titles = $4, radio_url = $5, radio_data_url = $6, empty_channel_timeout = $7, talk_over_eachother = $8",
+            "INSERT INTO guilds (id, default_volume, radio_volume, read_titles, radio_url, radio_data_url, empty_channel_timeout, talk_over_eachother, radio_announcements, idle_playlist, crossfade, gapless, tts_backend, read_spoilers, tts_daily_quota, tts_monthly_quota, tts_user_daily_quota, tts_ignored_users, tts_ignored_roles, tts_ignore_bots, tts_ignore_webhooks, tts_voice_members_only, tts_max_length, announcer_voice, announcer_backend, title_template, announce_queue_finished, announce_added_by, dectalk_voice, dectalk_rate, dectalk_commands, tts_user_monthly_quota) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32) ON CONFLICT (id) DO UPDATE SET default_volume = $2, radio_volume = $3, read_titles = $4, radio_url = $5, radio_data_url = $6, empty_channel_timeout = $7, talk_over_eachother = $8, radio_announcements = $9, idle_playlist = $10, crossfade = $11, gapless = $12, tts_backend = $13, read_spoilers = $14, tts_daily_quota = $15, tts_monthly_quota = $16, tts_user_daily_quota = $17, tts_ignored_users = $18, tts_ignored_roles = $19, tts_ignore_bots = $20, tts_ignore_webhooks = $21, tts_voice_members_only = $22, tts_max_length = $23, announcer_voice = $24, announcer_backend = $25, title_template = $26, announce_queue_finished = $27, announce_added_by = $28, dectalk_voice = $29, dectalk_rate = $30, dectalk_commands = $31, tts_user_monthly_quota = $32",
             id.get() as i64,
             default_song_volume,
             default_radio_volume,
@@ -135,7 +315,41 @@ mod set {
             radio_audio_url.map(|s| s.to_string()),
             radio_data_url.map(|s| s.to_string()),
             empty_channel_timeout.as_millis() as i32,
-            talk_over_eachother
+            talk_over_eachother,
+            match radio_announcements {
+                RadioAnnouncements::Off => 0i16,
+                RadioAnnouncements::TitlesOnly => 1,
+                RadioAnnouncements::EveryChange => 2,
+            },
+            idle_playlist.map(|s| s.to_string()),
+            crossfade.as_millis() as i32,
+            gapless,
+            tts_backend.map(|b| b.name()),
+            read_spoilers,
+            daily.map(|q| q as i64),
+            monthly.map(|q| q as i64),
+            user_daily.map(|q| q as i64),
+            &ignored_users
+                .iter()
+                .map(|id| id.get() as i64)
+                .collect::<Vec<_>>(),
+            &ignored_roles
+                .iter()
+                .map(|id| id.get() as i64)
+                .collect::<Vec<_>>(),
+            ignore_bots,
+            ignore_webhooks,
+            voice_members_only,
+            max_length.map(|l| l as i32),
+            voice.map(|v| v.name),
+            backend.map(|b| b.name()),
+            title_template,
+            queue_finished,
+            added_by,
+            dectalk_voice.map(|v| v.name()),
+            dectalk_rate.map(|r| r as i16),
+            dectalk_commands,
+            user_monthly.map(|q| q as i64)
         )
         .execute(&mut **conn)
         .await?;
@@ -1,11 +1,20 @@
 #![feature(once_cell_get_mut)]
 #![allow(static_mut_refs)]
 use common::anyhow::Result;
+use common::log;
+use common::serenity::all::{Cache, GuildId, UserId};
 use common::tokio::sync::OnceCell;
+use common::tts::{Dictionary, Speech, TtsBackendKind};
+use common::video::Video;
+use common::youtube::TTSVoice;
 use sqlx::{PgPool, Postgres, Transaction};
 
+mod announcer;
+pub use announcer::Announcer;
 mod channel;
-pub use channel::{get_receiver as get_tts_receiver, send_message as send_tts_message, Channel};
+pub use channel::{
+    get_receiver as get_tts_receiver, send_message as send_tts_message, Channel, TtsMessage,
+};
 mod guild;
 pub use guild::Guild;
 mod user;
@@ -13,6 +22,19 @@ pub use user::User;
 pub use user::VoicePreference;
 mod reminder;
 pub use reminder::Reminder;
+mod auto_embed;
+pub use auto_embed::{AutoEmbed, AutoEmbedDomain, DEFAULT_DOMAINS as DEFAULT_AUTO_EMBED_DOMAINS};
+mod radio_preset;
+pub use radio_preset::RadioPreset;
+mod schedule;
+pub use schedule::{Schedule, ScheduleAction, ScheduleRepeat};
+mod tts_dictionary;
+pub use tts_dictionary::{TtsRule, TtsRuleKind};
+mod tts_filter;
+pub use tts_filter::TtsFilter;
+mod tts_usage;
+use tts_usage::QuotaMeter;
+pub use tts_usage::{BackendUsage, TtsQuota, TtsUsage, UserUsage};
 // This crate is for LTS (Long Term Storage) of data for the Neon Circle Discord bot.
 // Uses PostgreSQL as the database.
 //
@@ -21,6 +43,10 @@ pub use reminder::Reminder;
 //  a boolean to consent (or not) to process their microphone data.
 //  a voice preference, which is either male or female and tells the bot which gender to use for TTS.
 //  a timezone, which is a string that can be parsed by chrono to get the timezone.
+//  the tts backend they'd rather be read with, if any
+//  the exact tts voice they picked, if any, which wins over the voice preference
+//  whether they've opted out of having their messages read
+//  whether their messages should only be read while they're muted in voice
 //
 // guild will store
 //  the id for querying
@@ -30,8 +56,37 @@ pub use reminder::Reminder;
 //  the radio audio url
 //  the radio data url
 //  the empty channel timeout (a duration between 0 and 600 seconds)
+//  whether to announce radio track changes (off, titles only, or every change)
+//  the idle playlist to shuffle through when there is no custom radio
+//  the crossfade between songs and whether albums play gapless
+//  the tts backend to read messages and titles with, if any
+//  whether tts reads spoilers out loud
+//  daily and monthly limits on characters synthesized with paid tts backends, for the guild and each user
+//  which messages tts skips (ignored users and roles, bots, webhooks, people outside the voice channel) and a length limit
+//  the dectalk voice and speaking rate, and whether people can use inline dectalk commands
+//  the voice, backend and template song titles are announced with, and whether the end of the queue and who added a song are announced
+//
+// auto embed will store
+//  the text channels links should automatically be embedded in
+//  per domain settings (enabled, a size cap in megabytes and a per minute rate limit)
+//
+// radio presets will store
+//  named stations per guild, each with an audio url and an optional data url and art url
+//
+// schedules will store
+//  audio actions (play a url or search, switch the radio to a preset) for a voice channel
+//  the next time they run, and whether they repeat daily or weekly in the creator's timezone
+//
+// tts dictionary will store
+//  per guild rules for saying a word, a regex match or a user's names differently
+//  and optionally the one tts backend a rule is for, since phonetic spellings differ between them
+//
+// tts usage will store
+//  characters synthesized per guild, user, backend and day, to enforce quotas and show what tts costs
 //
 // channel will be a map from a voice channel id to a text channel id, and usually be queried in reverse, getting a list of voice channels from a text channel id.
+//  it also stores whether the voice channel only has messages read from people who are muted in it.
+//  and the text channel live captions of what's said in it are posted to, if any.
 
 static POOL: OnceCell<PgPool> = OnceCell::const_new();
 
@@ -51,6 +106,130 @@ pub async fn init() {
     user::init().await;
 }
 
+/// a guild's settings for the tts helpers below, None outside of guilds or if it can't be loaded
+pub async fn tts_guild(guild_id: Option<GuildId>) -> Option<Guild> {
+    match Guild::load_opt(guild_id?).await {
+        Ok(guild) => guild,
+        Err(e) => {
+            log::error!("Failed to load guild tts settings: {:?}", e);
+            None
+        }
+    }
+}
+
+/// a user's tts settings, None if they can't be loaded
+pub async fn tts_user(user_id: Option<UserId>) -> Option<User> {
+    match User::load_opt(user_id?).await {
+        Ok(user) => user,
+        Err(e) => {
+            log::error!("Failed to load user tts settings: {:?}", e);
+            None
+        }
+    }
+}
+
+/// the tts backends to try first for someone in a guild, the user's pick wins over the guild's
+pub fn tts_backends(guild: Option<&Guild>, user: Option<&User>) -> Vec<TtsBackendKind> {
+    user.and_then(|u| u.tts_backend)
+        .into_iter()
+        .chain(guild.and_then(|g| g.tts_backend))
+        .collect()
+}
+
+/// how a guild announces songs, the default announcer outside of guilds or if it can't be loaded
+pub async fn announcer(guild_id: Option<GuildId>) -> Announcer {
+    let guild_id = match guild_id {
+        Some(g) => g,
+        None => return Announcer::default(),
+    };
+    match Guild::load_opt(guild_id).await {
+        Ok(guild) => guild.map(|g| g.announcer).unwrap_or_default(),
+        Err(e) => {
+            log::error!("Failed to load guild announcer: {:?}", e);
+            Announcer::default()
+        }
+    }
+}
+
+/// a guild's pronunciation rules, nicknames match every name the cache knows the user by
+pub async fn tts_dictionary(guild: Option<&Guild>, cache: Option<&Cache>) -> Dictionary {
+    let guild = match guild {
+        Some(g) => g,
+        None => return Dictionary::default(),
+    };
+    let guild_id = guild.id;
+    let rules = match TtsRule::all(guild_id).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::error!("Failed to load tts dictionary: {:?}", e);
+            return Dictionary::default();
+        }
+    };
+    let mut pronunciations = Vec::new();
+    for rule in rules {
+        let names = match (&rule.kind, cache) {
+            (TtsRuleKind::Nickname(user_id), Some(cache)) => {
+                let mut names = Vec::new();
+                if let Some(member) = cache.member(guild_id, *user_id) {
+                    names.extend(member.nick.clone());
+                    names.extend(member.user.global_name.clone());
+                    names.push(member.user.name.clone());
+                } else if let Some(user) = cache.user(*user_id) {
+                    names.extend(user.global_name.clone());
+                    names.push(user.name.clone());
+                }
+                names
+            }
+            _ => Vec::new(),
+        };
+        match rule.pronunciations(&names) {
+            Ok(p) => pronunciations.extend(p),
+            Err(e) => log::warn!("Skipping broken tts dictionary rule {}: {:?}", rule.id(), e),
+        }
+    }
+    Dictionary::new(pronunciations)
+}
+
+/// [`common::tts::speak_with`] with the guild's dectalk options, keeping to its tts quota and
+/// counting what gets synthesized
+pub async fn tts_speak_with(
+    text: &str,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+    dictionary: &Dictionary,
+    guild: Option<&Guild>,
+    user_id: Option<UserId>,
+) -> Result<Video> {
+    tts_speak_rendered(&Speech::text(text), voice, preferred, dictionary, guild, user_id).await
+}
+
+/// [`tts_speak_with`] for rendered speech, paid backends are skipped once a quota runs out
+pub async fn tts_speak_rendered(
+    speech: &Speech,
+    voice: Option<TTSVoice>,
+    preferred: &[TtsBackendKind],
+    dictionary: &Dictionary,
+    guild: Option<&Guild>,
+    user_id: Option<UserId>,
+) -> Result<Video> {
+    let guild_id = guild.map(|g| g.id);
+    let meter = QuotaMeter {
+        guild_id,
+        user_id,
+        quota: guild.map(|g| g.tts_quota).unwrap_or_default(),
+    };
+    let dectalk = guild.map(|g| g.dectalk).unwrap_or_default();
+    let (video, usage) =
+        common::tts::speak_metered(speech, voice, preferred, dictionary, dectalk, &meter).await?;
+    // paid backends were counted when they were reserved
+    if let Some(usage) = usage.filter(|u| !u.backend.is_paid()) {
+        if let Err(e) = TtsUsage::record(guild_id, user_id, usage).await {
+            log::error!("Failed to record tts usage: {:?}", e);
+        }
+    }
+    Ok(video)
+}
+
 pub async fn migrate_data_from_json() -> Result<()> {
     let mut conn = get_connection().await?;
     // user::migrate_data_from_json(&mut conn).await?;
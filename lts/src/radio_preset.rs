@@ -0,0 +1,158 @@
+// CREATE TABLE IF NOT EXISTS radio_presets (
+//     -- Discord guild ID for querying
+//     guild_id BIGINT NOT NULL,
+//     -- The name of the station, unique per guild
+//     name TEXT NOT NULL,
+//     -- The audio stream url
+//     audio_url TEXT NOT NULL,
+//     -- Optional AzuraCast or IceCast json endpoint for now playing data
+//     data_url TEXT,
+//     -- Optional art to show when the data url has none
+//     art_url TEXT,
+//     PRIMARY KEY (guild_id, name)
+// );
+
+use std::sync::Arc;
+
+use common::{anyhow::Result, audio::RadioStation, serenity::all::GuildId};
+
+#[derive(Debug, Clone)]
+pub struct RadioPreset {
+    pub guild_id: GuildId,
+    pub name: Arc<str>,
+    pub audio_url: Arc<str>,
+    pub data_url: Option<Arc<str>>,
+    pub art_url: Option<Arc<str>>,
+}
+
+impl RadioPreset {
+    /// the control panel's station picker sends `station:{name}` back, and discord caps select
+    /// menu values at 100 characters
+    pub const MAX_NAME_LENGTH: usize = 100 - "station:".len();
+    pub async fn load_opt(guild_id: GuildId, name: &str) -> Result<Option<Self>> {
+        let mut conn = crate::get_connection().await?;
+        get::full(guild_id, name, &mut conn).await
+    }
+    pub async fn all(guild_id: GuildId) -> Result<Vec<Self>> {
+        let mut conn = crate::get_connection().await?;
+        get::all(guild_id, &mut conn).await
+    }
+    pub async fn save(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::full(self, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    pub async fn delete(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::delete(self, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    pub fn station(&self) -> RadioStation {
+        RadioStation {
+            name: Arc::clone(&self.name),
+            audio_url: Arc::clone(&self.audio_url),
+            data_url: self.data_url.as_ref().map(Arc::clone),
+            art_url: self.art_url.as_ref().map(Arc::clone),
+        }
+    }
+}
+
+struct RawRadioPreset {
+    guild_id: i64,
+    name: String,
+    audio_url: String,
+    data_url: Option<String>,
+    art_url: Option<String>,
+}
+
+impl From<RawRadioPreset> for RadioPreset {
+    fn from(raw: RawRadioPreset) -> Self {
+        Self {
+            guild_id: GuildId::new(raw.guild_id as u64),
+            name: raw.name.into(),
+            audio_url: raw.audio_url.into(),
+            data_url: raw.data_url.map(Into::into),
+            art_url: raw.art_url.map(Into::into),
+        }
+    }
+}
+
+mod get {
+    use super::{GuildId, RadioPreset, RawRadioPreset, Result};
+
+    pub async fn full(
+        guild_id: GuildId,
+        name: &str,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<RadioPreset>> {
+        Ok(sqlx::query_as!(
+            RawRadioPreset,
+            "SELECT * FROM radio_presets WHERE guild_id = $1 AND name = $2",
+            guild_id.get() as i64,
+            name
+        )
+        .fetch_optional(&mut **conn)
+        .await?
+        .map(Into::into))
+    }
+
+    pub async fn all(
+        guild_id: GuildId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<RadioPreset>> {
+        Ok(sqlx::query_as!(
+            RawRadioPreset,
+            "SELECT * FROM radio_presets WHERE guild_id = $1 ORDER BY name",
+            guild_id.get() as i64
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+}
+
+mod set {
+    use super::{RadioPreset, Result};
+
+    pub async fn full(
+        preset: RadioPreset,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        let RadioPreset {
+            guild_id,
+            name,
+            audio_url,
+            data_url,
+            art_url,
+        } = preset;
+        sqlx::query!(
+            "INSERT INTO radio_presets (guild_id, name, audio_url, data_url, art_url) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (guild_id, name) DO UPDATE SET audio_url = $3, data_url = $4, art_url = $5",
+            guild_id.get() as i64,
+            name.as_ref(),
+            audio_url.as_ref(),
+            data_url.map(|s| s.to_string()),
+            art_url.map(|s| s.to_string())
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(
+        preset: RadioPreset,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM radio_presets WHERE guild_id = $1 AND name = $2",
+            preset.guild_id.get() as i64,
+            preset.name.as_ref()
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+}
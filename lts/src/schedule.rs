@@ -0,0 +1,389 @@
+// CREATE TABLE IF NOT EXISTS schedules (
+//     -- uuid for the schedule
+//     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+//     -- discord user id of whoever created it, their timezone is used for repeats
+//     user_id BIGINT NOT NULL,
+//     -- discord guild id
+//     guild_id BIGINT NOT NULL,
+//     -- discord voice channel id to play in
+//     channel_id BIGINT NOT NULL,
+//     -- what to do, 0 = play a url or search, 1 = switch the radio to a preset
+//     action SMALLINT NOT NULL,
+//     -- the url, search or preset name for the action
+//     argument TEXT NOT NULL,
+//     -- the next time the schedule runs
+//     run_at TIMESTAMP NOT NULL,
+//     -- how often it repeats, 0 = never, 1 = daily, 2 = weekly
+//     repeat SMALLINT NOT NULL DEFAULT 0,
+//     -- created at
+//     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+//     -- run attempt count
+//     run_attempt_count INT NOT NULL DEFAULT 0
+// );
+
+use common::{
+    anyhow::{anyhow, Result},
+    chrono::{self, TimeZone as _},
+    chrono_tz::Tz,
+    serenity::all::{ChannelId, GuildId, UserId},
+};
+use sqlx::types::Uuid;
+
+const MAX_FAILED_RUN_ATTEMPTS: i32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleAction {
+    // a url or search, same as /add
+    Play(String),
+    // the name of a radio preset
+    SwitchRadio(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleRepeat {
+    Never,
+    Daily,
+    Weekly,
+}
+
+impl std::fmt::Display for ScheduleRepeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Never => write!(f, "once"),
+            Self::Daily => write!(f, "every day"),
+            Self::Weekly => write!(f, "every week"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Schedule {
+    id: Uuid,
+    pub user_id: UserId,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub action: ScheduleAction,
+    pub run_at: chrono::DateTime<Tz>,
+    pub repeat: ScheduleRepeat,
+    pub created_at: chrono::DateTime<Tz>,
+    run_attempt_count: i32,
+}
+
+impl Schedule {
+    pub async fn new(
+        user_id: UserId,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        action: ScheduleAction,
+        run_at: chrono::DateTime<Tz>,
+        repeat: ScheduleRepeat,
+    ) -> Result<Self> {
+        let mut conn = crate::get_connection().await?;
+        let raw = set::new(user_id, guild_id, channel_id, &action, run_at, repeat, &mut conn).await?;
+        let user = crate::User::load(user_id).await?;
+        let schedule = Schedule::from_raw(raw, &user.timezone);
+        conn.commit().await?;
+        Ok(schedule)
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub async fn from_id(raw_uuid: &str) -> Result<Self> {
+        let uuid = Uuid::parse_str(raw_uuid)?;
+        let mut conn = crate::get_connection().await?;
+        match get::specific(uuid, &mut conn).await? {
+            Some(schedule) => Ok(schedule),
+            None => Err(anyhow!("Schedule not found")),
+        }
+    }
+    pub async fn all_for_guild(guild_id: GuildId) -> Result<Vec<Self>> {
+        let mut conn = crate::get_connection().await?;
+        get::all_for_guild(guild_id, &mut conn).await
+    }
+    pub async fn all_schedules(before: chrono::DateTime<chrono::Utc>) -> Result<Vec<Self>> {
+        let mut conn = crate::get_connection().await?;
+        get::all_before(before, &mut conn).await
+    }
+    /// moves a repeating schedule on to its next run, or deletes a one off schedule
+    pub async fn finished(mut self) -> Result<()> {
+        match self.next_run() {
+            Some(next) => {
+                self.run_at = next;
+                self.run_attempt_count = 0;
+                self.save().await
+            }
+            None => self.delete().await,
+        }
+    }
+    pub async fn failed(mut self) -> Result<()> {
+        self.run_attempt_count += 1;
+        if self.run_attempt_count >= MAX_FAILED_RUN_ATTEMPTS {
+            self.finished().await
+        } else {
+            self.save().await
+        }
+    }
+    pub async fn save(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::full(&self, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    pub async fn delete(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::delete(self.id, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    // steps in local time so "every friday at 20:00" stays at 20:00 across daylight savings changes
+    fn next_run(&self) -> Option<chrono::DateTime<Tz>> {
+        let step = match self.repeat {
+            ScheduleRepeat::Never => return None,
+            ScheduleRepeat::Daily => chrono::Duration::days(1),
+            ScheduleRepeat::Weekly => chrono::Duration::weeks(1),
+        };
+        let timezone = self.run_at.timezone();
+        let now = chrono::Utc::now();
+        let mut local = self.run_at.naive_local();
+        loop {
+            local += step;
+            // the local time might not exist on this day (skipped by daylight savings), so take the next hour
+            let next = timezone
+                .from_local_datetime(&local)
+                .earliest()
+                .or_else(|| {
+                    timezone
+                        .from_local_datetime(&(local + chrono::Duration::hours(1)))
+                        .earliest()
+                })?;
+            if next > now {
+                return Some(next);
+            }
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RawSchedule {
+    id: Uuid,
+    user_id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    action: i16,
+    argument: String,
+    run_at: chrono::NaiveDateTime,
+    repeat: i16,
+    created_at: chrono::NaiveDateTime,
+    run_attempt_count: i32,
+}
+
+impl Schedule {
+    fn from_raw(raw: RawSchedule, timezone: &Tz) -> Self {
+        Self {
+            id: raw.id,
+            user_id: UserId::new(raw.user_id as u64),
+            guild_id: GuildId::new(raw.guild_id as u64),
+            channel_id: ChannelId::new(raw.channel_id as u64),
+            action: match raw.action {
+                1 => ScheduleAction::SwitchRadio(raw.argument),
+                _ => ScheduleAction::Play(raw.argument),
+            },
+            run_at: raw.run_at.and_utc().with_timezone(timezone),
+            repeat: match raw.repeat {
+                1 => ScheduleRepeat::Daily,
+                2 => ScheduleRepeat::Weekly,
+                _ => ScheduleRepeat::Never,
+            },
+            created_at: raw.created_at.and_utc().with_timezone(timezone),
+            run_attempt_count: raw.run_attempt_count,
+        }
+    }
+}
+
+fn raw_action(action: &ScheduleAction) -> (i16, &str) {
+    match action {
+        ScheduleAction::Play(query) => (0, query.as_str()),
+        ScheduleAction::SwitchRadio(name) => (1, name.as_str()),
+    }
+}
+
+fn raw_repeat(repeat: ScheduleRepeat) -> i16 {
+    match repeat {
+        ScheduleRepeat::Never => 0,
+        ScheduleRepeat::Daily => 1,
+        ScheduleRepeat::Weekly => 2,
+    }
+}
+
+mod get {
+    use super::{chrono, GuildId, RawSchedule, Result, Schedule, Tz, UserId, Uuid};
+
+    // every schedule is shown in its creator's timezone
+    async fn with_timezones(raw_schedules: Vec<RawSchedule>) -> Result<Vec<Schedule>> {
+        let mut user_map: std::collections::HashMap<UserId, Tz> = std::collections::HashMap::new();
+        let mut schedules = Vec::new();
+        for raw in raw_schedules.into_iter() {
+            let user_id = UserId::new(raw.user_id as u64);
+            let tz = match user_map.entry(user_id) {
+                std::collections::hash_map::Entry::Occupied(entry) => *entry.get(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    let user = crate::User::load(user_id).await?;
+                    *entry.insert(user.timezone)
+                }
+            };
+            schedules.push(Schedule::from_raw(raw, &tz));
+        }
+        schedules.sort_by_key(|s| s.run_at);
+        Ok(schedules)
+    }
+
+    pub async fn all_for_guild(
+        guild_id: GuildId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Schedule>> {
+        let raw_schedules = sqlx::query_as!(
+            RawSchedule,
+            "SELECT * FROM schedules WHERE guild_id = $1 ORDER BY run_at ASC",
+            guild_id.get() as i64,
+        )
+        .fetch_all(&mut **conn)
+        .await?;
+        with_timezones(raw_schedules).await
+    }
+
+    pub async fn all_before(
+        before: chrono::DateTime<chrono::Utc>,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Schedule>> {
+        let raw_schedules = sqlx::query_as!(
+            RawSchedule,
+            "SELECT * FROM schedules WHERE run_at < $1",
+            before.naive_utc(),
+        )
+        .fetch_all(&mut **conn)
+        .await?;
+        with_timezones(raw_schedules).await
+    }
+
+    pub async fn specific(
+        id: Uuid,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<Schedule>> {
+        match sqlx::query_as!(RawSchedule, "SELECT * FROM schedules WHERE id = $1", id)
+            .fetch_optional(&mut **conn)
+            .await?
+        {
+            Some(raw) => {
+                let user = crate::User::load(UserId::new(raw.user_id as u64)).await?;
+                Ok(Some(Schedule::from_raw(raw, &user.timezone)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+mod set {
+    use super::{
+        chrono, raw_action, raw_repeat, ChannelId, GuildId, RawSchedule, Result, Schedule,
+        ScheduleAction, ScheduleRepeat, Tz, UserId, Uuid,
+    };
+
+    pub async fn full(
+        schedule: &Schedule,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        let Schedule {
+            id,
+            user_id,
+            guild_id,
+            channel_id,
+            action,
+            run_at,
+            repeat,
+            created_at,
+            run_attempt_count,
+        } = schedule;
+        let (action, argument) = raw_action(action);
+        sqlx::query!(
+            "INSERT INTO schedules \
+            (\
+                id, \
+                user_id, \
+                guild_id, \
+                channel_id, \
+                action, \
+                argument, \
+                run_at, \
+                repeat, \
+                created_at, \
+                run_attempt_count \
+            ) VALUES ( \
+                $1, \
+                $2, \
+                $3, \
+                $4, \
+                $5, \
+                $6, \
+                $7, \
+                $8, \
+                $9, \
+                $10 \
+            ) ON CONFLICT (id) DO \
+            UPDATE SET \
+                user_id = $2, \
+                guild_id = $3, \
+                channel_id = $4, \
+                action = $5, \
+                argument = $6, \
+                run_at = $7, \
+                repeat = $8, \
+                created_at = $9, \
+                run_attempt_count = $10",
+            id,
+            user_id.get() as i64,
+            guild_id.get() as i64,
+            channel_id.get() as i64,
+            action,
+            argument,
+            run_at.naive_utc(),
+            raw_repeat(*repeat),
+            created_at.naive_utc(),
+            run_attempt_count,
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn new(
+        user_id: UserId,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        action: &ScheduleAction,
+        run_at: chrono::DateTime<Tz>,
+        repeat: ScheduleRepeat,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<RawSchedule> {
+        let (action, argument) = raw_action(action);
+        Ok(sqlx::query_as!(
+            RawSchedule,
+            "INSERT INTO schedules (user_id, guild_id, channel_id, action, argument, run_at, repeat) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *",
+            user_id.get() as i64,
+            guild_id.get() as i64,
+            channel_id.get() as i64,
+            action,
+            argument,
+            run_at.to_utc().naive_utc(),
+            raw_repeat(repeat)
+        )
+        .fetch_one(&mut **conn)
+        .await?)
+    }
+
+    pub async fn delete(id: Uuid, conn: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!("DELETE FROM schedules WHERE id = $1", id)
+            .execute(&mut **conn)
+            .await?;
+        Ok(())
+    }
+}
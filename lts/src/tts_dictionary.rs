@@ -0,0 +1,219 @@
+// CREATE TABLE IF NOT EXISTS tts_dictionary (
+//     -- uuid for the rule
+//     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+//     -- discord guild id
+//     guild_id BIGINT NOT NULL,
+//     -- what to match, 0 = a plain word, 1 = a regex, 2 = every name a user goes by
+//     kind SMALLINT NOT NULL,
+//     -- the word, the regex, or the user id for nicknames
+//     pattern TEXT NOT NULL,
+//     -- what to say instead
+//     replacement TEXT NOT NULL,
+//     -- only apply for this tts backend (google, dectalk, sam, local), null for all of them
+//     backend TEXT DEFAULT NULL,
+//     -- created at, rules apply oldest first
+//     created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+// );
+
+use common::{
+    anyhow::Result,
+    log,
+    serenity::all::{GuildId, UserId},
+    tts::{Pronunciation, TtsBackendKind},
+};
+use sqlx::types::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TtsRuleKind {
+    // matched on its own, ignoring case
+    Word(String),
+    Regex(String),
+    // whatever the user is called at the time
+    Nickname(UserId),
+}
+
+#[derive(Debug, Clone)]
+pub struct TtsRule {
+    id: Uuid,
+    pub guild_id: GuildId,
+    pub kind: TtsRuleKind,
+    pub replacement: String,
+    pub backend: Option<TtsBackendKind>,
+}
+
+impl TtsRule {
+    pub async fn new(
+        guild_id: GuildId,
+        kind: TtsRuleKind,
+        replacement: String,
+        backend: Option<TtsBackendKind>,
+    ) -> Result<Self> {
+        let mut conn = crate::get_connection().await?;
+        let rule = set::new(guild_id, &kind, &replacement, backend, &mut conn).await?;
+        conn.commit().await?;
+        Ok(rule.into())
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub async fn all(guild_id: GuildId) -> Result<Vec<Self>> {
+        let mut conn = crate::get_connection().await?;
+        get::all(guild_id, &mut conn).await
+    }
+    pub async fn from_id(guild_id: GuildId, raw_uuid: &str) -> Result<Option<Self>> {
+        let uuid = Uuid::parse_str(raw_uuid)?;
+        let mut conn = crate::get_connection().await?;
+        get::specific(guild_id, uuid, &mut conn).await
+    }
+    pub async fn delete(self) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::delete(self.id, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    /// compiles the rule, nicknames get one pronunciation for each of the names passed in
+    pub fn pronunciations(&self, names: &[String]) -> Result<Vec<Pronunciation>> {
+        match self.kind {
+            TtsRuleKind::Word(ref word) => Ok(vec![Pronunciation::word(
+                word,
+                &self.replacement,
+                self.backend,
+            )?]),
+            TtsRuleKind::Regex(ref regex) => Ok(vec![Pronunciation::regex(
+                regex,
+                &self.replacement,
+                self.backend,
+            )?]),
+            TtsRuleKind::Nickname(_) => names
+                .iter()
+                .map(|name| Pronunciation::word(name, &self.replacement, self.backend))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for TtsRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            TtsRuleKind::Word(ref word) => write!(f, "`{}`", word)?,
+            TtsRuleKind::Regex(ref regex) => write!(f, "regex `{}`", regex)?,
+            TtsRuleKind::Nickname(user_id) => write!(f, "<@{}>", user_id)?,
+        }
+        write!(f, " is said as `{}`", self.replacement)?;
+        if let Some(backend) = self.backend {
+            write!(f, " with {} only", backend)?;
+        }
+        Ok(())
+    }
+}
+
+struct RawTtsRule {
+    id: Uuid,
+    guild_id: i64,
+    kind: i16,
+    pattern: String,
+    replacement: String,
+    backend: Option<String>,
+}
+
+impl From<RawTtsRule> for TtsRule {
+    fn from(raw: RawTtsRule) -> Self {
+        Self {
+            id: raw.id,
+            guild_id: GuildId::new(raw.guild_id as u64),
+            kind: match raw.kind {
+                1 => TtsRuleKind::Regex(raw.pattern),
+                2 => match raw.pattern.parse::<u64>() {
+                    Ok(id) if id != 0 => TtsRuleKind::Nickname(UserId::new(id)),
+                    _ => {
+                        log::warn!("Invalid user id in tts nickname: {}", raw.pattern);
+                        TtsRuleKind::Word(raw.pattern)
+                    }
+                },
+                _ => TtsRuleKind::Word(raw.pattern),
+            },
+            replacement: raw.replacement,
+            backend: raw.backend.and_then(|b| {
+                b.parse()
+                    .inspect_err(|e| log::warn!("Failed to parse tts backend: {}", e))
+                    .ok()
+            }),
+        }
+    }
+}
+
+fn raw_kind(kind: &TtsRuleKind) -> (i16, String) {
+    match kind {
+        TtsRuleKind::Word(word) => (0, word.clone()),
+        TtsRuleKind::Regex(regex) => (1, regex.clone()),
+        TtsRuleKind::Nickname(user_id) => (2, user_id.get().to_string()),
+    }
+}
+
+mod get {
+    use super::{GuildId, RawTtsRule, Result, TtsRule, Uuid};
+
+    pub async fn all(
+        guild_id: GuildId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<TtsRule>> {
+        Ok(sqlx::query_as!(
+            RawTtsRule,
+            "SELECT id, guild_id, kind, pattern, replacement, backend FROM tts_dictionary WHERE guild_id = $1 ORDER BY created_at ASC",
+            guild_id.get() as i64
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+
+    pub async fn specific(
+        guild_id: GuildId,
+        id: Uuid,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<TtsRule>> {
+        Ok(sqlx::query_as!(
+            RawTtsRule,
+            "SELECT id, guild_id, kind, pattern, replacement, backend FROM tts_dictionary WHERE guild_id = $1 AND id = $2",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(&mut **conn)
+        .await?
+        .map(Into::into))
+    }
+}
+
+mod set {
+    use super::{raw_kind, GuildId, RawTtsRule, Result, TtsBackendKind, TtsRuleKind, Uuid};
+
+    pub async fn new(
+        guild_id: GuildId,
+        kind: &TtsRuleKind,
+        replacement: &str,
+        backend: Option<TtsBackendKind>,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<RawTtsRule> {
+        let (kind, pattern) = raw_kind(kind);
+        Ok(sqlx::query_as!(
+            RawTtsRule,
+            "INSERT INTO tts_dictionary (guild_id, kind, pattern, replacement, backend) VALUES ($1, $2, $3, $4, $5) RETURNING id, guild_id, kind, pattern, replacement, backend",
+            guild_id.get() as i64,
+            kind,
+            pattern,
+            replacement,
+            backend.map(|b| b.name())
+        )
+        .fetch_one(&mut **conn)
+        .await?)
+    }
+
+    pub async fn delete(id: Uuid, conn: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<()> {
+        sqlx::query!("DELETE FROM tts_dictionary WHERE id = $1", id)
+            .execute(&mut **conn)
+            .await?;
+        Ok(())
+    }
+}
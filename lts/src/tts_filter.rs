@@ -0,0 +1,57 @@
+use common::serenity::all::{Cache, ChannelId, Message, RoleId, UserId};
+
+/// which messages get read out in a guild, stored on the guild
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtsFilter {
+    pub ignored_users: Vec<UserId>,
+    pub ignored_roles: Vec<RoleId>,
+    pub ignore_bots: bool,
+    pub ignore_webhooks: bool,
+    // only read people sitting in the voice channel the message would be read out in
+    pub voice_members_only: bool,
+    // longer messages get cut off, in characters
+    pub max_length: Option<usize>,
+}
+
+impl TtsFilter {
+    /// why a message shouldn't be read out in `voice_channel`, None if it should be
+    pub fn reject(
+        &self,
+        msg: &Message,
+        cache: &Cache,
+        voice_channel: ChannelId,
+    ) -> Option<&'static str> {
+        // webhooks show up as bot users, so they're checked first
+        if msg.webhook_id.is_some() {
+            if self.ignore_webhooks {
+                return Some("webhook");
+            }
+        } else if msg.author.bot && self.ignore_bots {
+            return Some("bot");
+        }
+        if self.ignored_users.contains(&msg.author.id) {
+            return Some("ignored user");
+        }
+        let roles = match (&msg.member, msg.guild_id) {
+            (Some(member), _) => member.roles.clone(),
+            (None, Some(guild_id)) => cache
+                .member(guild_id, msg.author.id)
+                .map(|m| m.roles.clone())
+                .unwrap_or_default(),
+            (None, None) => Vec::new(),
+        };
+        if roles.iter().any(|r| self.ignored_roles.contains(r)) {
+            return Some("ignored role");
+        }
+        if self.voice_members_only {
+            let channel = msg
+                .guild_id
+                .and_then(|g| cache.guild(g))
+                .and_then(|g| g.voice_states.get(&msg.author.id).and_then(|v| v.channel_id));
+            if channel != Some(voice_channel) {
+                return Some("not in the voice channel");
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,328 @@
+// CREATE TABLE IF NOT EXISTS tts_usage (
+//     -- discord guild id, 0 outside of a guild
+//     guild_id BIGINT NOT NULL,
+//     -- who it was said for, 0 when nobody asked for it (eg radio announcements)
+//     user_id BIGINT NOT NULL,
+//     -- the tts backend that synthesized it (google, dectalk, sam, local)
+//     backend TEXT NOT NULL,
+//     -- the day it was synthesized, quotas reset daily and monthly
+//     day DATE NOT NULL DEFAULT CURRENT_DATE,
+//     -- characters synthesized, cache hits aren't counted since they cost nothing
+//     characters BIGINT NOT NULL DEFAULT 0,
+//     -- how many requests those characters came from
+//     requests BIGINT NOT NULL DEFAULT 0,
+//     PRIMARY KEY (guild_id, user_id, backend, day)
+// );
+
+use common::{
+    anyhow::Result,
+    log,
+    serenity::{
+        all::{GuildId, UserId},
+        async_trait,
+    },
+    tts::{Meter, TtsBackendKind, Usage},
+};
+
+/// a guild's limits on characters synthesized with paid backends, None for no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TtsQuota {
+    pub daily: Option<u64>,
+    pub monthly: Option<u64>,
+    // for each user in the guild, on top of the guild wide limits
+    pub user_daily: Option<u64>,
+    pub user_monthly: Option<u64>,
+}
+
+impl TtsQuota {
+    pub fn is_unlimited(&self) -> bool {
+        self.daily.is_none()
+            && self.monthly.is_none()
+            && self.user_daily.is_none()
+            && self.user_monthly.is_none()
+    }
+    /// characters left on paid backends for a user in the guild, None when nothing is limited
+    pub async fn allowance(
+        &self,
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+    ) -> Result<Option<u64>> {
+        if self.is_unlimited() {
+            return Ok(None);
+        }
+        let mut conn = crate::get_connection().await?;
+        let used = get::paid(guild_id, user_id, &mut conn).await?;
+        Ok([
+            (self.daily, used.guild_today),
+            (self.monthly, used.guild_month),
+            (self.user_daily.filter(|_| user_id.is_some()), used.user_today),
+            (self.user_monthly.filter(|_| user_id.is_some()), used.user_month),
+        ]
+        .into_iter()
+        .filter_map(|(limit, used)| limit.map(|l| l.saturating_sub(used)))
+        .min())
+    }
+}
+
+/// characters a backend synthesized today and this month
+#[derive(Debug, Clone)]
+pub struct BackendUsage {
+    pub backend: TtsBackendKind,
+    pub today: u64,
+    pub month: u64,
+}
+
+/// characters synthesized for a user this month, None for things nobody asked for
+#[derive(Debug, Clone)]
+pub struct UserUsage {
+    pub user_id: Option<UserId>,
+    pub month: u64,
+}
+
+pub struct TtsUsage;
+
+impl TtsUsage {
+    /// counts usage on a paid backend before it's synthesized, but only if it fits in the quota.
+    /// checking and counting happen together so requests at the same time can't both squeeze in
+    pub async fn reserve(
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+        quota: TtsQuota,
+        usage: Usage,
+    ) -> Result<bool> {
+        let mut conn = crate::get_connection().await?;
+        let reserved = match guild_id {
+            Some(guild_id) if !quota.is_unlimited() => {
+                set::reserve(guild_id, user_id, quota, usage, &mut conn).await?
+            }
+            _ => {
+                set::record(guild_id, user_id, usage, &mut conn).await?;
+                true
+            }
+        };
+        conn.commit().await?;
+        Ok(reserved)
+    }
+    /// takes back a reservation for something that didn't end up being synthesized
+    pub async fn release(
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+        usage: Usage,
+    ) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::release(guild_id, user_id, usage, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    pub async fn record(
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+        usage: Usage,
+    ) -> Result<()> {
+        let mut conn = crate::get_connection().await?;
+        set::record(guild_id, user_id, usage, &mut conn).await?;
+        conn.commit().await?;
+        Ok(())
+    }
+    /// this month's usage in a guild for each backend
+    pub async fn backends(guild_id: GuildId) -> Result<Vec<BackendUsage>> {
+        let mut conn = crate::get_connection().await?;
+        get::backends(guild_id, &mut conn).await
+    }
+    /// the users with the most characters synthesized in a guild this month
+    pub async fn top_users(guild_id: GuildId, limit: i64) -> Result<Vec<UserUsage>> {
+        let mut conn = crate::get_connection().await?;
+        get::top_users(guild_id, limit, &mut conn).await
+    }
+}
+
+/// keeps a guild's tts to its quota, paid usage is counted as it's reserved
+pub(crate) struct QuotaMeter {
+    pub guild_id: Option<GuildId>,
+    pub user_id: Option<UserId>,
+    pub quota: TtsQuota,
+}
+
+#[async_trait]
+impl Meter for QuotaMeter {
+    async fn reserve(&self, usage: Usage) -> bool {
+        match TtsUsage::reserve(self.guild_id, self.user_id, self.quota, usage).await {
+            Ok(reserved) => reserved,
+            Err(e) => {
+                // better to go over than to stop talking when the database is having a moment
+                log::error!("Failed to reserve tts usage: {:?}", e);
+                true
+            }
+        }
+    }
+    async fn release(&self, usage: Usage) {
+        if let Err(e) = TtsUsage::release(self.guild_id, self.user_id, usage).await {
+            log::error!("Failed to release tts usage: {:?}", e);
+        }
+    }
+}
+
+fn raw_id(id: Option<u64>) -> i64 {
+    id.unwrap_or(0) as i64
+}
+
+fn paid_backends() -> Vec<String> {
+    TtsBackendKind::ALL
+        .iter()
+        .filter(|b| b.is_paid())
+        .map(|b| b.name().to_owned())
+        .collect()
+}
+
+mod get {
+    use super::{log, paid_backends, raw_id, BackendUsage, GuildId, Result, UserId, UserUsage};
+
+    pub struct PaidUsage {
+        pub guild_today: u64,
+        pub guild_month: u64,
+        pub user_today: u64,
+        pub user_month: u64,
+    }
+
+    pub async fn paid(
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<PaidUsage> {
+        let row = sqlx::query!(
+            r#"SELECT COALESCE(SUM(characters) FILTER (WHERE day = CURRENT_DATE), 0)::BIGINT AS "guild_today!", COALESCE(SUM(characters), 0)::BIGINT AS "guild_month!", COALESCE(SUM(characters) FILTER (WHERE day = CURRENT_DATE AND user_id = $2), 0)::BIGINT AS "user_today!", COALESCE(SUM(characters) FILTER (WHERE user_id = $2), 0)::BIGINT AS "user_month!" FROM tts_usage WHERE guild_id = $1 AND backend = ANY($3) AND day >= date_trunc('month', CURRENT_DATE)"#,
+            guild_id.get() as i64,
+            raw_id(user_id.map(|u| u.get())),
+            &paid_backends()[..]
+        )
+        .fetch_one(&mut **conn)
+        .await?;
+        Ok(PaidUsage {
+            guild_today: row.guild_today as u64,
+            guild_month: row.guild_month as u64,
+            user_today: row.user_today as u64,
+            user_month: row.user_month as u64,
+        })
+    }
+
+    pub async fn backends(
+        guild_id: GuildId,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<BackendUsage>> {
+        Ok(sqlx::query!(
+            r#"SELECT backend, COALESCE(SUM(characters) FILTER (WHERE day = CURRENT_DATE), 0)::BIGINT AS "today!", COALESCE(SUM(characters), 0)::BIGINT AS "month!" FROM tts_usage WHERE guild_id = $1 AND day >= date_trunc('month', CURRENT_DATE) GROUP BY backend ORDER BY 3 DESC"#,
+            guild_id.get() as i64
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .filter_map(|row| match row.backend.parse() {
+            Ok(backend) => Some(BackendUsage {
+                backend,
+                today: row.today as u64,
+                month: row.month as u64,
+            }),
+            Err(e) => {
+                log::warn!("Failed to parse tts backend: {}", e);
+                None
+            }
+        })
+        .collect())
+    }
+
+    pub async fn top_users(
+        guild_id: GuildId,
+        limit: i64,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<UserUsage>> {
+        Ok(sqlx::query!(
+            r#"SELECT user_id, COALESCE(SUM(characters), 0)::BIGINT AS "month!" FROM tts_usage WHERE guild_id = $1 AND day >= date_trunc('month', CURRENT_DATE) GROUP BY user_id ORDER BY 2 DESC LIMIT $2"#,
+            guild_id.get() as i64,
+            limit
+        )
+        .fetch_all(&mut **conn)
+        .await?
+        .into_iter()
+        .map(|row| UserUsage {
+            user_id: match row.user_id {
+                0 => None,
+                id => Some(UserId::new(id as u64)),
+            },
+            month: row.month as u64,
+        })
+        .collect())
+    }
+}
+
+mod set {
+    use super::{paid_backends, raw_id, GuildId, Result, TtsQuota, Usage, UserId};
+
+    pub async fn reserve(
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+        quota: TtsQuota,
+        usage: Usage,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool> {
+        // the sums below don't lock anything, so reservations in a guild take turns on its row
+        sqlx::query!("SELECT id FROM guilds WHERE id = $1 FOR UPDATE", guild_id.get() as i64)
+            .fetch_optional(&mut **conn)
+            .await?;
+        // per user limits don't apply to things nobody asked for
+        let (user_daily, user_monthly) = match user_id {
+            Some(_) => (quota.user_daily, quota.user_monthly),
+            None => (None, None),
+        };
+        let inserted = sqlx::query!(
+            r#"INSERT INTO tts_usage (guild_id, user_id, backend, characters, requests) SELECT $1, $2, $3, $4, 1 FROM (SELECT COALESCE(SUM(characters) FILTER (WHERE day = CURRENT_DATE), 0) AS guild_today, COALESCE(SUM(characters), 0) AS guild_month, COALESCE(SUM(characters) FILTER (WHERE day = CURRENT_DATE AND user_id = $2), 0) AS user_today, COALESCE(SUM(characters) FILTER (WHERE user_id = $2), 0) AS user_month FROM tts_usage WHERE guild_id = $1 AND backend = ANY($5) AND day >= date_trunc('month', CURRENT_DATE)) used WHERE ($6::BIGINT IS NULL OR used.guild_today + $4 <= $6) AND ($7::BIGINT IS NULL OR used.guild_month + $4 <= $7) AND ($8::BIGINT IS NULL OR used.user_today + $4 <= $8) AND ($9::BIGINT IS NULL OR used.user_month + $4 <= $9) ON CONFLICT (guild_id, user_id, backend, day) DO UPDATE SET characters = tts_usage.characters + $4, requests = tts_usage.requests + 1"#,
+            guild_id.get() as i64,
+            raw_id(user_id.map(|u| u.get())),
+            usage.backend.name(),
+            usage.characters as i64,
+            &paid_backends()[..],
+            quota.daily.map(|q| q as i64),
+            quota.monthly.map(|q| q as i64),
+            user_daily.map(|q| q as i64),
+            user_monthly.map(|q| q as i64)
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(inserted.rows_affected() > 0)
+    }
+
+    pub async fn release(
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+        usage: Usage,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE tts_usage SET characters = GREATEST(characters - $4, 0), requests = GREATEST(requests - 1, 0) WHERE guild_id = $1 AND user_id = $2 AND backend = $3 AND day = CURRENT_DATE",
+            raw_id(guild_id.map(|g| g.get())),
+            raw_id(user_id.map(|u| u.get())),
+            usage.backend.name(),
+            usage.characters as i64
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record(
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+        usage: Usage,
+        conn: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO tts_usage (guild_id, user_id, backend, characters, requests) VALUES ($1, $2, $3, $4, 1) ON CONFLICT (guild_id, user_id, backend, day) DO UPDATE SET characters = tts_usage.characters + $4, requests = tts_usage.requests + 1",
+            raw_id(guild_id.map(|g| g.get())),
+            raw_id(user_id.map(|u| u.get())),
+            usage.backend.name(),
+            usage.characters as i64
+        )
+        .execute(&mut **conn)
+        .await?;
+        Ok(())
+    }
+}
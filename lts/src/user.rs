@@ -2,7 +2,19 @@
 //     -- Discord user ID for querying
 //     id BIGINT PRIMARY KEY,
 //     -- Whether or not the user consents to their microphone data being processed
-//     mic_consent BOOLEAN NOT NULL DEFAULT FALSE
+//     mic_consent BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- TTS voice gender preference, null = no preference, true = female, false = male
+//     voice_preference BOOLEAN DEFAULT NULL,
+//     -- Timezone name for reminders and schedules
+//     timezone TEXT NOT NULL DEFAULT 'EST5EDT',
+//     -- TTS backend to try first (google, dectalk, sam, local), null to go with the guild's
+//     tts_backend TEXT,
+//     -- exact TTS voice name (eg en-GB-Neural2-D), overrides voice_preference when set
+//     voice TEXT,
+//     -- Whether the user never wants their messages read out
+//     tts_opt_out BOOLEAN NOT NULL DEFAULT FALSE,
+//     -- Only read the user's messages while they're muted in the bot's voice channel
+//     tts_muted_only BOOLEAN NOT NULL DEFAULT FALSE
 // );
 
 use common::{
@@ -10,6 +22,8 @@ use common::{
     chrono_tz::Tz,
     log,
     serenity::{all::UserId, futures::StreamExt as _},
+    tts::TtsBackendKind,
+    youtube::TTSVoice,
 };
 use std::{cell::OnceCell, collections::HashMap, str::FromStr};
 
@@ -70,6 +84,10 @@ pub struct User {
     pub mic_consent: bool,
     pub voice_preference: VoicePreference,
     pub timezone: Tz,
+    pub tts_backend: Option<TtsBackendKind>,
+    pub voice: Option<TTSVoice>,
+    pub tts_opt_out: bool,
+    pub tts_muted_only: bool,
 }
 
 impl User {
@@ -117,6 +135,10 @@ struct RawUser {
     mic_consent: bool,
     voice_preference: Option<bool>, // none = no preference, true = female, false = male
     timezone: String,
+    tts_backend: Option<String>,
+    voice: Option<String>,
+    tts_opt_out: bool,
+    tts_muted_only: bool,
 }
 
 impl From<RawUser> for User {
@@ -133,6 +155,20 @@ impl From<RawUser> for User {
                 Some(false) => VoicePreference::Male,
                 None => VoicePreference::NoPreference,
             },
+            tts_backend: val.tts_backend.and_then(|b| {
+                b.parse()
+                    .inspect_err(|e| log::warn!("Failed to parse tts backend: {}", e))
+                    .ok()
+            }),
+            voice: val.voice.and_then(|v| {
+                let voice = TTSVoice::by_name(&v);
+                if voice.is_none() {
+                    log::warn!("Unknown tts voice: {}", v);
+                }
+                voice
+            }),
+            tts_opt_out: val.tts_opt_out,
+            tts_muted_only: val.tts_muted_only,
         }
     }
 }
@@ -181,6 +217,10 @@ mod set {
             mic_consent,
             voice_preference,
             timezone,
+            tts_backend,
+            voice,
+            tts_opt_out,
+            tts_muted_only,
         } = user;
 
         // set the cache
@@ -190,7 +230,7 @@ mod set {
         }
         // set the user in the DB, either insert or update the user
         sqlx::query!(
-            "INSERT INTO users (id, mic_consent, voice_preference, timezone) VALUES ($1, $2, $3, $4) ON CONFLICT (id) DO UPDATE SET mic_consent = $2, voice_preference = $3, timezone = $4",
+            "INSERT INTO users (id, mic_consent, voice_preference, timezone, tts_backend, voice, tts_opt_out, tts_muted_only) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (id) DO UPDATE SET mic_consent = $2, voice_preference = $3, timezone = $4, tts_backend = $5, voice = $6, tts_opt_out = $7, tts_muted_only = $8",
             id.get() as i64,
             mic_consent,
             match voice_preference {
@@ -198,7 +238,11 @@ mod set {
                 super::VoicePreference::Male => Some(false),
                 super::VoicePreference::Female => Some(true),
             },
-            timezone.name()
+            timezone.name(),
+            tts_backend.map(|b| b.name()),
+            voice.map(|v| v.name),
+            tts_opt_out,
+            tts_muted_only
         )
         .execute(&mut **conn)
         .await?;
@@ -209,6 +209,7 @@ impl CommandTrait for Command {
                                 let transcription = TranscriptionThread::new(
                                     Arc::clone(&call),
                                     ctx.clone(),
+                                    guild_id,
                                     tx.clone(),
                                     packets,
                                 )
@@ -452,34 +453,58 @@ impl CommandTrait for Command {
                 Ok(rawvids) => {
                     let mut truevideos = Vec::new();
                     #[cfg(feature = "tts")]
+                    let guild = long_term_storage::tts_guild(interaction.guild_id).await;
+                    #[cfg(feature = "tts")]
+                    let announcer = guild.as_ref().map(|g| g.announcer.clone()).unwrap_or_default();
+                    #[cfg(feature = "tts")]
+                    let backends = announcer.backends(long_term_storage::tts_backends(
+                        guild.as_ref(),
+                        long_term_storage::tts_user(Some(interaction.user.id)).await.as_ref(),
+                    ));
+                    #[cfg(feature = "tts")]
+                    let dictionary =
+                        long_term_storage::tts_dictionary(guild.as_ref(), Some(ctx.cache.as_ref()))
+                            .await;
+                    #[cfg(feature = "tts")]
                     for v in rawvids {
-                        let title = match v.clone() {
-                            VideoType::Disk(v) => v.title(),
-                            VideoType::Url(v) => v.title(),
+                        let (title, artist, duration) = match v.clone() {
+                            VideoType::Disk(v) => (v.title(), None, Some(v.duration())),
+                            VideoType::Url(v) => (v.title(), v.artist(), v.duration()),
                         };
                         #[cfg(feature = "tts")]
                         {
                             log::trace!("Getting tts for {}", title);
+                            let author =
+                                Author::from_user(ctx, &interaction.user, interaction.guild_id)
+                                    .await;
+                            let text = announcer.announce(&common::tts::Announcement {
+                                title: &title,
+                                artist: artist.as_deref(),
+                                requester: author.as_ref().map(|a| a.name.as_str()),
+                                duration,
+                            });
                             truevideos.push(MetaVideo {
                                 video: v,
-                                ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn(async move {
-                                    match common::youtube::get_tts(Arc::clone(&title), None).await {
-                                        Ok(v) => Ok(v),
-                                        Err(original_error) => {
-                                            match dectalk::get_speech(&title).await {
-                                                Ok(v) => Ok(v),
-                                                Err(_) => Err(original_error),
-                                            }
-                                        }
+                                ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn({
+                                    let backends = backends.clone();
+                                    let dictionary = dictionary.clone();
+                                    let voice = announcer.voice;
+                                    let guild = guild.clone();
+                                    let user_id = interaction.user.id;
+                                    async move {
+                                        long_term_storage::tts_speak_with(
+                                            &text,
+                                            voice,
+                                            &backends,
+                                            &dictionary,
+                                            guild.as_ref(),
+                                            Some(user_id),
+                                        )
+                                        .await
                                     }
                                 }))),
                                 // title,
-                                author: Author::from_user(
-                                    ctx,
-                                    &interaction.user,
-                                    interaction.guild_id,
-                                )
-                                .await,
+                                author,
                             })
                         }
                         #[cfg(not(feature = "tts"))]
@@ -880,6 +905,7 @@ impl CommandTrait for AddRaw {
                                 let transcription = TranscriptionThread::new(
                                     Arc::clone(&call),
                                     ctx.clone(),
+                                    guild_id,
                                     tx.clone(),
                                     packets,
                                 )
@@ -1126,19 +1152,47 @@ impl CommandTrait for AddRaw {
             #[cfg(feature = "tts")]
             {
                 log::trace!("Getting tts for {}", title);
+                let guild = long_term_storage::tts_guild(interaction.guild_id).await;
+                let announcer = guild.as_ref().map(|g| g.announcer.clone()).unwrap_or_default();
+                let author = Author::from_user(ctx, &interaction.user, interaction.guild_id).await;
+                let duration = match v {
+                    VideoType::Disk(ref v) => Some(v.duration()),
+                    VideoType::Url(ref v) => v.duration(),
+                };
+                let text = announcer.announce(&common::tts::Announcement {
+                    title: &title,
+                    artist: None,
+                    requester: author.as_ref().map(|a| a.name.as_str()),
+                    duration,
+                });
                 truevideos.push(MetaVideo {
                     video: v,
-                    ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn(async move {
-                        match common::youtube::get_tts(&title, None).await {
-                            Ok(v) => Ok(v),
-                            Err(original_error) => match dectalk::get_speech(&title).await {
-                                Ok(v) => Ok(v),
-                                Err(_) => Err(original_error),
-                            },
+                    ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn({
+                        let backends = announcer.backends(long_term_storage::tts_backends(
+                            guild.as_ref(),
+                            long_term_storage::tts_user(Some(interaction.user.id)).await.as_ref(),
+                        ));
+                        let dictionary = long_term_storage::tts_dictionary(
+                            guild.as_ref(),
+                            Some(ctx.cache.as_ref()),
+                        )
+                        .await;
+                        let voice = announcer.voice;
+                        let user_id = interaction.user.id;
+                        async move {
+                            long_term_storage::tts_speak_with(
+                                &text,
+                                voice,
+                                &backends,
+                                &dictionary,
+                                guild.as_ref(),
+                                Some(user_id),
+                            )
+                            .await
                         }
                     }))),
                     // title,
-                    author: Author::from_user(ctx, &interaction.user, interaction.guild_id).await,
+                    author,
                 })
             }
             #[cfg(not(feature = "tts"))]
@@ -0,0 +1,221 @@
+use common::anyhow::Result;
+use common::audio::Transitions;
+use common::idle::{IdlePlaylist, IdleTags};
+use common::{log, songbird, tokio};
+use songbird::tracks::{PlayMode, Track, TrackHandle};
+use songbird::Call;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::time::Instant;
+const TICK: Duration = Duration::from_millis(100);
+// if every entry in a row fails to play, wait this long before trying again
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+/// whatever plays while nothing is queued
+pub enum IdleAudio {
+    // a radio stream or a single file, looped forever
+    Looping(TrackHandle),
+    Playlist(IdlePlayer),
+}
+impl IdleAudio {
+    pub fn set_volume(&self, volume: f32) -> Result<()> {
+        match self {
+            Self::Looping(handle) => handle.set_volume(volume)?,
+            Self::Playlist(player) => player.send(IdleCommand::SetVolume(volume))?,
+        }
+        Ok(())
+    }
+    /// only playlists fade between tracks, a looping track has nothing to change
+    pub fn set_transitions(&self, transitions: Transitions) -> Result<()> {
+        if let Self::Playlist(player) = self {
+            player.send(IdleCommand::SetTransitions(transitions))?;
+        }
+        Ok(())
+    }
+    pub fn stop(&self) -> Result<()> {
+        match self {
+            Self::Looping(handle) => handle.stop()?,
+            Self::Playlist(player) => player.send(IdleCommand::Stop)?,
+        }
+        Ok(())
+    }
+    pub fn is_playlist(&self) -> bool {
+        matches!(self, Self::Playlist(_))
+    }
+    /// tags of the playlist track currently playing
+    pub fn tags(&self) -> Option<IdleTags> {
+        match self {
+            Self::Looping(_) => None,
+            Self::Playlist(player) => player.tags.borrow().clone(),
+        }
+    }
+    /// resolves when the playlist moves on to another track, never for a looping track
+    pub async fn changed(&mut self) -> Option<()> {
+        match self {
+            Self::Looping(_) => std::future::pending().await,
+            Self::Playlist(player) => player.tags.changed().await.ok(),
+        }
+    }
+}
+enum IdleCommand {
+    SetVolume(f32),
+    SetTransitions(Transitions),
+    Stop,
+}
+pub struct IdlePlayer {
+    commands: mpsc::UnboundedSender<IdleCommand>,
+    tags: watch::Receiver<Option<IdleTags>>,
+}
+impl IdlePlayer {
+    pub fn new(
+        call: Arc<Mutex<Call>>,
+        playlist: IdlePlaylist,
+        volume: f32,
+        transitions: Transitions,
+    ) -> Self {
+        let (commands, rx) = mpsc::unbounded_channel();
+        let (tags_tx, tags) = watch::channel(None);
+        tokio::task::spawn(play(call, playlist, volume, transitions, rx, tags_tx));
+        Self { commands, tags }
+    }
+    fn send(&self, command: IdleCommand) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| common::anyhow::anyhow!("Idle player has stopped"))
+    }
+}
+impl Drop for IdlePlayer {
+    fn drop(&mut self) {
+        let _ = self.commands.send(IdleCommand::Stop);
+    }
+}
+struct Playing {
+    handle: TrackHandle,
+    duration: Option<Duration>,
+}
+enum Advance {
+    No,
+    // the current track is about to end, fade the next one in over it
+    Crossfade,
+    // the current track already ended or failed
+    Cut,
+}
+async fn play(
+    call: Arc<Mutex<Call>>,
+    mut playlist: IdlePlaylist,
+    mut volume: f32,
+    mut transitions: Transitions,
+    mut rx: mpsc::UnboundedReceiver<IdleCommand>,
+    tags: watch::Sender<Option<IdleTags>>,
+) {
+    let mut current: Option<Playing> = None;
+    let mut incoming: Option<(Playing, Instant)> = None;
+    let mut failures = 0;
+    let mut retry_at: Option<Instant> = None;
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        tokio::select! {
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(IdleCommand::SetVolume(v)) => {
+                        volume = v;
+                        // mid fade the next tick picks up the new volume
+                        if incoming.is_none() {
+                            if let Some(ref c) = current {
+                                if let Err(e) = c.handle.set_volume(volume) {
+                                    log::warn!("Error setting idle volume: {}", e);
+                                }
+                            }
+                        }
+                    }
+                    Some(IdleCommand::SetTransitions(t)) => transitions = t,
+                    Some(IdleCommand::Stop) | None => break,
+                }
+            }
+            _ = interval.tick() => {
+                if let Some((next, started)) = incoming.take() {
+                    let progress = if transitions.crossfade.is_zero() {
+                        1.0
+                    } else {
+                        (started.elapsed().as_secs_f32() / transitions.crossfade.as_secs_f32()).min(1.0)
+                    };
+                    if let Err(e) = next.handle.set_volume(volume * progress) {
+                        log::warn!("Error fading in idle track: {}", e);
+                    }
+                    if progress >= 1.0 {
+                        if let Some(old) = current.replace(next) {
+                            let _ = old.handle.stop();
+                        }
+                    } else {
+                        if let Some(ref c) = current {
+                            if let Err(e) = c.handle.set_volume(volume * (1.0 - progress)) {
+                                log::warn!("Error fading out idle track: {}", e);
+                            }
+                        }
+                        incoming = Some((next, started));
+                    }
+                    continue;
+                }
+                let advance = match current {
+                    None => Advance::Cut,
+                    Some(ref c) => match c.handle.get_info().await {
+                        Ok(state) => match state.playing {
+                            PlayMode::Play => {
+                                failures = 0;
+                                match c.duration {
+                                    Some(d) if !transitions.crossfade.is_zero()
+                                        && state.position + transitions.crossfade >= d =>
+                                    {
+                                        Advance::Crossfade
+                                    }
+                                    _ => Advance::No,
+                                }
+                            }
+                            PlayMode::Errored(e) => {
+                                log::warn!("Idle track failed: {:?}", e);
+                                failures += 1;
+                                Advance::Cut
+                            }
+                            PlayMode::End | PlayMode::Stop => Advance::Cut,
+                            _ => Advance::No,
+                        },
+                        // the track is gone
+                        Err(_) => Advance::Cut,
+                    },
+                };
+                if matches!(advance, Advance::No) {
+                    continue;
+                }
+                if failures >= playlist.len() {
+                    log::warn!("Every idle track failed, waiting before trying again");
+                    failures = 0;
+                    retry_at = Some(Instant::now() + RETRY_AFTER);
+                }
+                if retry_at.map(|r| r > Instant::now()).unwrap_or(false) {
+                    continue;
+                }
+                retry_at = None;
+                let entry = match playlist.next_entry() {
+                    Some(e) => e,
+                    None => break,
+                };
+                let track_tags = entry.tags().await;
+                let duration = track_tags.duration.map(Duration::from_secs_f64);
+                let fade = matches!(advance, Advance::Crossfade);
+                let handle = call.lock().await.play(
+                    Track::new(entry.input()).volume(if fade { 0.0 } else { volume }),
+                );
+                let _ = tags.send(Some(track_tags));
+                let next = Playing { handle, duration };
+                if fade {
+                    incoming = Some((next, Instant::now()));
+                } else if let Some(old) = current.replace(next) {
+                    let _ = old.handle.stop();
+                }
+            }
+        }
+    }
+    for playing in current.into_iter().chain(incoming.map(|(p, _)| p)) {
+        let _ = playing.handle.stop();
+    }
+}
@@ -5,7 +5,7 @@ use super::{
     AudioHandler,
 };
 use common::{
-    anyhow::Result,
+    anyhow::{self, Result},
     audio::{AudioCommandHandler, AudioPromiseCommand, SenderAndGuildId},
     songbird, tokio,
 };
@@ -127,14 +127,18 @@ impl CommandTrait for Command {
                     return Ok(());
                 }
                 VoiceAction::SatelliteShouldJoin(channel, satellite_ctx) => {
-                    let manager = match songbird::get(&satellite_ctx).await {
-                        Some(v) => v,
-                        None => {
+                    match connect(ctx, &satellite_ctx, guild_id, channel).await {
+                        Ok(()) => {
+                            if let Err(e) = interaction.delete_response(&ctx.http).await {
+                                log::error!("Error deleting interaction: {:?}", e);
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to join channel: {:?}", e);
                             if let Err(e) = interaction
                                 .edit_response(
                                     &ctx.http,
-                                    EditInteractionResponse::new()
-                                        .content("Failed to get songbird manager"),
+                                    EditInteractionResponse::new().content(e.to_string()),
                                 )
                                 .await
                             {
@@ -143,220 +147,6 @@ impl CommandTrait for Command {
                                     e
                                 );
                             }
-                            return Ok(());
-                        }
-                    };
-                    {
-                        let audio_handler = {
-                            match ctx.data.read().await.get::<AudioHandler>() {
-                                Some(v) => Arc::clone(v),
-                                None => {
-                                    if let Err(e) = interaction
-                                        .edit_response(
-                                            &ctx.http,
-                                            EditInteractionResponse::new()
-                                                .content("Failed to get audio handler"),
-                                        )
-                                        .await
-                                    {
-                                        log::error!(
-                                            "Failed to edit original interaction response: {:?}",
-                                            e
-                                        );
-                                    }
-                                    return Ok(());
-                                }
-                            }
-                        };
-                        match manager.join(guild_id, channel).await {
-                            Ok(call) => {
-                                let packets = {
-                                    let mut call = call.lock().await;
-                                    let (sender, receiver) = mpsc::unbounded_channel();
-                                    let event_sender = voice_events::VoiceEventSender::new(sender);
-                                    for event in voice_events::EVENTS {
-                                        call.add_global_event(*event, event_sender.clone());
-                                    }
-                                    receiver
-                                };
-                                let (tx, rx) = mpsc::unbounded_channel::<(
-                                    oneshot::Sender<Arc<str>>,
-                                    AudioPromiseCommand,
-                                )>();
-                                let transcription = TranscriptionThread::new(
-                                    Arc::clone(&call),
-                                    ctx.clone(),
-                                    tx.clone(),
-                                    packets,
-                                )
-                                .await;
-                                let msg = match channel
-                                    .send_message(
-                                        &ctx.http,
-                                        CreateMessage::new()
-                                            .content("<a:earloading:979852072998543443>")
-                                            .flags(MessageFlags::SUPPRESS_NOTIFICATIONS),
-                                    )
-                                    .await
-                                {
-                                    Ok(msg) => msg,
-                                    Err(e) => {
-                                        log::error!("Failed to send message: {:?}", e);
-                                        if let Err(e) = interaction
-                                            .edit_response(
-                                                &ctx.http,
-                                                EditInteractionResponse::new()
-                                                    .content("Failed to send message"),
-                                            )
-                                            .await
-                                        {
-                                            log::error!("Failed to edit original interaction response: {:?}", e);
-                                        }
-                                        return Ok(());
-                                    }
-                                };
-                                let messageref = super::MessageReference::new(
-                                    Arc::clone(&ctx.http),
-                                    Arc::clone(&ctx.cache),
-                                    guild_id,
-                                    channel,
-                                    msg,
-                                );
-                                let cfg = common::get_config();
-                                let mut nothing_path = cfg.data_path.clone();
-                                nothing_path.push("override.mp3");
-                                let nothing_path = if nothing_path.exists() {
-                                    Some(nothing_path)
-                                } else {
-                                    None
-                                };
-                                let guild_id = match interaction.guild_id {
-                                    Some(guild) => guild,
-                                    None => return Ok(()),
-                                };
-                                let settings = match SettingsData::new(guild_id).await {
-                                    Ok(v) => v,
-                                    Err(e) => {
-                                        log::error!("Failed to get settings: {:?}", e);
-                                        if let Err(e) = interaction
-                                            .edit_response(
-                                                &ctx.http,
-                                                EditInteractionResponse::new()
-                                                    .content("Failed to get settings"),
-                                            )
-                                            .await
-                                        {
-                                            log::error!(
-                                                "Failed to edit original interaction response: {:?}",
-                                                e
-                                            );
-                                        }
-                                        return Ok(());
-                                    }
-                                };
-                                // let em = match super::get_transcribe_channel_handler(ctx, &guild_id)
-                                //     .await
-                                // {
-                                //     Ok(v) => v,
-                                //     Err(e) => {
-                                //         log::error!(
-                                //             "Failed to get transcribe channel handler: {:?}",
-                                //             e
-                                //         );
-                                //         if let Err(e) = interaction
-                                //             .edit_response(
-                                //                 &ctx.http,
-                                //                 EditInteractionResponse::new()
-                                //                     .content("Failed to get handler"),
-                                //             )
-                                //             .await
-                                //         {
-                                //             log::error!(
-                                //     "Failed to edit original interaction response: {:?}",
-                                //     e
-                                // );
-                                //         }
-                                //         return Ok(());
-                                //     }
-                                // };
-                                // if let Err(e) = em.write().await.register(channel).await {
-                                //     log::error!("Error registering channel: {:?}", e);
-                                // }
-                                let this_bot_id = ctx.cache.current_user().id;
-                                let audio_command_handler = {
-                                    let read_lock = ctx.data.read().await;
-                                    match read_lock.get::<AudioCommandHandler>() {
-                                        Some(v) => Arc::clone(v),
-                                        None => {
-                                            if let Err(e) = interaction
-                                                .edit_response(
-                                                    &ctx.http,
-                                                    EditInteractionResponse::new().content(
-                                                        "Failed to get audio command handler",
-                                                    ),
-                                                )
-                                                .await
-                                            {
-                                                log::error!(
-                                                    "Failed to edit original interaction response: {:?}",
-                                                    e
-                                                );
-                                            }
-                                            return Ok(());
-                                        }
-                                    }
-                                };
-
-                                let handle = {
-                                    let ctx = ctx.clone();
-                                    let ach = Arc::clone(&audio_command_handler);
-                                    tokio::task::spawn(async move {
-                                        let control = ControlData {
-                                            call,
-                                            rx,
-                                            msg: messageref,
-                                            nothing_uri: nothing_path,
-                                            settings,
-                                            log: Log::new(format!("{}-{}", guild_id, channel)),
-                                            // transcribe: em,
-                                        };
-                                        super::mainloop::the_lüüp(
-                                            // cfg.looptime,
-                                            transcription,
-                                            control,
-                                            this_bot_id,
-                                            ctx,
-                                            channel,
-                                            ach,
-                                        )
-                                        .await;
-                                    })
-                                };
-                                audio_handler.write().await.insert(channel, handle);
-                                audio_command_handler
-                                    .write()
-                                    .await
-                                    .insert(channel, SenderAndGuildId::new(tx, guild_id));
-                                if let Err(e) = interaction.delete_response(&ctx.http).await {
-                                    log::error!("Error deleting interaction: {:?}", e);
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("Failed to join channel: {:?}", e);
-                                if let Err(e) = interaction
-                                    .edit_response(
-                                        &ctx.http,
-                                        EditInteractionResponse::new()
-                                            .content("Failed to join voice channel"),
-                                    )
-                                    .await
-                                {
-                                    log::error!(
-                                        "Failed to edit original interaction response: {:?}",
-                                        e
-                                    );
-                                }
-                            }
                         }
                     }
                 }
@@ -376,3 +166,99 @@ impl CommandTrait for Command {
         "join"
     }
 }
+/// joins `channel` with the given satellite and starts the main loop there, the message goes in the voice channel's chat
+pub async fn connect(
+    ctx: &Context,
+    satellite_ctx: &Context,
+    guild_id: GuildId,
+    channel: ChannelId,
+) -> Result<()> {
+    let manager = songbird::get(satellite_ctx)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Failed to get songbird manager"))?;
+    let audio_handler = ctx
+        .data
+        .read()
+        .await
+        .get::<AudioHandler>()
+        .map(Arc::clone)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get audio handler"))?;
+    let audio_command_handler = ctx
+        .data
+        .read()
+        .await
+        .get::<AudioCommandHandler>()
+        .map(Arc::clone)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get audio command handler"))?;
+    let settings = SettingsData::new(guild_id).await.map_err(|e| {
+        log::error!("Failed to get settings: {:?}", e);
+        anyhow::anyhow!("Failed to get settings")
+    })?;
+    let call = manager.join(guild_id, channel).await.map_err(|e| {
+        log::error!("Failed to join channel: {:?}", e);
+        anyhow::anyhow!("Failed to join voice channel")
+    })?;
+    let packets = {
+        let mut call = call.lock().await;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let event_sender = voice_events::VoiceEventSender::new(sender);
+        for event in voice_events::EVENTS {
+            call.add_global_event(*event, event_sender.clone());
+        }
+        receiver
+    };
+    let (tx, rx) = mpsc::unbounded_channel::<(oneshot::Sender<Arc<str>>, AudioPromiseCommand)>();
+    let transcription =
+        TranscriptionThread::new(Arc::clone(&call), ctx.clone(), guild_id, tx.clone(), packets)
+            .await;
+    let msg = channel
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content("<a:earloading:979852072998543443>")
+                .flags(MessageFlags::SUPPRESS_NOTIFICATIONS),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to send message: {:?}", e);
+            anyhow::anyhow!("Failed to send message")
+        })?;
+    let messageref = super::MessageReference::new(
+        Arc::clone(&ctx.http),
+        Arc::clone(&ctx.cache),
+        guild_id,
+        channel,
+        msg,
+    );
+    let cfg = common::get_config();
+    let mut nothing_path = cfg.data_path.clone();
+    nothing_path.push("override.mp3");
+    let nothing_path = if nothing_path.exists() {
+        Some(nothing_path)
+    } else {
+        None
+    };
+    let this_bot_id = ctx.cache.current_user().id;
+    let handle = {
+        let ctx = ctx.clone();
+        let ach = Arc::clone(&audio_command_handler);
+        tokio::task::spawn(async move {
+            let control = ControlData {
+                call,
+                rx,
+                msg: messageref,
+                nothing_uri: nothing_path,
+                settings,
+                log: Log::new(format!("{}-{}", guild_id, channel)),
+            };
+            super::mainloop::the_lüüp(transcription, control, this_bot_id, ctx, channel, ach)
+                .await;
+        })
+    };
+    audio_handler.write().await.insert(channel, handle);
+    audio_command_handler
+        .write()
+        .await
+        .insert(channel, SenderAndGuildId::new(tx, guild_id));
+    Ok(())
+}
@@ -2,19 +2,23 @@
 pub mod add;
 pub mod autoplay;
 pub mod consent;
+pub mod idle;
 pub mod join;
 pub mod loop_queue;
 pub mod mainloop;
 pub mod pause;
+pub mod radio;
 pub mod remove;
 pub mod repeat;
 pub mod resume;
+pub mod schedule;
 pub mod setbitrate;
 pub mod settingsdata;
 pub mod shuffle;
 pub mod skip;
 pub mod stop;
 pub mod transcribe;
+pub mod tts;
 pub mod voice_preference;
 pub mod volume;
 use self::mainloop::EmbedData;
@@ -23,14 +27,14 @@ use common::anyhow::{self, Result};
 #[cfg(not(feature = "new-controls"))]
 use common::serenity::all::{ButtonStyle, CreateButton};
 use common::serenity::all::{
-    Cache, Channel, ChannelId, ChannelType, Context, CreateActionRow, CreateMessage, EditMessage,
-    GetMessages, GuildChannel, GuildId, Http, Message, MessageFlags, UserId,
+    Cache, Channel, ChannelId, ChannelType, Context, CreateActionRow, CreateMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, GetMessages,
+    GuildChannel, GuildId, Http, Message, MessageFlags, UserId,
 };
-#[cfg(feature = "new-controls")]
-use common::serenity::all::{CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption};
 #[cfg(feature = "transcribe")]
-use common::serenity::all::{CreateThread, CreateWebhook};
+use common::serenity::all::{CreateAllowedMentions, CreateThread, CreateWebhook};
 use common::video::Video;
+use common::tts::{Dictionary, RenderOptions, Segment, Speech, TtsBackendKind};
 use common::youtube::TTSVoice;
 use common::{log, songbird, tokio};
 #[cfg(feature = "transcribe")]
@@ -60,6 +64,8 @@ pub struct MessageReference {
 
     resend_next_time: bool,
     transcription_thread: OptionOrFailed<GuildChannel>,
+    // where transcribed text goes instead when the voice channel has live captions
+    caption_channel: Option<ChannelId>,
 }
 #[derive(Debug, Clone)]
 pub enum OptionOrFailed<T> {
@@ -102,8 +108,12 @@ impl MessageReference {
             edit_delay: 10000,
             resend_next_time: false,
             transcription_thread: OptionOrFailed::None,
+            caption_channel: None,
         }
     }
+    fn change_caption_channel(&mut self, caption_channel: Option<ChannelId>) {
+        self.caption_channel = caption_channel;
+    }
     async fn change_channel(&mut self, channel_id: ChannelId) -> Result<()> {
         self.channel_id = channel_id;
         // delete and resend
@@ -114,6 +124,18 @@ impl MessageReference {
     #[cfg(feature = "transcribe")]
     async fn send_manually(&mut self, content: common::PostSomething, user: UserId) -> Result<()> {
         use common::{chrono, reqwest, PostSomething, WEB_CLIENT};
+        // captions come already formatted with everyone's names, so they're posted as the bot
+        if let (Some(channel), PostSomething::Text(text)) = (self.caption_channel, &content) {
+            channel
+                .send_message(
+                    &self.http,
+                    CreateMessage::new()
+                        .content(text.as_ref())
+                        .allowed_mentions(CreateAllowedMentions::new()),
+                )
+                .await?;
+            return Ok(());
+        }
         if cfg!(feature = "send_to_thread") {
             if self.transcription_thread.is_failed() {
                 return Ok(());
@@ -350,7 +372,7 @@ impl MessageReference {
     }
     #[cfg(not(feature = "new-controls"))]
     fn get_ars(settings: &SettingsData) -> Vec<CreateActionRow> {
-        vec![
+        let mut rows = vec![
             CreateActionRow::Buttons(vec![
                 CreateButton::new("volume")
                     .style(ButtonStyle::Primary)
@@ -405,6 +427,13 @@ impl MessageReference {
                 CreateButton::new("stop")
                     .style(ButtonStyle::Danger)
                     .label("⏹️"),
+                CreateButton::new("tts_clear")
+                    .style(if settings.tts_waiting > 0 {
+                        ButtonStyle::Danger
+                    } else {
+                        ButtonStyle::Secondary
+                    })
+                    .label("🔇"),
             ]),
             CreateActionRow::Buttons(vec![
                 CreateButton::new("looped")
@@ -448,7 +477,11 @@ impl MessageReference {
                     })
                     .label("🗣️"),
             ]),
-        ]
+        ];
+        if let Some(row) = Self::station_select(settings) {
+            rows.push(row);
+        }
+        rows
     }
     #[cfg(feature = "new-controls")]
     fn get_ars(settings: &SettingsData) -> Vec<CreateActionRow> {
@@ -485,6 +518,8 @@ impl MessageReference {
             .description(if settings.pause { "▶️" } else { "⏸️" }),
             CreateSelectMenuOption::new("Skip", "skip").description("⏭️"),
             CreateSelectMenuOption::new("Stop", "stop").description("⏹️"),
+            CreateSelectMenuOption::new("Silence TTS", "tts_clear")
+                .description(format!("🔇 {} waiting", settings.tts_waiting)),
             CreateSelectMenuOption::new(
                 if settings.looped {
                     "Queue Looped"
@@ -545,12 +580,42 @@ impl MessageReference {
         if !settings.log_empty {
             options.push(CreateSelectMenuOption::new("Log", "log").description("📜"));
         }
-        vec![CreateActionRow::SelectMenu(
+        let mut rows = vec![CreateActionRow::SelectMenu(
             CreateSelectMenu::new("::controls", CreateSelectMenuKind::String { options })
                 .placeholder("Bot Controls")
                 .max_values(1)
                 .min_values(1),
-        )]
+        )];
+        if let Some(row) = Self::station_select(settings) {
+            rows.push(row);
+        }
+        rows
+    }
+    // only shown while idle, the value is handled by the station: arm of the control panel
+    fn station_select(settings: &SettingsData) -> Option<CreateActionRow> {
+        if !settings.idle || settings.radio_stations.is_empty() {
+            return None;
+        }
+        let options = settings
+            .radio_stations
+            .iter()
+            // names saved before the limit was lowered would make discord reject the whole panel
+            .filter(|name| name.chars().count() <= long_term_storage::RadioPreset::MAX_NAME_LENGTH)
+            .take(25)
+            .map(|name| {
+                CreateSelectMenuOption::new(name.as_ref(), format!("station:{}", name))
+                    .default_selection(settings.current_station.as_ref() == Some(name))
+            })
+            .collect::<Vec<_>>();
+        if options.is_empty() {
+            return None;
+        }
+        Some(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new("::radio_station", CreateSelectMenuKind::String { options })
+                .placeholder("Radio Station")
+                .max_values(1)
+                .min_values(1),
+        ))
     }
     fn filter_bar_emojis(string: &str) -> String {
         let mut str = string.to_owned();
@@ -647,8 +712,20 @@ impl RawMessage {
         // msg: &Message,
         text: String,
         voice: &TTSVoice,
+        backends: &[TtsBackendKind],
+        dictionary: &Dictionary,
+        guild: Option<&long_term_storage::Guild>,
+        user_id: Option<UserId>,
     ) -> Result<Video> {
-        Self::audio_handle(text, *voice).await
+        Self::audio_handle(
+            &Speech::text(&text),
+            *voice,
+            backends,
+            dictionary,
+            guild,
+            user_id,
+        )
+        .await
     }
     // pub async fn message(ctx: &Context, msg: &Message, voice: &TTSVoice) -> Result<Self> {
     //     let safecontent = msg.content_safe(&ctx.cache);
@@ -692,26 +769,47 @@ impl RawMessage {
     //         tts,
     //     })
     // }
-    async fn message(ctx: &Context, msg: impl AsRef<Message>, voice: &TTSVoice) -> Result<Video> {
-        let safecontent = msg.as_ref().content_safe(&ctx.cache);
-        let finder = linkify::LinkFinder::new();
-        let links: Vec<_> = finder.links(&safecontent).map(|l| l.as_str()).collect();
-        let mut safecontent = safecontent.replace("#0000", "");
-        let emojis = detect_emojis(&safecontent);
-        for emoji in emojis {
-            safecontent = safecontent.replace(&emoji.raw_emoji_text, &emoji.name);
-        }
-        let mut filteredcontent = safecontent.to_string();
-        for link in links {
-            filteredcontent = filteredcontent.replace(link, "");
-        }
-        filteredcontent = filteredcontent.trim().to_lowercase().to_string();
-        if filteredcontent.is_empty() {
+    // the guild's settings came along with the message and the author's were loaded to pick a
+    // voice, so neither is loaded again here
+    async fn message(
+        ctx: &Context,
+        msg: &long_term_storage::TtsMessage,
+        voice: &TTSVoice,
+        user: Option<&long_term_storage::User>,
+    ) -> Result<Video> {
+        let guild = msg.guild.as_ref();
+        let max_length = guild.and_then(|g| g.tts_filter.max_length);
+        let options = RenderOptions {
+            read_spoilers: guild.map_or(false, |g| g.read_spoilers),
+            timezone: user.map_or(common::chrono_tz::Tz::EST5EDT, |u| u.timezone),
+        };
+        let mut speech = common::tts::render(&msg.message, &ctx.cache, &options)
+            .rewrite(|t| t.to_lowercase());
+        if speech.is_empty() {
             return Err(anyhow::anyhow!("Message is empty"));
         }
-        if let Some(othermsg) = msg.as_ref().referenced_message.as_ref() {
-            filteredcontent = format!("Replying to {}:\n{}", othermsg.author.name, filteredcontent)
+        if let Some(max_length) = max_length {
+            if speech.truncate(max_length) {
+                speech.push(Segment::Pause);
+                speech.push_text("and so on");
+            }
         }
+        // a voice that speaks the language, unless we can't tell what it's in
+        let voice = match common::tts::detect_language(&speech.to_text()) {
+            Some(language) => common::tts::voice_for_language(language, *voice),
+            None => *voice,
+        };
+        let speech = match msg.referenced_message.as_ref() {
+            Some(othermsg) => {
+                let mut reply = Speech::text(&format!(
+                    "Replying to {}:\n",
+                    othermsg.author.display_name()
+                ));
+                reply.extend(speech);
+                reply
+            }
+            None => speech,
+        };
         // let channelname = match msg.channel(&ctx).await {
         //     Ok(Channel::Guild(channel)) => channel.name,
         //     Ok(Channel::Private(private)) => private.name(),
@@ -720,7 +818,17 @@ impl RawMessage {
         //         return Err(anyhow::anyhow!("Failed to get channel name"));
         //     }
         // };
-        Self::audio_handle(filteredcontent, *voice).await
+        let backends = long_term_storage::tts_backends(guild, user);
+        let dictionary = long_term_storage::tts_dictionary(guild, Some(ctx.cache.as_ref())).await;
+        Self::audio_handle(
+            &speech,
+            voice,
+            &backends,
+            &dictionary,
+            guild,
+            Some(msg.author.id),
+        )
+        .await
     }
     // pub fn audio_handle(
     //     text: String,
@@ -736,11 +844,23 @@ impl RawMessage {
     //     })
     // }
     async fn audio_handle(
-        text: String,
+        speech: &Speech,
         voice: TTSVoice,
+        backends: &[TtsBackendKind],
+        dictionary: &Dictionary,
+        guild: Option<&long_term_storage::Guild>,
+        user_id: Option<UserId>,
         // call: &Arc<Mutex<Call>>,
     ) -> Result<Video> {
-        common::youtube::get_tts(text, Some(voice)).await
+        long_term_storage::tts_speak_rendered(
+            speech,
+            Some(voice),
+            backends,
+            dictionary,
+            guild,
+            user_id,
+        )
+        .await
         // let res = crate::youtube::get_tts(text, key, Some(voice)).await?;
         // let handle = {
         //     let mut clock = call.lock().await;
@@ -749,38 +869,6 @@ impl RawMessage {
         // Ok(HandleMetadata::process_handle(HandleType::Tts(handle)).await?)
     }
 }
-fn detect_emojis(safecontent: &str) -> Vec<EmojiData> {
-    let mut emojis: Vec<EmojiData> = Vec::new();
-    let regex = match regex::Regex::new(r"<a?:([^:]+):\d+>") {
-        Ok(regex) => regex,
-        Err(e) => {
-            log::error!("Failed to create regex: {:?}", e);
-            return emojis;
-        }
-    };
-    for cap in regex.captures_iter(safecontent) {
-        let name = match cap.get(1) {
-            Some(name) => name.as_str(),
-            None => continue,
-        };
-        let raw_emoji_text = match cap.get(0) {
-            Some(text) => text.as_str(),
-            None => continue,
-        };
-        emojis.push(EmojiData {
-            name: name.to_string(),
-            raw_emoji_text: raw_emoji_text.to_string(),
-        });
-    }
-    emojis.sort_by(|a, b| a.name.cmp(&b.name));
-    emojis.dedup_by(|a, b| a.name == b.name);
-    emojis
-}
-#[derive(Debug, Clone)]
-pub struct EmojiData {
-    pub name: String,
-    pub raw_emoji_text: String,
-}
 // pub async fn get_transcribe_channel_handler(
 //     ctx: &Context,
 //     guild_id: &GuildId,
@@ -1,17 +1,24 @@
+use super::idle::{IdleAudio, IdlePlayer};
 use super::settingsdata::SettingsData;
 use super::transcribe::TranscriptionThread;
 use super::{AudioHandler, MessageReference};
 use crate::RawMessage;
 use common::anyhow::{self, Result};
-use common::audio::{AudioPromiseCommand, MetaCommand, OrAuto, SenderAndGuildId, SpecificVolume};
-use common::radio::{OriginalOrCustom, RadioData};
+use common::audio::{
+    AudioPromiseCommand, MetaCommand, OrAuto, RadioAnnouncements, RadioStation, SenderAndGuildId,
+    SpecificVolume,
+};
+use common::idle::IdlePlaylist;
+use common::radio::{IcyThread, OriginalOrCustom, RadioData, RadioHistoryEntry, RadioInfo};
 use common::serenity::all::{
-    ChannelId, Color, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Message, UserId,
+    ChannelId, Color, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, GuildId, Message,
+    MessageId, UserId,
 };
 use common::serenity::async_trait;
 use common::serenity::futures::stream::FuturesOrdered;
 use common::serenity::futures::StreamExt as _;
-use common::utils::{friendly_duration, OptionalTimeout};
+use common::tts::CacheStats;
+use common::utils::{friendly_duration, OptionalTimeout, TranscriptionMessage};
 use common::video::{Author, MetaVideo, Video, VideoType};
 use common::youtube::{self, TTSVoice};
 use common::{log, rand, songbird, tokio, PostSomething, WEB_CLIENT};
@@ -22,7 +29,7 @@ use songbird::driver::Bitrate;
 use songbird::input::{File, Input, YoutubeDl};
 use songbird::tracks::{Track, TrackHandle, TrackState};
 use songbird::{Call, EventContext};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::mem;
 use std::path::PathBuf;
@@ -42,10 +49,16 @@ pub struct ControlData {
     pub settings: SettingsData,
     pub log: Log,
 }
+const MAX_RADIO_HISTORY: usize = 20;
+// how often volumes are stepped during a crossfade, and how early a gapless handover starts
+const TRANSITION_TICK: Duration = Duration::from_millis(100);
+// the position is only watched every tick once the handover is this close, before then it sleeps
+const TRANSITION_MARGIN: Duration = Duration::from_secs(2);
 enum RadioCommand {
-    ChangeAudioUrl,
+    ChangeAudioUrl(Arc<str>),
     ChangeSource(Arc<str>),
     ResetSource,
+    ClearSource,
     Shutdown,
 }
 pub async fn the_lüüp(
@@ -59,6 +72,8 @@ pub async fn the_lüüp(
     let log = control.log.clone();
     let mut current_channel = control.msg.channel_id;
     log.log("Starting loop").await;
+    // the cache is shared by every guild, so this also counts anything said elsewhere meanwhile
+    let tts_cache = CacheStats::now();
     log.log("Creating control data").await;
     let guild_config = match long_term_storage::Guild::load(control.msg.guild_id).await {
         Ok(g) => g,
@@ -80,6 +95,7 @@ pub async fn the_lüüp(
     }
     let (msg_updater, update_msg) = mpsc::channel::<(SettingsData, EmbedData)>(8);
     let (change_channel, mut change_rx) = tokio::sync::broadcast::channel::<ChannelId>(1);
+    let (change_captions, mut captions_rx) = mpsc::unbounded_channel::<Option<ChannelId>>();
     let (manually_send, send_msg) = mpsc::unbounded_channel::<(PostSomething, UserId)>();
     let (killmsg, killrx) = tokio::sync::oneshot::channel::<()>();
     log.log("Spawning message updater").await;
@@ -117,6 +133,9 @@ pub async fn the_lüüp(
                             }
                         }
                     }
+                    Some(caption_channel) = captions_rx.recv() => {
+                        msg.change_caption_channel(caption_channel);
+                    }
                     manmsg = send_msg.recv() => {
                         if let Some((manmsg, user)) = manmsg {
                             if let Err(e) = msg.send_manually(manmsg, user).await {
@@ -134,14 +153,35 @@ pub async fn the_lüüp(
         })
     };
     let mut queue: Vec<SuperHandle> = Vec::new();
-    let mut generating_tts_queue: FuturesOrdered<JoinHandle<Vec<Video>>> = FuturesOrdered::new();
-    let mut tts_queue: FuturesOrdered<JoinHandle<Result<HandleMetadata>>> = FuturesOrdered::new();
+    // tts is tagged with a ticket so it can be dropped on the way through, song titles aren't
+    let mut generating_tts_queue: FuturesOrdered<JoinHandle<(u64, Vec<Video>)>> =
+        FuturesOrdered::new();
+    let mut tts_queue: FuturesOrdered<JoinHandle<(Option<u64>, Result<HandleMetadata>)>> =
+        FuturesOrdered::new();
     let mut current_tts: Option<HandleMetadata> = None;
+    // tts that hasn't started being read yet, oldest first, so it can be counted and cancelled
+    let mut pending_tts: Vec<PendingTts> = Vec::new();
+    // tickets cleared or cancelled while still in one of the queues, skipped when they come out
+    let mut dropped_tts: HashSet<u64> = HashSet::new();
+    let mut next_tts_ticket: u64 = 0;
+    let mut tts_paused = false;
     let mut current_song: Option<SuperHandle> = None;
     let mut current_handle: Option<HandleMetadata> = None;
+    // the previous song while it fades out under the current one
+    let mut fading_out: Option<TrackHandle> = None;
+    // when the current crossfade began, both volume ramps are driven off of this
+    let mut transition_started: Option<Instant> = None;
+    // set when the current song was let go early, the next song starts without waiting on its title
+    let mut handover: Option<Duration> = None;
+    let mut transition_tick = tokio::time::interval(TRANSITION_TICK);
+    // when to next look at how far through the current song is, None to look right away
+    let mut next_transition_check: Option<Instant> = None;
+    transition_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // songs added in one go (an album or playlist) share a batch so they can play gapless
+    let mut next_batch: usize = 0;
     let mut last_embed: Option<EmbedData> = None;
     let mut last_settings = None;
-    let mut nothing_handle: Option<TrackHandle> = None;
+    let mut nothing_handle: Option<IdleAudio> = None;
     let mut nothing_muted = false;
     // let mut ttsrx = common::global_data::transcribe::get_receiver(current_channel).await;
     let mut ttsrx = match long_term_storage::get_tts_receiver(current_channel).await {
@@ -151,6 +191,8 @@ pub async fn the_lüüp(
             return;
         }
     };
+    let (mut channel_muted_only, caption_channel) = load_channel_settings(current_channel).await;
+    set_caption_channel(&transcription, &change_captions, caption_channel);
     let mut assigned_voice: HashMap<UserId, TTSVoice> = HashMap::new();
     let mut voice_cycle: Vec<TTSVoice> = {
         let mut v = youtube::VOICES.clone();
@@ -234,7 +276,7 @@ pub async fn the_lüüp(
     let mut pending_disconnect = { OptionalTimeout::new(guild_config.empty_channel_timeout) };
     let mut custom_radio_audio_url: Option<Arc<str>> =
         guild_config.radio_audio_url.as_ref().map(Arc::clone);
-    let custom_video = if let Some(ref url) = custom_radio_audio_url {
+    let mut custom_video = if let Some(ref url) = custom_radio_audio_url {
         radio_data = None;
         Video::get_video(url.as_ref(), false, false)
             .await
@@ -243,19 +285,47 @@ pub async fn the_lüüp(
     } else {
         None
     };
+    let mut idle_playlist = match guild_config.idle_playlist {
+        Some(ref source) => match IdlePlaylist::load(source).await {
+            Ok(p) => Some(p),
+            Err(e) => {
+                log.log(&format!("Error loading idle playlist: {}\n", e)).await;
+                None
+            }
+        },
+        None => None,
+    };
+    // a preset switched to with /radio switch, only lasts for this session
+    let mut current_station: Option<RadioStation> = None;
+    // the radio thread has its own copy, this one is only for /radio
+    let mut current_data_url = guild_config.radio_data_url.as_ref().map(Arc::clone);
+    // sources other than azuracast don't keep a history so we track it ourselves
+    let mut radio_history: VecDeque<RadioHistoryEntry> = VecDeque::new();
+    // what was last announced over tts, so the same track isn't read twice
+    let mut last_announcement: Option<String> = None;
     let (radio_data_thread, message_radio_thread, mut recv_radio_data) = {
         let (tx, mut inner_rx) = tokio::sync::mpsc::channel::<RadioCommand>(1);
         let (inner_tx, rx) = tokio::sync::mpsc::unbounded_channel::<Arc<OriginalOrCustom>>();
         let mut custom_radio_data_url = guild_config.radio_data_url.as_ref().map(Arc::clone);
         let mut listen_to_custom = custom_radio_audio_url.is_some();
+        let mut custom_audio_url = custom_radio_audio_url.as_ref().map(Arc::clone);
         let handle = tokio::task::spawn({
             let log = log.clone();
+            // in-stream metadata, only used when there's a custom audio url without a data url
+            let mut icy: Option<IcyThread> = None;
             let mut last_custom_data = None;
             let mut last_azuracast_data = None;
             async move {
                 let mut interval = tokio::time::interval(Duration::from_secs(5));
                 let mut failures = 0;
                 loop {
+                    let wanted_icy = match (listen_to_custom, &custom_radio_data_url, &custom_audio_url) {
+                        (true, None, Some(url)) => Some(url),
+                        _ => None,
+                    };
+                    if icy.as_ref().map(|i| i.url()) != wanted_icy.map(|u| u.as_ref()) {
+                        icy = wanted_icy.map(|url| IcyThread::new(Arc::clone(url)));
+                    }
                     tokio::select! {
                         cmd = inner_rx.recv() => {
                             match cmd {
@@ -271,8 +341,9 @@ pub async fn the_lüüp(
                                     log.log("Radio thread received change source command").await;
                                     custom_radio_data_url = Some(url);
                                 }
-                                Some(RadioCommand::ChangeAudioUrl) => {
+                                Some(RadioCommand::ChangeAudioUrl(url)) => {
                                     log.log("Radio thread received change audio url command").await;
+                                    custom_audio_url = Some(url);
                                     listen_to_custom = true;
                                 }
                                 Some(RadioCommand::ResetSource) => {
@@ -285,6 +356,17 @@ pub async fn the_lüüp(
                                     }
                                     listen_to_custom = false;
                                 }
+                                Some(RadioCommand::ClearSource) => {
+                                    log.log("Radio thread received clear source command").await;
+                                    custom_radio_data_url = None;
+                                    last_custom_data = None;
+                                }
+                            }
+                        }
+                        Some(data) = icy_recv(icy.as_mut()) => {
+                            log.log("Got new icy metadata").await;
+                            if let Err(e) = inner_tx.send(data) {
+                                log.log(&format!("Error sending radio data: {}\n", e)).await;
                             }
                         }
                         Ok(new_data) = azuracast_updates.recv() => {
@@ -338,18 +420,64 @@ pub async fn the_lüüp(
     let mut rerun = OptionalTimeout::new(std::time::Duration::from_millis(10));
     let mut manually_set = ManuallySet::default();
     let mut empty_channel_timeout = guild_config.empty_channel_timeout;
+    let mut announcer = guild_config.announcer.clone();
     drop(guild_config);
     drop(global_config);
     rerun.begin_now();
     loop {
         control.settings.log_empty = log.is_empty().await;
+        // how long before the end of the current song the next one should start, if it's time to start watching for that
+        let upcoming_transition = match (
+            current_song.as_ref(),
+            current_handle.as_ref(),
+            queue.get(next_index),
+        ) {
+            (
+                Some(current),
+                Some(HandleMetadata {
+                    handle: HandleType::Song(_),
+                    ..
+                }),
+                Some(next),
+            ) if transition_started.is_none() && !control.settings.pause => {
+                let transitions = control.settings.transitions;
+                match current.duration {
+                    Some(_)
+                        if transitions.gapless
+                            && current.batch.is_some()
+                            && current.batch == next.batch =>
+                    {
+                        Some(Duration::ZERO)
+                    }
+                    // short songs shouldn't spend more than half their time fading
+                    Some(d) if !transitions.crossfade.is_zero() => Some(
+                        transitions
+                            .crossfade
+                            .min(Duration::from_secs_f64(d / 2.0)),
+                    ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if upcoming_transition.is_none() {
+            next_transition_check = None;
+        }
         tokio::select! {
             t = control.rx.recv() => {
+                // a seek or anything else could move the song along, so the estimate is redone
+                next_transition_check = None;
                 match t {
                     Some((snd, command)) => match command {
                         AudioPromiseCommand::Play(videos) => {
+                            let batch = if videos.len() > 1 {
+                                next_batch += 1;
+                                Some(next_batch)
+                            } else {
+                                None
+                            };
                             for v in videos {
-                                queue.push(match SuperHandle::new(&control.call, v, control.settings.song_volume()).await {
+                                queue.push(match SuperHandle::new(&control.call, v, control.settings.song_volume(), batch).await {
                                     Ok(h) => h,
                                     Err(e) => {
                                         log.log(&format!("Error creating handle: {}\n", e)).await;
@@ -391,6 +519,17 @@ pub async fn the_lüüp(
                                     control.settings.pause = val;
                                     let trackhandle = handle.get_handle();
                                     if control.settings.pause {
+                                        // a crossfade can't be paused halfway, so jump to the end of it
+                                        if let Some(old) = fading_out.take() {
+                                            if let Err(e) = old.stop() {
+                                                log.log(&format!("Error stopping faded track: {}\n", e)).await;
+                                            }
+                                        }
+                                        if transition_started.take().is_some() {
+                                            if let Err(e) = trackhandle.set_volume(control.settings.song_volume()) {
+                                                log.log(&format!("Error setting volume: {}\n", e)).await;
+                                            }
+                                        }
                                         if let Err(e) = trackhandle.pause() {
                                             log.log(&format!("Error pausing track: {}\n", e)).await;
                                         }
@@ -475,6 +614,13 @@ pub async fn the_lüüp(
                             }
                         }
                         AudioPromiseCommand::Skip => {
+                            if let Some(old) = fading_out.take() {
+                                if let Err(e) = old.stop() {
+                                    log.log(&format!("Error stopping faded track: {}\n", e)).await;
+                                }
+                            }
+                            transition_started = None;
+                            handover = None;
                             if let Some(trackhandle) = current_song.take() {
                                 log.log(&format!("Skipping track on line {}", line!())).await;
                                 trackhandle.stop(&log).await;
@@ -564,6 +710,86 @@ pub async fn the_lüüp(
                                 log.log(&format!("Error responding to command{}\n", e)).await;
                             }
                         }
+                        AudioPromiseCommand::SwitchRadio(station) => {
+                            custom_radio_audio_url = Some(Arc::clone(&station.audio_url));
+                            custom_video = None;
+                            radio_data = None;
+                            if let Some(handle) = nothing_handle.take() {
+                                if let Err(e) = handle.stop() {
+                                    log.log(&format!("Error stopping nothing: {}\n", e)).await;
+                                }
+                            }
+                            if let Err(e) = message_radio_thread.send(RadioCommand::ChangeAudioUrl(Arc::clone(&station.audio_url))).await {
+                                log.log(&format!("Error sending radio command: {}\n", e)).await;
+                            }
+                            let source = match station.data_url {
+                                Some(ref url) => RadioCommand::ChangeSource(Arc::clone(url)),
+                                None => RadioCommand::ClearSource,
+                            };
+                            if let Err(e) = message_radio_thread.send(source).await {
+                                log.log(&format!("Error sending radio command: {}\n", e)).await;
+                            }
+                            if let Err(e) = snd.send(format!("Switched to `{}`", station.name).into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            control.settings.current_station = Some(Arc::clone(&station.name));
+                            current_data_url = station.data_url.as_ref().map(Arc::clone);
+                            radio_history.clear();
+                            current_station = Some(station);
+                        }
+                        AudioPromiseCommand::TtsSkip => {
+                            let response = match current_tts.take() {
+                                Some(tts) => {
+                                    if let Err(e) = tts.get_handle().stop() {
+                                        log.log(&format!("Error stopping tts: {}\n", e)).await;
+                                    }
+                                    "Skipped the message being read"
+                                }
+                                None => "Nothing is being read",
+                            };
+                            if let Err(e) = snd.send(response.into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                        }
+                        AudioPromiseCommand::TtsClear => {
+                            let cleared = pending_tts.len();
+                            dropped_tts.extend(pending_tts.drain(..).map(|p| p.ticket));
+                            if let Some(tts) = current_tts.take() {
+                                if let Err(e) = tts.get_handle().stop() {
+                                    log.log(&format!("Error stopping tts: {}\n", e)).await;
+                                }
+                            }
+                            if let Err(e) = snd.send(format!("Cleared `{}` waiting messages", cleared).into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                        }
+                        AudioPromiseCommand::TtsPause(paused) => {
+                            let val = paused.get_val(tts_paused);
+                            if tts_paused != val {
+                                tts_paused = val;
+                                if let Some(tts) = current_tts.as_ref() {
+                                    let res = if tts_paused { tts.get_handle().pause() } else { tts.get_handle().play() };
+                                    if let Err(e) = res {
+                                        log.log(&format!("Error pausing tts: {}\n", e)).await;
+                                    }
+                                }
+                            }
+                            if let Err(e) = snd.send(format!("TTS paused set to `{}`", tts_paused).into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                        }
+                        AudioPromiseCommand::TtsCancel(message) => {
+                            let cancelled = pending_tts.iter().position(|p| p.message == Some(message)).map(|i| pending_tts.remove(i));
+                            let response = match cancelled {
+                                Some(p) => {
+                                    dropped_tts.insert(p.ticket);
+                                    "Cancelled"
+                                }
+                                None => "That message isn't waiting to be read",
+                            };
+                            // deleted messages don't wait around for an answer
+                            let _ = snd.send(response.into());
+                        }
                         AudioPromiseCommand::SetBitrate(bitrate) => {
                             let mut cl = control.call.lock().await;
                             control.settings.bitrate = bitrate;
@@ -612,6 +838,23 @@ pub async fn the_lüüp(
                             }
                             log.clear_until(end).await;
                         }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::RetrieveRadioInfo(info_snd)) => {
+                            let history = radio_data
+                                .as_ref()
+                                .and_then(|d| d.history())
+                                .unwrap_or_else(|| radio_history.iter().cloned().collect());
+                            if let Err(e) = info_snd
+                                .send(RadioInfo {
+                                    data_url: current_data_url.as_ref().map(Arc::clone),
+                                    history,
+                                })
+                                .await {
+                                log.log(&format!("Error sending radio info: {}\n", e)).await;
+                            }
+                            if let Err(e) = snd.send("Radio info sent!".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                        }
                         AudioPromiseCommand::MetaCommand(MetaCommand::ChangeDefaultRadioVolume(v)) => {
                             if let Err(e) = snd.send("Ack".into()) {
                                 log.log(&format!("Error responding to command{}\n", e)).await;
@@ -646,6 +889,23 @@ pub async fn the_lüüp(
                             }
                             control.settings.talk_over_eachother = v;
                         }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ChangeRadioAnnouncements(v)) => {
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            control.settings.radio_announcements = v;
+                        }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ChangeTransitions(v)) => {
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            control.settings.transitions = v;
+                            if let Some(ref handle) = nothing_handle {
+                                if let Err(e) = handle.set_transitions(v) {
+                                    log.log(&format!("Error changing idle transitions: {}\n", e)).await;
+                                }
+                            }
+                        }
                         AudioPromiseCommand::MetaCommand(MetaCommand::ChangeReadTitles(v)) => {
                             if let Err(e) = snd.send("Ack".into()) {
                                 log.log(&format!("Error responding to command{}\n", e)).await;
@@ -658,13 +918,15 @@ pub async fn the_lüüp(
                             if let Err(e) = snd.send("Ack".into()) {
                                 log.log(&format!("Error responding to command{}\n", e)).await;
                             }
-                            custom_radio_audio_url = Some(url);
+                            custom_radio_audio_url = Some(Arc::clone(&url));
+                            current_station = None;
+                            control.settings.current_station = None;
                             if let Some(handle) = nothing_handle.take() {
                                 if let Err(e) = handle.stop() {
                                     log.log(&format!("Error stopping nothing: {}\n", e)).await;
                                 }
                             }
-                            if let Err(e) = message_radio_thread.send(RadioCommand::ChangeAudioUrl).await {
+                            if let Err(e) = message_radio_thread.send(RadioCommand::ChangeAudioUrl(url)).await {
                                 log.log(&format!("Error sending radio command: {}\n", e)).await;
                             }
                         }
@@ -672,6 +934,7 @@ pub async fn the_lüüp(
                             if let Err(e) = snd.send("Ack".into()) {
                                 log.log(&format!("Error responding to command{}\n", e)).await;
                             }
+                            current_data_url = Some(Arc::clone(&url));
                             if let Err(e) = message_radio_thread.send(RadioCommand::ChangeSource(url)).await {
                                 log.log(&format!("Error sending radio command: {}\n", e)).await;
                             }
@@ -683,6 +946,9 @@ pub async fn the_lüüp(
                             if let Err(e) = message_radio_thread.send(RadioCommand::ResetSource).await {
                                 log.log(&format!("Error sending radio command: {}\n", e)).await;
                             }
+                            current_station = None;
+                            control.settings.current_station = None;
+                            current_data_url = None;
                             if custom_radio_audio_url.take().is_some() {
                                 if let Some(handle) = nothing_handle.take() {
                                     if let Err(e) = handle.stop() {
@@ -691,6 +957,29 @@ pub async fn the_lüüp(
                                 }
                             }
                         }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ChangeIdlePlaylist(source)) => {
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            idle_playlist = match source {
+                                Some(source) => match IdlePlaylist::load(&source).await {
+                                    Ok(p) => Some(p),
+                                    Err(e) => {
+                                        log.log(&format!("Error loading idle playlist: {}\n", e)).await;
+                                        None
+                                    }
+                                },
+                                None => None,
+                            };
+                            // a custom radio takes priority over the playlist, so only restart the idle audio if there isn't one
+                            if custom_radio_audio_url.is_none() {
+                                if let Some(handle) = nothing_handle.take() {
+                                    if let Err(e) = handle.stop() {
+                                        log.log(&format!("Error stopping nothing: {}\n", e)).await;
+                                    }
+                                }
+                            }
+                        }
                         AudioPromiseCommand::MetaCommand(MetaCommand::ChangeAloneTimeout(time)) => {
                             if let Err(e) = snd.send("Ack".into()) {
                                 log.log(&format!("Error responding to command{}\n", e)).await;
@@ -698,6 +987,24 @@ pub async fn the_lüüp(
                             empty_channel_timeout = time;
                             pending_disconnect.set_duration(time);
                         }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ChangeMutedOnly(v)) => {
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            channel_muted_only = v;
+                        }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ChangeCaptionChannel(channel)) => {
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                            set_caption_channel(&transcription, &change_captions, channel);
+                        }
+                        AudioPromiseCommand::MetaCommand(MetaCommand::ReloadAnnouncer) => {
+                            announcer = long_term_storage::announcer(Some(control.msg.guild_id)).await;
+                            if let Err(e) = snd.send("Ack".into()) {
+                                log.log(&format!("Error responding to command{}\n", e)).await;
+                            }
+                        }
                     },
                     None => {
                         log.log("rx closed").await;
@@ -721,6 +1028,22 @@ pub async fn the_lüüp(
                     continue
                 };
                 log::trace!("Playing next audio");
+                if let Some(lead) = handover.take() {
+                    // the song starts right away, so the title is read over it like any other message, gapless albums skip it
+                    match superhandle.take_title().await {
+                        Ok(Some(title)) => {
+                            if control.settings.read_titles && !lead.is_zero() {
+                                tts_queue.push_back(tokio::task::spawn(async move { (None, Ok(title)) }));
+                            } else if let Err(e) = title.get_handle().stop() {
+                                log.log(&format!("Error stopping tts: {}\n", e)).await;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            log.log(&format!("Error getting title: {}\n", e)).await;
+                        }
+                    }
+                }
                 match superhandle.next_audio(control.settings.read_titles).await {
                     Ok(Some(next_song)) => {
                         log::info!("Playing next audio");
@@ -747,7 +1070,11 @@ pub async fn the_lüüp(
                             }
                         };
                         log::trace!("Setting volume");
-                        if let Err(e) = next_song.get_handle().set_volume(control.settings.song_volume()) {
+                        let volume = match transition_started {
+                            Some(started) => control.settings.song_volume() * fade_progress(started, control.settings.transitions.crossfade),
+                            None => control.settings.song_volume(),
+                        };
+                        if let Err(e) = next_song.get_handle().set_volume(volume) {
                             log.log(&format!("Error setting volume: {}\n", e)).await;
                         }
                         log::trace!("Storing current handle and song");
@@ -763,6 +1090,62 @@ pub async fn the_lüüp(
                     }
                 }
             }
+            _ = transition_wait(transition_started.is_some(), upcoming_transition.map(|_| next_transition_check.unwrap_or_else(Instant::now)), &mut transition_tick) => {
+                if let (Some(lead), Some(handle), Some(song)) = (upcoming_transition, current_handle.as_ref(), current_song.as_ref()) {
+                    match handle.get_handle().get_info().await {
+                        Ok(state) => {
+                            let remaining = song.duration.unwrap_or_default() - state.position.as_secs_f64();
+                            // gapless handovers go one tick early so the next song is already running when this one ends
+                            if remaining <= lead.max(TRANSITION_TICK).as_secs_f64() {
+                                log.log("Handing over to the next song").await;
+                                if let Some(old) = fading_out.take() {
+                                    if let Err(e) = old.stop() {
+                                        log.log(&format!("Error stopping faded track: {}\n", e)).await;
+                                    }
+                                }
+                                // no longer listening to its events, it finishes or fades out on its own
+                                if let Some(old) = current_handle.take() {
+                                    if !lead.is_zero() {
+                                        fading_out = Some(old.get_handle().clone());
+                                        transition_started = Some(Instant::now());
+                                    }
+                                }
+                                current_song = None;
+                                handover = Some(lead);
+                            } else {
+                                let until_close = remaining - (lead + TRANSITION_MARGIN).as_secs_f64();
+                                next_transition_check = Some(Instant::now() + if until_close > 0.0 { Duration::from_secs_f64(until_close) } else { TRANSITION_TICK });
+                            }
+                        }
+                        Err(e) => {
+                            log.log(&format!("Error getting track info: {}\n", e)).await;
+                            next_transition_check = Some(Instant::now() + TRANSITION_MARGIN);
+                        }
+                    }
+                }
+                if let Some(started) = transition_started {
+                    let progress = fade_progress(started, control.settings.transitions.crossfade);
+                    let volume = control.settings.song_volume();
+                    if let Some(ref old) = fading_out {
+                        if let Err(e) = old.set_volume(volume * (1.0 - progress)) {
+                            log.log(&format!("Error setting volume: {}\n", e)).await;
+                        }
+                    }
+                    if let Some(HandleMetadata { handle: HandleType::Song(ref new), .. }) = current_handle {
+                        if let Err(e) = new.set_volume(volume * progress) {
+                            log.log(&format!("Error setting volume: {}\n", e)).await;
+                        }
+                    }
+                    if progress >= 1.0 {
+                        if let Some(old) = fading_out.take() {
+                            if let Err(e) = old.stop() {
+                                log.log(&format!("Error stopping faded track: {}\n", e)).await;
+                            }
+                        }
+                        transition_started = None;
+                    }
+                }
+            }
             msg = get_message(current_handle.as_mut(), current_song.as_mut()) => {
                 match msg {
                     Ok((_handle, msg, current, song)) => {
@@ -771,6 +1154,27 @@ pub async fn the_lüüp(
                             SimpleTrackEvent::SongFinished => {
                                 log.log("Track finished").await;
                                 current_song = None;
+                                // looping, repeating or autoplay keep the music going, so it isn't really the end
+                                let keeps_going = control.settings.looped || control.settings.repeat || control.settings.autoplay;
+                                if queue.is_empty() && announcer.queue_finished && !keeps_going {
+                                    let announcer = announcer.clone();
+                                    let guild_id = control.msg.guild_id;
+                                    let ctx = planet_ctx.clone();
+                                    let ticket = next_tts_ticket;
+                                    next_tts_ticket += 1;
+                                    pending_tts.push(PendingTts { ticket, message: None });
+                                    generating_tts_queue.push_back(tokio::task::spawn(async move {
+                                        let guild = long_term_storage::tts_guild(Some(guild_id)).await;
+                                        let backends = announcer.backends(long_term_storage::tts_backends(guild.as_ref(), None));
+                                        let dictionary = long_term_storage::tts_dictionary(guild.as_ref(), Some(ctx.cache.as_ref())).await;
+                                        let announcement = RawMessage::announcement("That's the end of the queue".to_owned(), &announcer.voice.unwrap_or_default(), &backends, &dictionary, guild.as_ref(), None)
+                                            .await
+                                            .ok()
+                                            .into_iter()
+                                            .collect();
+                                        (ticket, announcement)
+                                    }));
+                                }
                             }
                             SimpleTrackEvent::SongError(e) => {
                                 log.log(&format!("Error playing track: {}\n", e)).await;
@@ -890,6 +1294,9 @@ pub async fn the_lüüp(
                                 }
                                 log.log("Channel changed, updating it as well as the tts receiver").await;
                                 current_channel = channel;
+                                let caption_channel;
+                                (channel_muted_only, caption_channel) = load_channel_settings(current_channel).await;
+                                set_caption_channel(&transcription, &change_captions, caption_channel);
                                 match long_term_storage::get_tts_receiver(current_channel).await {
                                     Ok(r) => {
                                         ttsrx = r;
@@ -971,6 +1378,9 @@ pub async fn the_lüüp(
                 }
                 pending_disconnect.end_now();
             }
+            Some(()) = idle_changed(nothing_handle.as_mut()) => {
+                log::trace!("Idle playlist moved on to the next track");
+            }
             _ = &mut rerun => {
                 log.log("Force rerun").await;
                 rerun.end_now();
@@ -982,14 +1392,93 @@ pub async fn the_lüüp(
             }
             Some(data) = recv_radio_data.recv() => {
                 log.log("Got new radio data").await;
+                let config = common::get_config();
+                let custom_url = custom_radio_audio_url
+                    .as_ref()
+                    .map(|u| u.as_ref())
+                    .unwrap_or(&config.idle_url);
+                let title = data.now_playing_title(custom_url);
+                if data.history().is_none() {
+                    if radio_history.front().map(|h| h.title != title).unwrap_or(true) {
+                        radio_history.push_front(RadioHistoryEntry {
+                            played_at: common::chrono::Utc::now().timestamp(),
+                            title: title.to_owned(),
+                            artist: data.now_playing_artist(custom_url).map(|a| a.to_owned()),
+                            art: data.now_playing_art(custom_url).map(|a| a.to_owned()),
+                        });
+                        radio_history.truncate(MAX_RADIO_HISTORY);
+                    }
+                }
+                // data for the other source (original vs custom) isn't what's playing
+                let playlist_playing = nothing_handle.as_ref().map(|h| h.is_playlist()).unwrap_or(false);
+                if custom_radio_audio_url.is_none() == data.is_original() && !playlist_playing && control.settings.radio_announcements != RadioAnnouncements::Off {
+                    let (key, text) = match control.settings.radio_announcements {
+                        RadioAnnouncements::TitlesOnly => (title.to_owned(), format!("Now playing {}", title)),
+                        _ => {
+                            let station = data.station_name(custom_url);
+                            let artist = data.now_playing_artist(custom_url);
+                            (
+                                format!("{}\n{}\n{}", station, title, artist.unwrap_or_default()),
+                                format!(
+                                    "Now on {}: {}{}",
+                                    station,
+                                    title,
+                                    match artist {
+                                        Some(artist) => format!(" by {}", artist),
+                                        None => "".to_owned(),
+                                    }
+                                ),
+                            )
+                        }
+                    };
+                    if last_announcement.as_ref() != Some(&key) {
+                        // the first data we get is whatever was already playing when we joined
+                        let first = last_announcement.is_none();
+                        last_announcement = Some(key);
+                        if !first && queue.is_empty() && current_song.is_none() {
+                            let guild_id = control.msg.guild_id;
+                            let ctx = planet_ctx.clone();
+                            let ticket = next_tts_ticket;
+                            next_tts_ticket += 1;
+                            pending_tts.push(PendingTts { ticket, message: None });
+                            let announcer = announcer.clone();
+                            generating_tts_queue.push_back(tokio::task::spawn(async move {
+                                let guild = long_term_storage::tts_guild(Some(guild_id)).await;
+                                let backends = announcer.backends(long_term_storage::tts_backends(guild.as_ref(), None));
+                                let dictionary = long_term_storage::tts_dictionary(guild.as_ref(), Some(ctx.cache.as_ref())).await;
+                                let announcement = RawMessage::announcement(text, &announcer.voice.unwrap_or_default(), &backends, &dictionary, guild.as_ref(), None)
+                                    .await
+                                    .ok()
+                                    .into_iter()
+                                    .collect();
+                                (ticket, announcement)
+                            }));
+                        }
+                    }
+                }
                 radio_data = Some(data);
             }
             msg = ttsrx.recv() => {
                 match msg {
                     Ok(msg) => {
-                        let mut voice_preference = {
-                            long_term_storage::User::load(msg.author.id).await.map(|u| u.voice_preference).unwrap_or_default()
+                        let user = long_term_storage::User::load(msg.author.id).await.ok();
+                        // the guild's tts filter was already applied before the message was sent here
+                        let rejected = match user {
+                            Some(ref u) if u.tts_opt_out => Some("opted out"),
+                            _ => None,
+                        };
+                        let rejected = match rejected {
+                            None => {
+                                let muted_only = channel_muted_only || user.as_ref().map_or(false, |u| u.tts_muted_only);
+                                speaking_for_themselves(&msg, control.msg.guild_id, current_channel, muted_only).await.then_some("not muted")
+                            }
+                            reason => reason,
                         };
+                        if let Some(reason) = rejected {
+                            log::trace!("Not reading message {}: {}", msg.id, reason);
+                            continue;
+                        }
+                        let (mut voice_preference, picked_voice) = user.as_ref().map(|u| (u.voice_preference, u.voice)).unwrap_or_default();
                         match voice_preference {
                             VoicePreference::NoPreference => {}
                             VoicePreference::Male => {
@@ -1003,12 +1492,25 @@ pub async fn the_lüüp(
                                 }
                             }
                         }
+                        // a voice they picked themselves beats anything we'd hand out
+                        if let Some(picked) = picked_voice {
+                            voice_preference = VoicePreference::NoPreference;
+                            if assigned_voice.get(&msg.author.id).map(|v| v.name) != Some(picked.name) {
+                                assigned_voice.remove(&msg.author.id);
+                            }
+                        }
                         let mut voice = match assigned_voice.get(&msg.author.id) {
                             Some(v) => TTSVoiceState::Existing(*v),
                             None => {
-                                let v = voice_cycle.remove(0);
+                                let v = match picked_voice {
+                                    Some(picked) => picked,
+                                    None => {
+                                        let v = voice_cycle.remove(0);
+                                        voice_cycle.push(v);
+                                        v
+                                    }
+                                };
                                 assigned_voice.insert(msg.author.id, v);
-                                voice_cycle.push(v);
                                 TTSVoiceState::New(v)
                             }
                         };
@@ -1036,9 +1538,12 @@ pub async fn the_lüüp(
                                 }
                             }
                         }
+                        let ticket = next_tts_ticket;
+                        next_tts_ticket += 1;
+                        pending_tts.push(PendingTts { ticket, message: Some(msg.id) });
                         generating_tts_queue.push_back({
                             let ctx = planet_ctx.clone();
-                            tokio::task::spawn(generate_tts(ctx, msg, voice))
+                            tokio::task::spawn(async move { (ticket, generate_tts(ctx, msg, voice, user).await) })
                         });
                     }
                     Err(e) => {
@@ -1046,30 +1551,50 @@ pub async fn the_lüüp(
                     }
                 }
             }
-            result = if_then(current_tts.is_none() || control.settings.talk_over_eachother, &mut tts_queue) => {
+            result = if_then((current_tts.is_none() || control.settings.talk_over_eachother) && !tts_paused, &mut tts_queue) => {
                 match result {
-                    Ok(Ok(res)) => {
-                        if let Err(e) = res.get_handle().play() {
-                            log.log(&format!("Error playing tts: {}\n", e)).await;
-                        } else {
-                            current_tts = Some(res);
-                        };
+                    Ok((Some(ticket), Ok(res))) if dropped_tts.contains(&ticket) => {
+                        // loaded paused, it has to be stopped to leave the call
+                        if let Err(e) = res.get_handle().stop() {
+                            log.log(&format!("Error stopping tts: {}\n", e)).await;
+                        }
                     }
-                    Ok(Err(e)) => {
-                        log.log(&format!("Error getting tts: {}\n", e)).await;
+                    Ok((ticket, res)) => {
+                        pending_tts.retain(|p| Some(p.ticket) != ticket);
+                        match res {
+                            Ok(res) => {
+                                if let Err(e) = res.get_handle().play() {
+                                    log.log(&format!("Error playing tts: {}\n", e)).await;
+                                } else {
+                                    current_tts = Some(res);
+                                };
+                            }
+                            Err(e) => {
+                                log.log(&format!("Error getting tts: {}\n", e)).await;
+                            }
+                        }
                     }
                     Err(e) => {
                         log.log(&format!("Error awaiting tts: {}\n", e)).await;
                     }
                 }
+                if tts_queue.is_empty() && generating_tts_queue.is_empty() {
+                    dropped_tts.clear();
+                }
             }
             result = then(&mut generating_tts_queue) => {
                 match result {
-                    Ok(res) => {
+                    Ok((ticket, _)) if dropped_tts.contains(&ticket) => {
+                        dropped_tts.remove(&ticket);
+                    }
+                    Ok((ticket, res)) => {
+                        if res.is_empty() {
+                            pending_tts.retain(|p| p.ticket != ticket);
+                        }
                         for r in res {
                             tts_queue.push_back({
                                 let call = Arc::clone(&control.call);
-                                tokio::task::spawn(video_to_handle(r, call))
+                                tokio::task::spawn(async move { (Some(ticket), video_to_handle(r, call).await) })
                             });
                         }
                     }
@@ -1103,7 +1628,19 @@ pub async fn the_lüüp(
             }
         }
         let mut embed = EmbedData::default();
-        if queue.is_empty() && current_song.is_none() {
+        // shown when there is no radio data for whatever is playing
+        let fallback_title = match current_station {
+            Some(ref station) => Some(station.name.to_string()),
+            None => custom_video.as_ref().map(|v| v.get_title().to_string()),
+        };
+        let fallback_art = current_station
+            .as_ref()
+            .and_then(|s| s.art_url.as_ref())
+            .map(|u| u.to_string());
+        control.settings.idle = queue.is_empty() && current_song.is_none();
+        control.settings.tts_waiting = pending_tts.len();
+        control.settings.tts_paused = tts_paused;
+        if control.settings.idle {
             control.settings.pause = false;
             if let Some(handle) = nothing_handle.as_mut() {
                 nothing_muted = false;
@@ -1116,26 +1653,50 @@ pub async fn the_lüüp(
                 // } else {
                 //     YoutubeDl::new(crate::WEB_CLIENT.clone(), crate::config::get_config().idle_url).into()
                 // };
-                let r: Input = match (
+                let r: Option<Input> = match (
                     control.nothing_uri.as_ref(),
                     custom_radio_audio_url.as_ref(),
+                    idle_playlist.as_ref(),
                 ) {
-                    (_, Some(uri)) => YoutubeDl::new(WEB_CLIENT.clone(), uri.to_string()).into(),
-                    (Some(uri), _) => File::new(uri.clone()).into(),
-                    _ => YoutubeDl::new(WEB_CLIENT.clone(), common::get_config().idle_url).into(),
+                    (_, Some(uri), _) => Some(YoutubeDl::new(WEB_CLIENT.clone(), uri.to_string()).into()),
+                    (_, None, Some(_)) => None,
+                    (Some(uri), _, _) => Some(File::new(uri.clone()).into()),
+                    _ => Some(YoutubeDl::new(WEB_CLIENT.clone(), common::get_config().idle_url).into()),
                 };
-                {
-                    let mut clock = control.call.lock().await;
-                    let handle = clock.play(
-                        Track::new(r)
-                            .volume(control.settings.radio_volume())
-                            .loops(songbird::tracks::LoopState::Infinite),
-                    );
-                    nothing_handle = Some(handle);
+                match (r, idle_playlist.as_ref()) {
+                    (Some(r), _) => {
+                        let mut clock = control.call.lock().await;
+                        let handle = clock.play(
+                            Track::new(r)
+                                .volume(control.settings.radio_volume())
+                                .loops(songbird::tracks::LoopState::Infinite),
+                        );
+                        nothing_handle = Some(IdleAudio::Looping(handle));
+                    }
+                    (None, Some(playlist)) => {
+                        nothing_handle = Some(IdleAudio::Playlist(IdlePlayer::new(
+                            Arc::clone(&control.call),
+                            playlist.clone(),
+                            control.settings.radio_volume(),
+                            control.settings.transitions,
+                        )));
+                    }
+                    (None, None) => {}
                 }
             }
             let mut possible_body = "Queue is empty, use `/add` to play something!".to_owned();
-            if let Some(ref data) = radio_data {
+            let idle_tags = nothing_handle.as_ref().and_then(|h| h.tags());
+            if nothing_handle.as_ref().map(|h| h.is_playlist()).unwrap_or(false) {
+                possible_body = format!(
+                    "{}\nIn the meantime, enjoy some tunes from `{}`",
+                    possible_body,
+                    idle_playlist.as_ref().map(|p| p.source()).unwrap_or_else(|| "the idle playlist".into())
+                );
+                if let Some(tags) = idle_tags {
+                    embed.fields.push(("Now Playing".to_owned(), tags.describe(), false));
+                    embed.thumbnail = tags.art;
+                }
+            } else if let Some(ref data) = radio_data {
                 if custom_radio_audio_url.is_none() == data.is_original() {
                     let config = common::get_config();
                     let custom_url = custom_radio_audio_url
@@ -1175,26 +1736,23 @@ pub async fn the_lüüp(
                             true,
                         ));
                     }
-                } else if let Some(ref video) = custom_video {
-                    embed.fields.push((
-                        "Now Playing".to_owned(),
-                        video.get_title().to_string(),
-                        false,
-                    ));
+                } else if let Some(title) = fallback_title {
+                    embed.fields.push(("Now Playing".to_owned(), title, false));
+                    embed.thumbnail = fallback_art;
                 }
-            } else if let Some(ref video) = custom_video {
-                embed.fields.push((
-                    "Now Playing".to_owned(),
-                    video.get_title().to_string(),
-                    false,
-                ));
+            } else if let Some(title) = fallback_title {
+                embed.fields.push(("Now Playing".to_owned(), title, false));
+                embed.thumbnail = fallback_art;
             };
             if !possible_body.is_empty() {
                 embed.body = Some(possible_body);
             }
             embed.color = Some(Color::from_rgb(184, 29, 19));
         } else {
-            if let Some(ref data) = radio_data {
+            if let Some(tags) = nothing_handle.as_ref().and_then(|h| h.tags()) {
+                embed.author = Some(format!("{} playing in the background", tags.describe()));
+                embed.author_icon_url = tags.art;
+            } else if let Some(ref data) = radio_data {
                 if custom_radio_audio_url.is_none() == data.is_original() {
                     let config = common::get_config();
                     let custom_url = custom_radio_audio_url
@@ -1212,11 +1770,13 @@ pub async fn the_lüüp(
                     ));
                     embed.author_icon_url =
                         data.now_playing_art(custom_url).map(|url| url.to_string());
-                } else if let Some(ref video) = custom_video {
-                    embed.author = Some(video.get_title().to_string());
+                } else if let Some(title) = fallback_title {
+                    embed.author = Some(title);
+                    embed.author_icon_url = fallback_art;
                 };
-            } else if let Some(ref video) = custom_video {
-                embed.author = Some(video.get_title().to_string());
+            } else if let Some(title) = fallback_title {
+                embed.author = Some(title);
+                embed.author_icon_url = fallback_art;
             }
             if let Some(handle) = nothing_handle.as_mut() {
                 nothing_muted = true;
@@ -1324,6 +1884,16 @@ pub async fn the_lüüp(
                 embed.body = Some(possible_body);
             }
         }
+        if !pending_tts.is_empty() || tts_paused {
+            embed.fields.push((
+                format!("TTS{}", if tts_paused { " | Paused" } else { "" }),
+                match pending_tts.len() {
+                    1 => "1 message waiting".to_owned(),
+                    n => format!("{} messages waiting", n),
+                },
+                false,
+            ));
+        }
         let send_now = match last_embed {
             Some(ref last_embed) => last_embed != &embed,
             None => true,
@@ -1340,6 +1910,7 @@ pub async fn the_lüüp(
         }
     }
     log.log("SHUTTING DOWN").await;
+    log.log(&format!("TTS cache: {}", CacheStats::now().since(tts_cache))).await;
     // let (returner, gimme) =
     //     tokio::sync::oneshot::channel::<tokio::sync::broadcast::Receiver<RawMessage>>();
     // if killsubthread.send(returner).is_err() {
@@ -1356,6 +1927,12 @@ pub async fn the_lüüp(
     //         log.log(&format!("Error getting ttsrx: {}\n", e)).await;
     //     }
     // }
+    if let Some(handle) = nothing_handle.take() {
+        log.log("Stopping idle audio").await;
+        if let Err(e) = handle.stop() {
+            log.log(&format!("Error stopping idle audio: {}\n", e)).await;
+        }
+    }
     {
         log.log("Getting call lock").await;
         let mut calllock = control.call.lock().await;
@@ -1909,6 +2486,7 @@ struct SuperHandle {
     title: Arc<str>,
     duration: Option<f64>,
     author: Option<Author>,
+    batch: Option<usize>,
 }
 impl SuperHandle {
     async fn stop(mut self, log: &Log) {
@@ -1933,7 +2511,12 @@ impl SuperHandle {
             let _ = song.get_handle().stop();
         }
     }
-    async fn new(call: &Arc<Mutex<Call>>, data: MetaVideo, volume: f32) -> Result<Self> {
+    async fn new(
+        call: &Arc<Mutex<Call>>,
+        data: MetaVideo,
+        volume: f32,
+        batch: Option<usize>,
+    ) -> Result<Self> {
         let (song, title, duration, author) = {
             let call = Arc::clone(call);
             let song = data.video;
@@ -1998,8 +2581,22 @@ impl SuperHandle {
             title,
             duration,
             author,
+            batch,
         })
     }
+    // takes the title out so the song can start without waiting for it to be read
+    async fn take_title(&mut self) -> Result<Option<HandleMetadata>> {
+        match self.tts.take() {
+            Some(mut tts) => {
+                tts.resolve().await?;
+                match tts.take() {
+                    Some(tts) => tts,
+                    None => Ok(None),
+                }
+            }
+            None => Ok(None),
+        }
+    }
     async fn next_audio(&mut self, read_titles: bool) -> Result<Option<HandleMetadata>> {
         if read_titles {
             if let Some(ref mut tts) = self.tts {
@@ -2123,6 +2720,18 @@ enum SimpleConnectionEvent {
     DriverDisconnect,
     DriverConnect(Option<ChannelId>),
 }
+async fn idle_changed(idle: Option<&mut IdleAudio>) -> Option<()> {
+    match idle {
+        Some(idle) => idle.changed().await,
+        None => std::future::pending().await,
+    }
+}
+async fn icy_recv(icy: Option<&mut IcyThread>) -> Option<Arc<OriginalOrCustom>> {
+    match icy {
+        Some(icy) => icy.recv().await,
+        None => Never::default().await,
+    }
+}
 async fn if_some_await<T, V>(b: Option<&V>, future: T) -> &V
 where
     T: Future,
@@ -2139,6 +2748,27 @@ where
         }
     }
 }
+// ticks steadily through a crossfade, otherwise sleeps until the position is next worth checking
+async fn transition_wait(
+    fading: bool,
+    check_at: Option<Instant>,
+    interval: &mut tokio::time::Interval,
+) {
+    match (fading, check_at) {
+        (true, _) => {
+            interval.tick().await;
+        }
+        (false, Some(at)) => tokio::time::sleep_until(at).await,
+        (false, None) => Never::default().await,
+    }
+}
+// how far through a crossfade we are, from 0.0 to 1.0
+fn fade_progress(started: Instant, crossfade: Duration) -> f32 {
+    if crossfade.is_zero() {
+        return 1.0;
+    }
+    (started.elapsed().as_secs_f32() / crossfade.as_secs_f32()).min(1.0)
+}
 async fn if_then<T>(b: bool, queue: &mut FuturesOrdered<T>) -> <T as Future>::Output
 where
     T: Future,
@@ -2155,6 +2785,11 @@ where
         None => Never::default().await,
     }
 }
+// tts waiting to be generated or read, message is None for announcements
+struct PendingTts {
+    ticket: u64,
+    message: Option<MessageId>,
+}
 #[derive(Debug, Clone, Default)]
 struct ManuallySet {
     song_volume: bool,
@@ -2179,17 +2814,26 @@ impl TTSVoiceState {
 
 async fn generate_tts(
     ctx: common::serenity::all::Context,
-    msg: Arc<Message>,
+    msg: Arc<long_term_storage::TtsMessage>,
     voice: TTSVoiceState,
+    user: Option<long_term_storage::User>,
 ) -> Vec<Video> {
     let mut tts = Vec::new();
     let voice = match voice {
         // error means its a new voice and we have to put an announcement message on it first
         TTSVoiceState::Existing(voice) => voice,
         TTSVoiceState::New(voice) => {
+            let guild = msg.guild.as_ref();
+            let backends = long_term_storage::tts_backends(guild, user.as_ref());
+            let dictionary =
+                long_term_storage::tts_dictionary(guild, Some(ctx.cache.as_ref())).await;
             if let Ok(b) = RawMessage::announcement(
                 format!("{} is now using this voice to speak", msg.author.name),
                 &voice,
+                &backends,
+                &dictionary,
+                guild,
+                Some(msg.author.id),
             )
             .await
             {
@@ -2199,8 +2843,57 @@ async fn generate_tts(
         }
     };
     // we might eventually optionally have some kind of map for channel names here idk
-    if let Ok(b) = RawMessage::message(&ctx, msg, &voice).await {
+    if let Ok(b) = RawMessage::message(&ctx, &msg, &voice, user.as_ref()).await {
         tts.push(b);
     }
     tts
 }
+// whether the voice channel has read only when muted mode on and where its captions go, kept for
+// the session and updated when they're changed or the bot moves
+async fn load_channel_settings(voice_channel: ChannelId) -> (bool, Option<ChannelId>) {
+    match long_term_storage::Channel::load_opt(voice_channel).await {
+        Ok(channel) => channel.map_or((false, None), |c| (c.muted_only, c.caption_channel)),
+        Err(e) => {
+            log::error!("Error loading channel: {:?}", e);
+            (false, None)
+        }
+    }
+}
+// the transcription thread only collects captions when they're on, the message posts them
+fn set_caption_channel(
+    transcription: &TranscriptionThread,
+    change_captions: &mpsc::UnboundedSender<Option<ChannelId>>,
+    caption_channel: Option<ChannelId>,
+) {
+    if let Err(e) = transcription
+        .message
+        .send(TranscriptionMessage::Captions(caption_channel.is_some()))
+    {
+        log::error!("Error updating captions: {:?}", e);
+    }
+    if let Err(e) = change_captions.send(caption_channel) {
+        log::error!("Error updating caption channel: {:?}", e);
+    }
+}
+// in read only when muted mode, which the author or the voice channel can turn on, messages are
+// only read for people sitting muted in the voice channel, everyone else can just talk
+async fn speaking_for_themselves(
+    msg: &Message,
+    guild_id: GuildId,
+    voice_channel: ChannelId,
+    muted_only: bool,
+) -> bool {
+    if !muted_only {
+        return false;
+    }
+    match common::global_data::voice_data::member_state(&guild_id, &msg.author.id).await {
+        Ok(Some(state)) => {
+            state.channel_id != Some(voice_channel) || !(state.self_mute || state.mute)
+        }
+        Ok(None) => true,
+        Err(e) => {
+            log::error!("Error getting voice state: {:?}", e);
+            true
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use common::anyhow::{self, Result};
+use common::audio::{AudioCommandHandler, AudioPromiseCommand, MetaCommand};
+use common::global_data::voice_data::VoiceAction;
+use common::radio::{AzuraCastApi, RadioData, RadioInfo};
+use common::serenity::all::*;
+use common::utils::edit_response;
+use common::{log, tokio, CommandTrait, SubCommandTrait};
+use long_term_storage::RadioPreset;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![
+                Box::new(List),
+                Box::new(Switch),
+                Box::new(History),
+                Box::new(Request),
+            ],
+        }
+    }
+}
+impl Default for Command {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[async_trait]
+impl CommandTrait for Command {
+    fn register_command(&self) -> Option<CreateCommand> {
+        Some(
+            CreateCommand::new(self.command_name())
+                .contexts(vec![InteractionContext::Guild])
+                .description("Radio stations for when nothing is queued")
+                .set_options(
+                    self.subcommands
+                        .iter()
+                        .map(|sc| sc.register_command())
+                        .collect(),
+                ),
+        )
+    }
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        if let Err(e) = interaction.defer_ephemeral(&ctx.http).await {
+            log::error!("Failed to send response: {}", e);
+        }
+        let (subcommand, opts) = match interaction.data.options().into_iter().find_map(|o| match o
+            .value
+        {
+            ResolvedValue::SubCommand(opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                edit_response(ctx, interaction, "Invalid subcommand").await;
+                return Ok(());
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                return sc.run(ctx, interaction, &opts).await;
+            }
+        }
+        edit_response(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "radio"
+    }
+    async fn autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        for option in interaction.data.options() {
+            for sc in &self.subcommands {
+                if sc.command_name() == option.name {
+                    match option.value {
+                        ResolvedValue::SubCommand(opts) => {
+                            return sc.autocomplete(ctx, interaction, &opts).await;
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!("Invalid option type"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+// /radio list - Show the stations this server has set up
+struct List;
+#[async_trait]
+impl SubCommandTrait for List {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Show the radio stations this server has set up",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let presets = match RadioPreset::all(guild_id).await {
+            Ok(p) => p,
+            Err(e) => {
+                log::error!("Failed to load radio presets: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load radio stations").await;
+                return Ok(());
+            }
+        };
+        if presets.is_empty() {
+            edit_response(
+                ctx,
+                interaction,
+                "This server has no radio stations, use `/config radio_source preset_add` to add one",
+            )
+            .await;
+            return Ok(());
+        }
+        let list = presets
+            .iter()
+            .map(|p| format!("- `{}`: <{}>", p.name, p.audio_url))
+            .collect::<Vec<String>>()
+            .join("\n");
+        edit_response(ctx, interaction, &list).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "list"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// /radio switch <name> - Switch the idle radio to a preset until the bot leaves
+struct Switch;
+#[async_trait]
+impl SubCommandTrait for Switch {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Switch to another radio station until the bot leaves",
+        )
+        .set_sub_options(vec![CreateCommandOption::new(
+            CommandOptionType::String,
+            "name",
+            "The station to switch to",
+        )
+        .required(true)
+        .set_autocomplete(true)])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let name = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "name" => Some(s),
+            _ => None,
+        }) {
+            Some(n) => n,
+            None => {
+                edit_response(ctx, interaction, "You must provide a station").await;
+                return Ok(());
+            }
+        };
+        let preset = match RadioPreset::load_opt(guild_id, name).await {
+            Ok(Some(p)) => p,
+            Ok(None) => {
+                edit_response(
+                    ctx,
+                    interaction,
+                    &format!("There is no station called `{}`", name),
+                )
+                .await;
+                return Ok(());
+            }
+            Err(e) => {
+                log::error!("Failed to load radio preset: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load that station").await;
+                return Ok(());
+            }
+        };
+        let member = match interaction.member.as_ref() {
+            Some(m) => m,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let next_step =
+            match common::global_data::voice_data::mutual_channel(&guild_id, &member.user.id).await
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("Failed to get mutual channel: {:?}", e);
+                    edit_response(ctx, interaction, "Failed to get mutual channel").await;
+                    return Ok(());
+                }
+            };
+        next_step
+            .send_command_or_respond(
+                interaction,
+                guild_id,
+                AudioPromiseCommand::SwitchRadio(preset.station()),
+            )
+            .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "switch"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let partial = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } if o.name == "name" => Some(value),
+            _ => None,
+        }) {
+            Some(p) => p.to_lowercase(),
+            None => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        for preset in RadioPreset::all(guild_id)
+            .await?
+            .iter()
+            .filter(|p| p.name.to_lowercase().contains(&partial))
+            .take(25)
+        {
+            completions = completions.add_string_choice(preset.name.as_ref(), preset.name.as_ref());
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
+// /radio history <optional count> - Show the last songs played on the radio
+struct History;
+#[async_trait]
+impl SubCommandTrait for History {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Show what the radio played recently",
+        )
+        .set_sub_options(vec![CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "count",
+            "How many songs to show",
+        )
+        .min_int_value(1)
+        .max_int_value(20)
+        .required(false)])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let count = options
+            .iter()
+            .find_map(|o| match o.value {
+                ResolvedValue::Integer(i) if o.name == "count" => Some(i.clamp(1, 20) as usize),
+                _ => None,
+            })
+            .unwrap_or(10);
+        let info = match radio_info(ctx, guild_id, interaction.user.id).await {
+            Ok(info) => info,
+            Err(e) => {
+                log::error!("Failed to get radio info: {:?}", e);
+                edit_response(ctx, interaction, "Failed to get the radio history").await;
+                return Ok(());
+            }
+        };
+        if info.history.is_empty() {
+            edit_response(ctx, interaction, "Nothing has played yet").await;
+            return Ok(());
+        }
+        let mut embed = CreateEmbed::new()
+            .title("Recently played")
+            .color(Color::from_rgb(184, 29, 19))
+            .description(
+                info.history
+                    .iter()
+                    .take(count)
+                    .map(|h| {
+                        format!(
+                            "<t:{}:R> **{}**{}",
+                            h.played_at,
+                            h.title,
+                            match h.artist {
+                                Some(ref artist) => format!(" by {}", artist),
+                                None => String::new(),
+                            }
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            );
+        if let Some(art) = info.history.iter().find_map(|h| h.art.as_ref()) {
+            embed = embed.thumbnail(art);
+        }
+        if let Err(e) = interaction
+            .edit_response(&ctx.http, EditInteractionResponse::new().embed(embed))
+            .await
+        {
+            log::error!("Failed to edit original interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "history"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// /radio request <song> - Request a song on an azuracast station
+struct Request;
+#[async_trait]
+impl SubCommandTrait for Request {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Request a song on the radio",
+        )
+        .set_sub_options(vec![CreateCommandOption::new(
+            CommandOptionType::String,
+            "song",
+            "The song to request",
+        )
+        .required(true)
+        .set_autocomplete(true)])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let song = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "song" => Some(s),
+            _ => None,
+        }) {
+            Some(s) => s,
+            None => {
+                edit_response(ctx, interaction, "You must provide a song").await;
+                return Ok(());
+            }
+        };
+        let result: Result<String> = try {
+            let info = radio_info(ctx, guild_id, interaction.user.id).await?;
+            let api = AzuraCastApi::new(info.data_url.as_deref()).await?;
+            // autocomplete gives us the request id, anything typed by hand is searched for
+            let songs = api.requestable().await?;
+            let request = match songs.iter().find(|s| s.request_id == song) {
+                Some(s) => s,
+                None => {
+                    let search = song.to_lowercase();
+                    songs
+                        .iter()
+                        .find(|s| song_text(&s.song).to_lowercase().contains(&search))
+                        .ok_or_else(|| anyhow::anyhow!("No requestable songs match `{}`", song))?
+                }
+            };
+            let message = api.request(&request.request_id).await?;
+            format!("Requested **{}**\n{}", song_text(&request.song), message)
+        };
+        match result {
+            Ok(message) => edit_response(ctx, interaction, &message).await,
+            Err(e) => {
+                let content = format!("Failed to request that song: {}", e);
+                edit_response(ctx, interaction, &content).await
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "request"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let partial = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } if o.name == "song" => Some(value),
+            _ => None,
+        }) {
+            Some(p) => p.to_lowercase(),
+            None => return Ok(()),
+        };
+        let info = radio_info(ctx, guild_id, interaction.user.id).await?;
+        let api = AzuraCastApi::new(info.data_url.as_deref()).await?;
+        let mut completions = CreateAutocompleteResponse::new();
+        for requestable in api
+            .requestable()
+            .await?
+            .iter()
+            .filter(|s| song_text(&s.song).to_lowercase().contains(&partial))
+            .take(25)
+        {
+            let mut name = song_text(&requestable.song);
+            if name.len() > 100 {
+                name = name.chars().take(97).collect::<String>() + "...";
+            }
+            completions = completions.add_string_choice(name, requestable.request_id.as_str());
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
+fn song_text(song: &common::radio::Song) -> String {
+    if song.artist.is_empty() {
+        song.title.clone()
+    } else {
+        format!("{} - {}", song.title, song.artist)
+    }
+}
+// asks the bot in the user's channel what it's playing, otherwise falls back to the server's configured radio
+async fn radio_info(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Result<RadioInfo> {
+    if let Ok(next_step) = common::global_data::voice_data::mutual_channel(&guild_id, &user_id).await {
+        if let VoiceAction::SatelliteInVcWithUser(channel, _ctx) = next_step.action {
+            let handler = ctx
+                .data
+                .read()
+                .await
+                .get::<AudioCommandHandler>()
+                .map(Arc::clone);
+            if let Some(handler) = handler {
+                let (info_tx, mut info_rx) = mpsc::channel::<RadioInfo>(1);
+                let (rtx, _rrx) = oneshot::channel::<Arc<str>>();
+                let sent = handler.read().await.get(&channel).map(|tx| {
+                    tx.send((
+                        rtx,
+                        AudioPromiseCommand::MetaCommand(MetaCommand::RetrieveRadioInfo(info_tx)),
+                    ))
+                });
+                if let Some(Ok(())) = sent {
+                    if let Ok(Some(info)) =
+                        tokio::time::timeout(Duration::from_secs(5), info_rx.recv()).await
+                    {
+                        return Ok(info);
+                    }
+                }
+            }
+        }
+    }
+    let data_url = long_term_storage::Guild::load(guild_id).await?.radio_data_url;
+    let url = match data_url {
+        Some(ref url) => url.to_string(),
+        None => common::get_config().api_url,
+    };
+    let history = RadioData::get(url)
+        .await
+        .ok()
+        .and_then(|d| d.history())
+        .unwrap_or_default();
+    Ok(RadioInfo { data_url, history })
+}
@@ -0,0 +1,132 @@
+use common::anyhow::{self, Result};
+use common::audio::{AudioCommandHandler, AudioPromiseCommand};
+use common::global_data::voice_data::VoiceAction;
+use common::serenity::all::*;
+use common::video::{Author, MetaVideo, Video};
+#[cfg(feature = "tts")]
+use common::video::{LazyLoadedVideo, VideoType};
+use common::{log, tokio};
+use long_term_storage::{RadioPreset, Schedule, ScheduleAction};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+/// runs a schedule that is due, joining its channel with whichever satellite is free if there's no bot there yet
+pub async fn run(ctx: &Context, schedule: &Schedule) -> Result<Arc<str>> {
+    // resolve everything before joining so a bad schedule doesn't leave a bot sitting in the channel
+    let command = match schedule.action {
+        ScheduleAction::Play(ref query) => {
+            AudioPromiseCommand::Play(videos(ctx, query, schedule).await?)
+        }
+        ScheduleAction::SwitchRadio(ref name) => {
+            match RadioPreset::load_opt(schedule.guild_id, name).await? {
+                Some(preset) => AudioPromiseCommand::SwitchRadio(preset.station()),
+                None => return Err(anyhow::anyhow!("No radio station named `{}`", name)),
+            }
+        }
+    };
+    let next_step =
+        common::global_data::voice_data::channel_action(&schedule.guild_id, schedule.channel_id)
+            .await?;
+    let channel = match next_step.action {
+        VoiceAction::SatelliteInVcWithUser(channel, _ctx) => channel,
+        VoiceAction::SatelliteShouldJoin(channel, satellite_ctx) => {
+            super::join::connect(ctx, &satellite_ctx, schedule.guild_id, channel).await?;
+            channel
+        }
+        VoiceAction::InviteSatellite(_) => {
+            return Err(anyhow::anyhow!("No satellite can see that channel"))
+        }
+        VoiceAction::NoRemaining => return Err(anyhow::anyhow!("No satellites available")),
+        VoiceAction::UserNotConnected => return Err(anyhow::anyhow!("Channel not found")),
+    };
+    let audio_command_handler = ctx
+        .data
+        .read()
+        .await
+        .get::<AudioCommandHandler>()
+        .map(Arc::clone)
+        .ok_or_else(|| anyhow::anyhow!("Failed to get audio command handler"))?;
+    let (rtx, rrx) = oneshot::channel::<Arc<str>>();
+    match audio_command_handler.read().await.get(&channel) {
+        Some(tx) => tx.send((rtx, command))?,
+        None => return Err(anyhow::anyhow!("Couldn't find the channel handler")),
+    }
+    match tokio::time::timeout(Duration::from_secs(10), rrx).await {
+        Ok(Ok(msg)) => Ok(msg),
+        _ => Err(anyhow::anyhow!("Failed to send inner command")),
+    }
+}
+// same lookup as /add, a url or playlist first and a youtube search if that fails
+async fn videos(ctx: &Context, query: &str, schedule: &Schedule) -> Result<Vec<MetaVideo>> {
+    let rawvids = match Video::get_video(query, true, true).await {
+        Ok(v) => v,
+        Err(_) => {
+            let url = common::youtube::search(query.to_owned(), 1)
+                .await
+                .first()
+                .map(|v| v.url())
+                .ok_or_else(|| anyhow::anyhow!("No videos found for `{}`", query))?;
+            Video::get_video(url.as_ref(), true, false).await?
+        }
+    };
+    let author = match schedule.user_id.to_user(ctx).await {
+        Ok(user) => Author::from_user(ctx, &user, Some(schedule.guild_id)).await,
+        Err(e) => {
+            log::error!("Failed to get schedule author: {:?}", e);
+            None
+        }
+    };
+    #[cfg(feature = "tts")]
+    let guild = long_term_storage::tts_guild(Some(schedule.guild_id)).await;
+    #[cfg(feature = "tts")]
+    let announcer = guild.as_ref().map(|g| g.announcer.clone()).unwrap_or_default();
+    #[cfg(feature = "tts")]
+    let backends = announcer.backends(long_term_storage::tts_backends(
+        guild.as_ref(),
+        long_term_storage::tts_user(Some(schedule.user_id)).await.as_ref(),
+    ));
+    #[cfg(feature = "tts")]
+    let dictionary =
+        long_term_storage::tts_dictionary(guild.as_ref(), Some(ctx.cache.as_ref())).await;
+    Ok(rawvids
+        .into_iter()
+        .map(|v| {
+            #[cfg(feature = "tts")]
+            let text = {
+                let (title, artist, duration) = match v.clone() {
+                    VideoType::Disk(v) => (v.title(), None, Some(v.duration())),
+                    VideoType::Url(v) => (v.title(), v.artist(), v.duration()),
+                };
+                announcer.announce(&common::tts::Announcement {
+                    title: &title,
+                    artist: artist.as_deref(),
+                    requester: author.as_ref().map(|a| a.name.as_str()),
+                    duration,
+                })
+            };
+            MetaVideo {
+                video: v,
+                #[cfg(feature = "tts")]
+                ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn({
+                    let backends = backends.clone();
+                    let dictionary = dictionary.clone();
+                    let voice = announcer.voice;
+                    let guild = guild.clone();
+                    let user_id = schedule.user_id;
+                    async move {
+                        long_term_storage::tts_speak_with(
+                            &text,
+                            voice,
+                            &backends,
+                            &dictionary,
+                            guild.as_ref(),
+                            Some(user_id),
+                        )
+                        .await
+                    }
+                }))),
+                author: author.clone(),
+            }
+        })
+        .collect())
+}
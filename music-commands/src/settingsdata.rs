@@ -1,4 +1,9 @@
-use common::{anyhow::Result, audio::OrAuto, serenity::all::GuildId};
+use common::{
+    anyhow::Result,
+    audio::{OrAuto, RadioAnnouncements, Transitions},
+    serenity::all::GuildId,
+};
+use std::sync::Arc;
 #[derive(Clone, PartialEq, Debug)]
 pub struct SettingsData {
     // pub something_playing: bool,
@@ -15,11 +20,28 @@ pub struct SettingsData {
     pub pause: bool,
     pub read_titles: bool,
     pub talk_over_eachother: bool,
+    pub radio_announcements: RadioAnnouncements,
+    pub transitions: Transitions,
+
+    // nothing is queued, so the radio is what's playing
+    pub idle: bool,
+    pub radio_stations: Vec<Arc<str>>,
+    pub current_station: Option<Arc<str>>,
+    // messages waiting to be read out
+    pub tts_waiting: usize,
+    pub tts_paused: bool,
 }
 impl SettingsData {
     pub async fn new(guild: GuildId) -> Result<Self> {
         // let cfg = common::global_data::guild_config::GuildConfig::get(guild).await;
         let cfg = long_term_storage::Guild::load(guild).await?;
+        let radio_stations = match long_term_storage::RadioPreset::all(guild).await {
+            Ok(presets) => presets.into_iter().map(|p| p.name).collect(),
+            Err(e) => {
+                log::error!("Failed to load radio presets: {:?}", e);
+                Vec::new()
+            }
+        };
         Ok(Self {
             // something_playing: false,
             song_volume: cfg.default_song_volume,
@@ -34,6 +56,13 @@ impl SettingsData {
             log_empty: true,
             read_titles: cfg.read_titles,
             talk_over_eachother: cfg.talk_over_eachother,
+            radio_announcements: cfg.radio_announcements,
+            transitions: cfg.transitions,
+            idle: true,
+            radio_stations,
+            current_station: None,
+            tts_waiting: 0,
+            tts_paused: false,
         })
     }
     pub fn song_volume(&self) -> f32 {
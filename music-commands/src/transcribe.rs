@@ -209,13 +209,14 @@ impl TranscriptionThread {
     pub async fn new(
         call: Arc<Mutex<Call>>,
         context: Context,
+        guild_id: GuildId,
         otx: mpsc::UnboundedSender<(oneshot::Sender<Arc<str>>, AudioPromiseCommand)>,
         packets: mpsc::UnboundedReceiver<PacketData>,
     ) -> Self {
         let (message, messagerx) = mpsc::unbounded_channel();
         let (tx, receiver) = mpsc::unbounded_channel::<(PostSomething, UserId)>();
         let thread = tokio::task::spawn(voice_events::transcription_thread(
-            call, context, otx, messagerx, tx, packets,
+            call, context, guild_id, otx, messagerx, tx, packets,
         ));
         Self {
             thread,
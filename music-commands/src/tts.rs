@@ -0,0 +1,439 @@
+use common::anyhow::{self, Result};
+use common::audio::{AudioPromiseCommand, OrToggle};
+use common::serenity::all::*;
+use common::tts::TtsBackendKind;
+use common::utils::edit_response;
+use common::{log, tokio, CommandTrait, SubCommandTrait};
+use long_term_storage::{Guild, TtsUsage, User};
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![
+                Box::new(Backend),
+                Box::new(UserToggle {
+                    name: "opt_out",
+                    description: "Stop the bot reading your messages out in every server",
+                    option_description: "Whether your messages are left unread",
+                    setting: |user| &mut user.tts_opt_out,
+                    when_on: "Your messages are never read out",
+                    when_off: "Your messages are read out",
+                    turned_on: "Your messages won't be read out anymore",
+                    turned_off: "Your messages will be read out again",
+                }),
+                Box::new(UserToggle {
+                    name: "muted_only",
+                    description: "Only read your messages while you're muted in voice",
+                    option_description: "Whether your messages are only read while you're muted",
+                    setting: |user| &mut user.tts_muted_only,
+                    when_on: "Your messages are only read while you're muted",
+                    when_off: "Your messages are read whether you're muted or not",
+                    turned_on: "Your messages will only be read while you're muted",
+                    turned_off: "Your messages will be read whether you're muted or not",
+                }),
+                Box::new(Usage),
+                Box::new(QueueControl {
+                    name: "skip",
+                    description: "Stop reading the current message",
+                    command: || AudioPromiseCommand::TtsSkip,
+                }),
+                Box::new(QueueControl {
+                    name: "clear",
+                    description: "Stop reading and drop every message waiting to be read",
+                    command: || AudioPromiseCommand::TtsClear,
+                }),
+                Box::new(QueueControl {
+                    name: "pause",
+                    description: "Pause or resume reading messages out",
+                    command: || AudioPromiseCommand::TtsPause(OrToggle::Toggle),
+                }),
+            ],
+        }
+    }
+}
+impl Default for Command {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[async_trait]
+impl CommandTrait for Command {
+    fn register_command(&self) -> Option<CreateCommand> {
+        Some(
+            CreateCommand::new(self.command_name())
+                .description("Your TTS settings, reading controls, and what TTS costs the server")
+                .set_options(
+                    self.subcommands
+                        .iter()
+                        .map(|sc| sc.register_command())
+                        .collect(),
+                ),
+        )
+    }
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        if let Err(e) = interaction.defer_ephemeral(&ctx.http).await {
+            log::error!("Failed to send response: {}", e);
+        }
+        let (subcommand, opts) = match interaction.data.options().into_iter().find_map(|o| match o
+            .value
+        {
+            ResolvedValue::SubCommand(opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                edit_response(ctx, interaction, "Invalid subcommand").await;
+                return Ok(());
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                let allowed = sc.permissions().is_empty()
+                    || interaction
+                        .member
+                        .as_ref()
+                        .and_then(|m| m.permissions(&ctx.cache).ok())
+                        .map(|p| p.contains(sc.permissions()))
+                        .unwrap_or(false);
+                if !allowed {
+                    edit_response(
+                        ctx,
+                        interaction,
+                        "You do not have permission to run this command",
+                    )
+                        .await;
+                    return Ok(());
+                }
+                return sc.run(ctx, interaction, &opts).await;
+            }
+        }
+        edit_response(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "tts"
+    }
+    async fn autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        for option in interaction.data.options() {
+            for sc in &self.subcommands {
+                if sc.command_name() == option.name {
+                    match option.value {
+                        ResolvedValue::SubCommand(opts) => {
+                            return sc.autocomplete(ctx, interaction, &opts).await;
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!("Invalid option type"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+// /tts backend [backend] - Pick the TTS engine your messages are read with
+struct Backend;
+#[async_trait]
+impl SubCommandTrait for Backend {
+    fn register_command(&self) -> CreateCommandOption {
+        let mut backend = CreateCommandOption::new(
+            CommandOptionType::String,
+            "backend",
+            "The TTS engine to try first, the others are still used if it fails",
+        )
+        .add_string_choice("Whatever the server uses", "default");
+        for kind in TtsBackendKind::ALL {
+            backend = backend.add_string_choice(kind.to_string(), kind.name());
+        }
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Pick the TTS engine your messages are read with",
+        )
+        .add_sub_option(backend)
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let backend = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("backend", ResolvedValue::String(s)) => Some(*s),
+            _ => None,
+        });
+        let mut user = match User::load(interaction.user.id).await {
+            Ok(u) => u,
+            Err(e) => {
+                log::error!("Failed to load user: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load user").await;
+                return Ok(());
+            }
+        };
+        let backend = match backend {
+            None => {
+                edit_response(
+                    ctx,
+                    interaction,
+                    &match user.tts_backend {
+                        Some(b) => format!("Your messages are read with {} first", b),
+                        None => "Your messages are read with whatever the server uses".to_owned(),
+                    },
+                )
+                .await;
+                return Ok(());
+            }
+            Some("default") => None,
+            Some(s) => match s.parse::<TtsBackendKind>() {
+                Ok(b) => Some(b),
+                Err(e) => {
+                    log::error!("Invalid tts backend: {:?}", e);
+                    edit_response(ctx, interaction, "Invalid backend").await;
+                    return Ok(());
+                }
+            },
+        };
+        user.tts_backend = backend;
+        if let Err(e) = user.save().await {
+            log::error!("Failed to save user: {:?}", e);
+            edit_response(ctx, interaction, "Failed to save user").await;
+            return Ok(());
+        }
+        edit_response(
+            ctx,
+            interaction,
+            &match backend {
+                Some(b) => format!("Your messages will now be read with {} first", b),
+                None => "Your messages will now be read with whatever the server uses".to_owned(),
+            },
+        )
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "backend"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// /tts opt_out [enabled], /tts muted_only [enabled] - Your own reading settings, either on or off
+struct UserToggle {
+    name: &'static str,
+    description: &'static str,
+    option_description: &'static str,
+    setting: fn(&mut User) -> &mut bool,
+    // what's said when asked without a value
+    when_on: &'static str,
+    when_off: &'static str,
+    // what's said once it's been changed
+    turned_on: &'static str,
+    turned_off: &'static str,
+}
+#[async_trait]
+impl SubCommandTrait for UserToggle {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(CommandOptionType::SubCommand, self.name, self.description)
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "enabled",
+                self.option_description,
+            ))
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let enabled = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("enabled", ResolvedValue::Boolean(b)) => Some(*b),
+            _ => None,
+        });
+        let mut user = match User::load(interaction.user.id).await {
+            Ok(u) => u,
+            Err(e) => {
+                log::error!("Failed to load user: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load user").await;
+                return Ok(());
+            }
+        };
+        let enabled = match enabled {
+            Some(e) => e,
+            None => {
+                let content = if *(self.setting)(&mut user) {
+                    self.when_on
+                } else {
+                    self.when_off
+                };
+                edit_response(ctx, interaction, content).await;
+                return Ok(());
+            }
+        };
+        *(self.setting)(&mut user) = enabled;
+        if let Err(e) = user.save().await {
+            log::error!("Failed to save user: {:?}", e);
+            edit_response(ctx, interaction, "Failed to save user").await;
+            return Ok(());
+        }
+        let content = if enabled {
+            self.turned_on
+        } else {
+            self.turned_off
+        };
+        edit_response(ctx, interaction, content).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        self.name
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
+// /tts usage - How many characters TTS has synthesized in this server, for admins
+struct Usage;
+#[async_trait]
+impl SubCommandTrait for Usage {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "How many characters TTS has synthesized in this server this month",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(g) => g,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let quota = match Guild::load(guild_id).await {
+            Ok(g) => g.tts_quota,
+            Err(e) => {
+                log::error!("Failed to load guild: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load guild").await;
+                return Ok(());
+            }
+        };
+        let usage = tokio::try_join!(
+            TtsUsage::backends(guild_id),
+            TtsUsage::top_users(guild_id, 10)
+        );
+        let (backends, users) = match usage {
+            Ok(u) => u,
+            Err(e) => {
+                log::error!("Failed to load tts usage: {:?}", e);
+                edit_response(ctx, interaction, "Failed to load TTS usage").await;
+                return Ok(());
+            }
+        };
+        let mut content = String::from("**Characters synthesized this month**\n");
+        if backends.is_empty() {
+            content.push_str("Nothing yet\n");
+        }
+        for usage in &backends {
+            content.push_str(&format!(
+                "{}: {} today, {} this month{}\n",
+                usage.backend,
+                usage.today,
+                usage.month,
+                if usage.backend.is_paid() { " (paid)" } else { "" }
+            ));
+        }
+        content.push_str("\n**Paid quota**\n");
+        if quota.is_unlimited() {
+            content.push_str("No limits\n");
+        }
+        for (limit, what) in [
+            (quota.daily, "a day"),
+            (quota.monthly, "a month"),
+            (quota.user_daily, "a day for each user"),
+            (quota.user_monthly, "a month for each user"),
+        ] {
+            if let Some(limit) = limit {
+                content.push_str(&format!("{} characters {}\n", limit, what));
+            }
+        }
+        match quota.allowance(guild_id, None).await {
+            Ok(Some(left)) => {
+                content.push_str(&format!("{} characters left before falling back\n", left));
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to check tts quota: {:?}", e),
+        }
+        if !users.is_empty() {
+            content.push_str("\n**Top users this month**\n");
+        }
+        for usage in &users {
+            content.push_str(&match usage.user_id {
+                Some(user_id) => format!("<@{}>: {}\n", user_id, usage.month),
+                None => format!("Announcements: {}\n", usage.month),
+            });
+        }
+        edit_response(ctx, interaction, &content).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "usage"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+// /tts skip, /tts clear, /tts pause - Control message reading in your voice channel
+struct QueueControl {
+    name: &'static str,
+    description: &'static str,
+    command: fn() -> AudioPromiseCommand,
+}
+#[async_trait]
+impl SubCommandTrait for QueueControl {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(CommandOptionType::SubCommand, self.name, self.description)
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                edit_response(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let next_step =
+            match common::global_data::voice_data::mutual_channel(&guild_id, &interaction.user.id)
+                .await
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("Failed to get mutual channel: {:?}", e);
+                    edit_response(ctx, interaction, "Failed to get mutual channel").await;
+                    return Ok(());
+                }
+            };
+        next_step
+            .send_command_or_respond(interaction, guild_id, (self.command)())
+            .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        self.name
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+}
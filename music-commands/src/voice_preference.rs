@@ -1,7 +1,12 @@
 use common::anyhow::Result;
 use common::serenity::all::*;
+use common::tts::{Dictionary, TtsBackendKind};
+use common::youtube::{TTSVoice, ALL_VOICES};
 use common::{log, CommandTrait};
 use long_term_storage::{User, VoicePreference};
+/// custom id prefix for the button that speaks a sample of a voice, followed by the voice name
+pub const PREVIEW_PREFIX: &str = "voice_preview:";
+const PREVIEW_TEXT: &str = "Hi! This is what your messages will sound like.";
 #[derive(Debug, Clone)]
 pub struct Command;
 #[async_trait]
@@ -12,15 +17,22 @@ impl CommandTrait for Command {
                 .description(
                     "Allows you to set your TTS voice preference."
                 )
-                .set_options(vec![CreateCommandOption::new(
-                    CommandOptionType::String,
-                    "preference",
-                    "The voice preference you want to set.",
-                )
-                .add_string_choice("No Preference", "none")
-                .add_string_choice("Male Voice", "male")
-                .add_string_choice("Female Voice", "female")
-                .required(true)]),
+                .set_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "preference",
+                        "The voice preference you want to set.",
+                    )
+                    .add_string_choice("No Preference", "none")
+                    .add_string_choice("Male Voice", "male")
+                    .add_string_choice("Female Voice", "female"),
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "voice",
+                        "A specific voice to always use, this wins over the preference.",
+                    )
+                    .set_autocomplete(true),
+                ]),
         )
     }
     async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
@@ -36,24 +48,26 @@ impl CommandTrait for Command {
             log::error!("Failed to create interaction response: {:?}", e);
         }
         let options = interaction.data.options();
-        let option = match options.iter().find_map(|o| match o.name {
-            "preference" => Some(&o.value),
+        let preference = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("preference", ResolvedValue::String(s)) => Some(*s),
             _ => None,
-        }) {
-            Some(ResolvedValue::String(b)) => *b,
-            _ => {
-                if let Err(e) = interaction
-                    .edit_response(
-                        &ctx.http,
-                        EditInteractionResponse::new().content("This command requires an option"),
-                    )
-                    .await
-                {
-                    log::error!("Failed to edit original interaction response: {:?}", e);
-                }
-                return Ok(());
+        });
+        let voice = options.iter().find_map(|o| match (o.name, &o.value) {
+            ("voice", ResolvedValue::String(s)) => Some(*s),
+            _ => None,
+        });
+        if preference.is_none() && voice.is_none() {
+            if let Err(e) = interaction
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new().content("This command requires an option"),
+                )
+                .await
+            {
+                log::error!("Failed to edit original interaction response: {:?}", e);
             }
-        };
+            return Ok(());
+        }
         let mut user_conf = match User::load(interaction.user.id).await {
             Ok(c) => c,
             Err(e) => {
@@ -70,11 +84,12 @@ impl CommandTrait for Command {
                 return Ok(());
             }
         };
-        let option = match option {
-            "none" => VoicePreference::NoPreference,
-            "female" => VoicePreference::Female,
-            "male" => VoicePreference::Male,
-            _ => {
+        let preference = match preference {
+            None => user_conf.voice_preference,
+            Some("none") => VoicePreference::NoPreference,
+            Some("female") => VoicePreference::Female,
+            Some("male") => VoicePreference::Male,
+            Some(_) => {
                 if let Err(e) = interaction
                     .edit_response(
                         &ctx.http,
@@ -87,8 +102,30 @@ impl CommandTrait for Command {
                 return Ok(());
             }
         };
-        
-        if user_conf.voice_preference == option {
+        let voice = match voice {
+            None => user_conf.voice,
+            Some("none") => None,
+            Some(name) => match TTSVoice::by_name(name) {
+                Some(v) => Some(v),
+                None => {
+                    if let Err(e) = interaction
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::new()
+                                .content(format!("`{}` isn't a voice I know", name)),
+                        )
+                        .await
+                    {
+                        log::error!("Failed to edit original interaction response: {:?}", e);
+                    }
+                    return Ok(());
+                }
+            },
+        };
+
+        if user_conf.voice_preference == preference
+            && user_conf.voice.map(|v| v.name) == voice.map(|v| v.name)
+        {
             if let Err(e) = interaction
                 .edit_response(
                     &ctx.http,
@@ -102,7 +139,8 @@ impl CommandTrait for Command {
             return Ok(());
         }
 
-        user_conf.voice_preference = option;
+        user_conf.voice_preference = preference;
+        user_conf.voice = voice;
         if let Err(e) = user_conf.save().await {
             log::error!("Failed to save user: {:?}", e);
             if let Err(e) = interaction
@@ -116,18 +154,23 @@ impl CommandTrait for Command {
             }
             return Ok(());
         }
-        if let Err(e) = interaction
-            .edit_response(
-                &ctx.http,
-                EditInteractionResponse::new().content(
-                    match option {
-                        VoicePreference::NoPreference => "Your voice preference has been unset.",
-                        VoicePreference::Male => "Your voice preference has been set to male.",
-                        VoicePreference::Female => "Your voice preference has been set to female.",
-                    },
-                ))
-            .await
-        {
+        let mut response = EditInteractionResponse::new().content(match voice {
+            Some(v) => format!("Your messages will now be read by {}.", v.describe()),
+            None => match preference {
+                VoicePreference::NoPreference => "Your voice preference has been unset.",
+                VoicePreference::Male => "Your voice preference has been set to male.",
+                VoicePreference::Female => "Your voice preference has been set to female.",
+            }
+            .to_owned(),
+        });
+        if let Some(v) = voice {
+            response = response.button(
+                CreateButton::new(format!("{}{}", PREVIEW_PREFIX, v.name))
+                    .label("Preview")
+                    .style(ButtonStyle::Secondary),
+            );
+        }
+        if let Err(e) = interaction.edit_response(&ctx.http, response).await {
             log::error!("Failed to edit original interaction response: {:?}", e);
         }
         Ok(())
@@ -135,4 +178,94 @@ impl CommandTrait for Command {
     fn command_name(&self) -> &str {
         "voice_preference"
     }
+    async fn autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        let partial = match interaction.data.autocomplete() {
+            Some(o) if o.name == "voice" => o.value.to_lowercase(),
+            _ => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        if "none".contains(&partial) {
+            completions = completions.add_string_choice("No specific voice", "none");
+        }
+        for voice in ALL_VOICES
+            .iter()
+            .filter(|v| {
+                v.name.to_lowercase().contains(&partial)
+                    || v.describe().to_lowercase().contains(&partial)
+            })
+            .take(24)
+        {
+            completions = completions.add_string_choice(voice.describe(), voice.name);
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to create interaction response: {:?}", e);
+        }
+        Ok(())
+    }
+}
+/// speaks a sample of the voice named in a preview button's custom id back to whoever clicked it
+pub async fn preview(ctx: &Context, interaction: &ComponentInteraction, name: &str) {
+    if let Err(e) = interaction.defer_ephemeral(&ctx.http).await {
+        log::error!("Failed to send response: {}", e);
+    }
+    let voice = match TTSVoice::by_name(name) {
+        Some(v) => v,
+        None => {
+            preview_failed(ctx, interaction, "That voice doesn't exist anymore").await;
+            return;
+        }
+    };
+    // google first, the other backends would ignore the voice. it still counts towards the quota
+    let video = match long_term_storage::tts_speak_with(
+        PREVIEW_TEXT,
+        Some(voice),
+        &[TtsBackendKind::Google],
+        &Dictionary::default(),
+        long_term_storage::tts_guild(interaction.guild_id).await.as_ref(),
+        Some(interaction.user.id),
+    )
+    .await
+    {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Failed to generate voice preview: {:?}", e);
+            preview_failed(ctx, interaction, "Failed to generate a preview").await;
+            return;
+        }
+    };
+    let file = match CreateAttachment::path(&video.path()).await {
+        Ok(f) => f.description(voice.describe()),
+        Err(e) => {
+            log::error!("Failed to attach voice preview: {:?}", e);
+            preview_failed(ctx, interaction, "Failed to generate a preview").await;
+            return;
+        }
+    };
+    if let Err(e) = interaction
+        .create_followup(
+            &ctx.http,
+            CreateInteractionResponseFollowup::new()
+                .add_file(file)
+                .ephemeral(true),
+        )
+        .await
+    {
+        log::error!("Failed to send response: {}", e);
+    }
+}
+async fn preview_failed(ctx: &Context, interaction: &ComponentInteraction, content: &str) {
+    if let Err(e) = interaction
+        .create_followup(
+            &ctx.http,
+            CreateInteractionResponseFollowup::new()
+                .content(content)
+                .ephemeral(true),
+        )
+        .await
+    {
+        log::error!("Failed to send response: {}", e);
+    }
 }
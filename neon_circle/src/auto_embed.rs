@@ -0,0 +1,108 @@
+use crate::commands::embed::max_upload_size;
+use crate::context_menu::embed_video::find_links;
+use common::serenity::all::*;
+use common::tokio::sync::Mutex;
+use common::video::{ClipRange, MediaType, Video, VideoType};
+use common::{lazy_static, log};
+use long_term_storage::AutoEmbed;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+lazy_static::lazy_static! {
+    // when links from a domain were last embedded in a channel, used for the per minute rate limit
+    static ref RECENT_EMBEDS: Mutex<HashMap<(ChannelId, Arc<str>), VecDeque<Instant>>> =
+        Mutex::new(HashMap::new());
+}
+pub async fn handle(ctx: Context, message: Message) {
+    if !message.content.contains("http") {
+        return;
+    }
+    let settings = match AutoEmbed::for_channel(message.channel_id).await {
+        Ok(Some(settings)) => settings,
+        Ok(None) => return,
+        Err(e) => {
+            log::error!("Failed to load auto embed settings: {}", e);
+            return;
+        }
+    };
+    let guild_limit = max_upload_size(&ctx, message.guild_id);
+    for (url, spoiler) in find_links(&message.content) {
+        let domain = match host(&url).and_then(|h| settings.domain_for(h)) {
+            Some(domain) if domain.enabled => domain,
+            _ => continue,
+        };
+        if !take_rate_limit(message.channel_id, domain).await {
+            log::trace!("Rate limited auto embed for {}", domain.domain);
+            continue;
+        }
+        let max_size = match domain.max_size_mb {
+            Some(mb) if mb < parse_megabytes(guild_limit) => format!("{}M", mb),
+            _ => guild_limit.to_owned(),
+        };
+        let video = match Video::download_video(
+            &url,
+            MediaType::Video,
+            spoiler,
+            &max_size,
+            ClipRange::default(),
+        )
+        .await
+        {
+            Ok(VideoType::Disk(video)) => video,
+            Ok(VideoType::Url(_)) => continue,
+            Err(e) => {
+                log::warn!("Failed to auto embed {}: {}", url, e);
+                continue;
+            }
+        };
+        let file = match CreateAttachment::path(&video.path()).await {
+            Ok(f) => f,
+            Err(e) => {
+                log::error!("Failed to read auto embed file: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = message
+            .channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .add_file(file)
+                    .reference_message(&message)
+                    .allowed_mentions(CreateAllowedMentions::new().replied_user(false))
+                    .flags(MessageFlags::SUPPRESS_NOTIFICATIONS),
+            )
+            .await
+        {
+            log::error!("Failed to send auto embed: {}", e);
+        }
+    }
+}
+async fn take_rate_limit(channel_id: ChannelId, domain: &long_term_storage::AutoEmbedDomain) -> bool {
+    let mut recent = RECENT_EMBEDS.lock().await;
+    let times = recent
+        .entry((channel_id, Arc::clone(&domain.domain)))
+        .or_default();
+    while times
+        .front()
+        .map(|t| t.elapsed() > RATE_LIMIT_WINDOW)
+        .unwrap_or(false)
+    {
+        times.pop_front();
+    }
+    if times.len() >= domain.per_minute as usize {
+        return false;
+    }
+    times.push_back(Instant::now());
+    true
+}
+fn parse_megabytes(size: &str) -> u32 {
+    size.trim_end_matches('M').parse().unwrap_or(8)
+}
+fn host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    Some(host.strip_prefix("www.").unwrap_or(host))
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use common::log;
 use common::serenity::all::*;
-use common::video::{MediaType, Video, VideoType};
+use common::utils::parse_timestamp;
+use common::video::{ClipRange, MediaType, Video, VideoType};
 #[derive(Debug, Clone)]
 pub struct DlVideo;
 #[async_trait]
@@ -28,6 +29,8 @@ impl crate::traits::CommandTrait for DlVideo {
                         "Whether to spoiler the video",
                     )
                     .required(false),
+                    start_option(),
+                    end_option(),
                 ]),
         )
     }
@@ -48,18 +51,38 @@ impl crate::traits::CommandTrait for DlAudio {
             CreateCommand::new(self.command_name())
                 .contexts(vec![InteractionContext::Guild, InteractionContext::BotDm])
                 .description("Embed some audio using ytdl")
-                .set_options(vec![CreateCommandOption::new(
-                    CommandOptionType::String,
-                    "audio_url",
-                    "The url of the audio to embed",
-                )
-                .required(true)]),
+                .set_options(vec![
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "audio_url",
+                        "The url of the audio to embed",
+                    )
+                    .required(true),
+                    start_option(),
+                    end_option(),
+                ]),
         )
     }
     fn command_name(&self) -> &str {
         "embed_audio"
     }
 }
+fn start_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "start",
+        "Where the clip should start (mm:ss or hh:mm:ss)",
+    )
+    .required(false)
+}
+fn end_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "end",
+        "Where the clip should end (mm:ss or hh:mm:ss)",
+    )
+    .required(false)
+}
 #[allow(dead_code)]
 fn get_command_data_option_name(option: &CommandDataOptionValue) -> String {
     match option {
@@ -75,6 +98,24 @@ fn get_command_data_option_name(option: &CommandDataOptionValue) -> String {
     }
     .to_owned()
 }
+pub fn max_upload_size(ctx: &Context, guild_id: Option<GuildId>) -> &'static str {
+    let mut max_size = "8M";
+    if let Some(guild_id) = guild_id {
+        let guild = guild_id.to_guild_cached(&ctx.cache);
+        if let Some(guild) = guild {
+            match guild.premium_tier {
+                PremiumTier::Tier3 => max_size = "100M",
+                PremiumTier::Tier2 => max_size = "50M",
+                _ => {}
+            }
+        } else {
+            log::warn!("No guild in cache");
+        }
+    } else {
+        log::trace!("No guild id in interaction");
+    }
+    max_size
+}
 async fn dotheroar(ctx: &Context, interaction: &CommandInteraction) {
     match interaction.defer_ephemeral(&ctx.http).await {
         Ok(_) => {}
@@ -114,22 +155,40 @@ async fn dotheroar(ctx: &Context, interaction: &CommandInteraction) {
         Some(ResolvedValue::Boolean(spoiler)) => *spoiler,
         _ => false,
     };
-    let mut max_size = "8M";
-    if let Some(guild_id) = interaction.guild_id {
-        let guild = guild_id.to_guild_cached(&ctx.cache);
-        if let Some(guild) = guild {
-            match guild.premium_tier {
-                PremiumTier::Tier3 => max_size = "100M",
-                PremiumTier::Tier2 => max_size = "50M",
+    let clip: Result<ClipRange> = try {
+        let mut start = None;
+        let mut end = None;
+        for o in options.iter() {
+            match (o.name, &o.value) {
+                ("start", ResolvedValue::String(s)) => start = Some(parse_timestamp(s)?),
+                ("end", ResolvedValue::String(s)) => end = Some(parse_timestamp(s)?),
                 _ => {}
             }
-        } else {
-            log::warn!("No guild in cache");
         }
-    } else {
-        log::trace!("No guild id in interaction");
-    }
-    match Video::download_video(option, media_type, spoiler, max_size).await {
+        ClipRange::new(start, end)?
+    };
+    let clip = match clip {
+        Ok(clip) => clip,
+        Err(e) => {
+            if let Err(e) = interaction
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new().add_embed(
+                        builder::CreateEmbed::default()
+                            .title("Error")
+                            .description(format!("{}", e))
+                            .color(Color::RED),
+                    ),
+                )
+                .await
+            {
+                log::error!("Error editing original interaction response: {}", e);
+            }
+            return;
+        }
+    };
+    let max_size = max_upload_size(ctx, interaction.guild_id);
+    match Video::download_video(option, media_type, spoiler, max_size, clip).await {
         Err(e) => match interaction
             .edit_response(
                 &ctx.http,
@@ -181,12 +240,12 @@ async fn dotheroar(ctx: &Context, interaction: &CommandInteraction) {
                             log::error!("Error deleting original interaction response: {}", e)
                         }
                     };
-                    if let Err(e) = interaction
-                        .create_followup(
-                            &ctx.http,
-                            CreateInteractionResponseFollowup::new().add_file(file),
-                        )
-                        .await
+                    let mut followup = CreateInteractionResponseFollowup::new().add_file(file);
+                    match video.size_and_quality() {
+                        Ok(quality) => followup = followup.content(format!("-# {}", quality)),
+                        Err(e) => log::warn!("Failed to get video quality: {}", e),
+                    }
+                    if let Err(e) = interaction.create_followup(&ctx.http, followup).await
                     {
                         match interaction
                             .create_followup(
@@ -2,3 +2,4 @@ pub mod embed;
 pub mod feedback;
 pub mod john;
 pub mod remind;
+pub mod schedule;
@@ -470,7 +470,7 @@ impl SubCommandTrait for Me {
     }
 }
 
-async fn parse_time_and_date(
+pub(crate) async fn parse_time_and_date(
     user_id: UserId,
     message: &str,
 ) -> Result<chrono::DateTime<chrono_tz::Tz>> {
@@ -0,0 +1,465 @@
+use anyhow::Result;
+use common::serenity::all::*;
+use common::utils::respond;
+use common::{chrono, log, SubCommandTrait};
+use long_term_storage::{RadioPreset, Schedule, ScheduleAction, ScheduleRepeat};
+pub struct Command {
+    subcommands: Vec<Box<dyn SubCommandTrait>>,
+}
+impl Command {
+    pub fn new() -> Self {
+        Self {
+            subcommands: vec![Box::new(Add), Box::new(List), Box::new(Remove)],
+        }
+    }
+}
+#[async_trait]
+impl crate::traits::CommandTrait for Command {
+    fn register_command(&self) -> Option<CreateCommand> {
+        Some(
+            CreateCommand::new(self.command_name())
+                .description("Play something or switch the radio at a set time")
+                .contexts(vec![InteractionContext::Guild])
+                .default_member_permissions(Permissions::MANAGE_GUILD)
+                .set_options(
+                    self.subcommands
+                        .iter()
+                        .map(|sc| sc.register_command())
+                        .collect(),
+                ),
+        )
+    }
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        if let Err(e) = interaction.defer_ephemeral(&ctx.http).await {
+            log::error!("Failed to send response: {}", e);
+        }
+        let (subcommand, opts) = match interaction.data.options().into_iter().find_map(|o| match o
+            .value
+        {
+            ResolvedValue::SubCommand(opts) => Some((o.name, opts)),
+            _ => None,
+        }) {
+            None => {
+                unreachable!();
+            }
+            Some(s) => s,
+        };
+        for sc in &self.subcommands {
+            if sc.command_name() == subcommand {
+                return sc.run(ctx, interaction, &opts).await;
+            }
+        }
+        respond(ctx, interaction, "Invalid subcommand").await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "schedule"
+    }
+    async fn autocomplete(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        for option in interaction.data.options() {
+            for sc in &self.subcommands {
+                if sc.command_name() == option.name {
+                    match option.value {
+                        ResolvedValue::SubCommand(opts) => {
+                            return sc.autocomplete(ctx, interaction, &opts).await;
+                        }
+                        _ => {
+                            return Err(anyhow::anyhow!("Invalid option type"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Add;
+#[async_trait]
+impl SubCommandTrait for Add {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Schedule something to play (give either play or radio)",
+        )
+        .set_sub_options(vec![
+            CreateCommandOption::new(CommandOptionType::Channel, "channel", "The voice channel")
+                .channel_types(vec![ChannelType::Voice, ChannelType::Stage])
+                .required(true),
+            CreateCommandOption::new(CommandOptionType::String, "at", "When to run it")
+                .required(true)
+                .set_autocomplete(true),
+            CreateCommandOption::new(CommandOptionType::String, "repeat", "How often to repeat it")
+                .add_string_choice("Never", "never")
+                .add_string_choice("Every day", "daily")
+                .add_string_choice("Every week", "weekly"),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "play",
+                "A url or search to queue, same as /add",
+            ),
+            CreateCommandOption::new(CommandOptionType::String, "radio", "A radio station to switch to")
+                .set_autocomplete(true),
+        ])
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let user = match long_term_storage::User::load(interaction.user.id).await {
+            Ok(user) => user,
+            Err(e) => {
+                log::error!("Failed to load user: {}", e);
+                respond(ctx, interaction, "Failed to load user").await;
+                return Ok(());
+            }
+        };
+        let mut channel = None;
+        let mut at = None;
+        let mut repeat = ScheduleRepeat::Never;
+        let mut play = None;
+        let mut radio = None;
+        for option in options {
+            match (option.name, &option.value) {
+                ("channel", ResolvedValue::Channel(c)) => channel = Some(c.id),
+                ("at", ResolvedValue::String(s)) if *s != "::INVALID" => {
+                    at = match s.parse::<i64>() {
+                        Ok(i) => chrono::DateTime::from_timestamp(i, 0)
+                            .map(|dt| dt.with_timezone(&user.timezone)),
+                        Err(e) => {
+                            log::error!("Failed to parse timestamp: {}", e);
+                            None
+                        }
+                    }
+                }
+                ("repeat", ResolvedValue::String("daily")) => repeat = ScheduleRepeat::Daily,
+                ("repeat", ResolvedValue::String("weekly")) => repeat = ScheduleRepeat::Weekly,
+                ("play", ResolvedValue::String(s)) => play = Some(s.to_string()),
+                ("radio", ResolvedValue::String(s)) => radio = Some(s.to_string()),
+                _ => {}
+            }
+        }
+        let (channel, at) = match (channel, at) {
+            (Some(channel), Some(at)) => (channel, at),
+            (None, _) => {
+                respond(ctx, interaction, "Pick a voice channel").await;
+                return Ok(());
+            }
+            (_, None) => {
+                respond(
+                    ctx,
+                    interaction,
+                    "Couldn't understand that time, pick one of the suggestions",
+                )
+                .await;
+                return Ok(());
+            }
+        };
+        if at < chrono::Utc::now() {
+            respond(ctx, interaction, "Schedules must be in the future").await;
+            return Ok(());
+        }
+        let action = match (play, radio) {
+            (Some(query), None) => ScheduleAction::Play(query),
+            (None, Some(name)) => match RadioPreset::load_opt(guild_id, &name).await {
+                Ok(Some(_)) => ScheduleAction::SwitchRadio(name),
+                Ok(None) => {
+                    respond(
+                        ctx,
+                        interaction,
+                        &format!("There's no radio station named `{}`, see `/radio list`", name),
+                    )
+                    .await;
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::error!("Failed to load radio preset: {:?}", e);
+                    respond(ctx, interaction, "Failed to load radio station").await;
+                    return Ok(());
+                }
+            },
+            _ => {
+                respond(ctx, interaction, "Give either something to play or a radio station").await;
+                return Ok(());
+            }
+        };
+        let schedule = match Schedule::new(
+            interaction.user.id,
+            guild_id,
+            channel,
+            action,
+            at,
+            repeat,
+        )
+        .await
+        {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to create schedule: {}", e);
+                respond(ctx, interaction, "Failed to create schedule").await;
+                return Ok(());
+            }
+        };
+        respond(
+            ctx,
+            interaction,
+            &format!(
+                "Scheduled {}\nWith your configured timezone `{}`, change it with `/remind timezone`",
+                describe(&schedule),
+                user.timezone,
+            ),
+        )
+        .await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "add"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let (name, value) = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } => Some((o.name, value)),
+            _ => None,
+        }) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        match name {
+            "at" if value.is_empty() => return Ok(()),
+            "at" => match super::remind::parse_time_and_date(interaction.user.id, value).await {
+                Ok(time) => {
+                    completions = completions.add_string_choice(
+                        common::utils::full_datetime_format(&time, true),
+                        time.to_utc().timestamp().to_string(),
+                    );
+                }
+                Err(e) => {
+                    let mut err = e.to_string();
+                    if err.len() > 100 {
+                        err.truncate(97);
+                        err.push_str("...");
+                    }
+                    completions = completions.add_string_choice(err, "::INVALID");
+                }
+            },
+            "radio" => {
+                let guild_id = match interaction.guild_id {
+                    Some(id) => id,
+                    None => return Ok(()),
+                };
+                let partial = value.to_lowercase();
+                for preset in RadioPreset::all(guild_id)
+                    .await?
+                    .iter()
+                    .filter(|p| p.name.to_lowercase().contains(&partial))
+                    .take(25)
+                {
+                    completions =
+                        completions.add_string_choice(preset.name.as_ref(), preset.name.as_ref());
+                }
+            }
+            _ => return Ok(()),
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to send response: {}", e);
+        }
+        Ok(())
+    }
+}
+
+struct List;
+#[async_trait]
+impl SubCommandTrait for List {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "List this server's schedules",
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        _options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => {
+                respond(ctx, interaction, "This command can only be used in a server").await;
+                return Ok(());
+            }
+        };
+        let schedules = match Schedule::all_for_guild(guild_id).await {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Failed to list schedules: {}", e);
+                respond(ctx, interaction, "Failed to list schedules").await;
+                return Ok(());
+            }
+        };
+        if schedules.is_empty() {
+            respond(ctx, interaction, "This server has no schedules, add one with `/schedule add`")
+                .await;
+            return Ok(());
+        }
+        let mut content = String::new();
+        for schedule in schedules.iter() {
+            let line = format!("- {}\n", describe(schedule));
+            // leave room for the "and more" line
+            if content.len() + line.len() > 1900 {
+                content.push_str("...and more");
+                break;
+            }
+            content.push_str(&line);
+        }
+        respond(ctx, interaction, &content).await;
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "list"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+}
+
+struct Remove;
+#[async_trait]
+impl SubCommandTrait for Remove {
+    fn register_command(&self) -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            self.command_name(),
+            "Remove a schedule",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "schedule", "The schedule")
+                .required(true)
+                .set_autocomplete(true),
+        )
+    }
+    async fn run(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let id = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::String(s) if o.name == "schedule" => Some(s),
+            _ => None,
+        }) {
+            Some(id) => id,
+            None => {
+                respond(ctx, interaction, "Pick a schedule to remove").await;
+                return Ok(());
+            }
+        };
+        let schedule = match Schedule::from_id(id).await {
+            Ok(s) if Some(s.guild_id) == interaction.guild_id => s,
+            _ => {
+                respond(ctx, interaction, "Couldn't find that schedule").await;
+                return Ok(());
+            }
+        };
+        let description = describe(&schedule);
+        match schedule.delete().await {
+            Ok(()) => respond(ctx, interaction, &format!("Removed {}", description)).await,
+            Err(e) => {
+                log::error!("Failed to delete schedule: {}", e);
+                respond(ctx, interaction, "Failed to remove schedule").await;
+            }
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "remove"
+    }
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_GUILD
+    }
+    async fn autocomplete(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        options: &[ResolvedOption],
+    ) -> Result<()> {
+        let guild_id = match interaction.guild_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let partial = match options.iter().find_map(|o| match o.value {
+            ResolvedValue::Autocomplete { value, .. } if o.name == "schedule" => Some(value),
+            _ => None,
+        }) {
+            Some(p) => p.to_lowercase(),
+            None => return Ok(()),
+        };
+        let mut completions = CreateAutocompleteResponse::new();
+        let schedules = Schedule::all_for_guild(guild_id).await?;
+        let matching = schedules.iter().filter_map(|schedule| {
+            // autocomplete can't render timestamps or mentions so this is plainer than describe
+            let name = format!(
+                "{} {}, {}",
+                match schedule.action {
+                    ScheduleAction::Play(ref query) => format!("Play {}", query),
+                    ScheduleAction::SwitchRadio(ref name) => format!("Switch radio to {}", name),
+                },
+                common::utils::full_datetime_format(&schedule.run_at, true),
+                schedule.repeat,
+            );
+            name.to_lowercase()
+                .contains(&partial)
+                .then_some((name, schedule.id()))
+        });
+        // discord rejects the whole response past 25 choices
+        for (mut name, id) in matching.take(25) {
+            if name.len() > 100 {
+                name = name.chars().take(97).collect();
+                name.push_str("...");
+            }
+            completions = completions.add_string_choice(name, id.to_string());
+        }
+        if let Err(e) = interaction
+            .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(completions))
+            .await
+        {
+            log::error!("Failed to send response: {}", e);
+        }
+        Ok(())
+    }
+}
+
+fn describe(schedule: &Schedule) -> String {
+    format!(
+        "{} in <#{}> at <t:{}:F>, {}",
+        match schedule.action {
+            ScheduleAction::Play(ref query) => format!("playing `{}`", query),
+            ScheduleAction::SwitchRadio(ref name) => format!("switching the radio to `{}`", name),
+        },
+        schedule.channel_id.get(),
+        schedule.run_at.timestamp(),
+        schedule.repeat,
+    )
+}
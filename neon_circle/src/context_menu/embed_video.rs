@@ -1 +1,226 @@
+use anyhow::Result;
+use common::log;
+use common::serenity::all::*;
+use common::serenity::futures::{stream, StreamExt as _};
+use common::video::{ClipRange, MediaType, Video, VideoType};
+// discord will not take more than this many attachments in a single message
+const MAX_ATTACHMENTS: usize = 10;
+// anything past this many links in one message is ignored, it's a message not a playlist
+const MAX_LINKS: usize = 10;
+// each download is a yt-dlp and often an ffmpeg process, so only a few run at once
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+// discord rejects embeds with a longer description
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+// a single yt-dlp error can be long enough to crowd out every other link
+const MAX_FAILURE_LENGTH: usize = 500;
+#[derive(Debug, Clone)]
+pub struct Command;
+#[async_trait]
+impl crate::traits::CommandTrait for Command {
+    fn register_command(&self) -> Option<CreateCommand> {
+        Some(
+            CreateCommand::new(self.command_name())
+                .kind(CommandType::Message)
+                .contexts(vec![InteractionContext::Guild, InteractionContext::BotDm]),
+        )
+    }
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        if let Err(e) = interaction.defer_ephemeral(&ctx.http).await {
+            log::error!("Error deferring: {}", e);
+        }
+        let message = match interaction.data.target() {
+            Some(ResolvedTarget::Message(message)) => message,
+            _ => {
+                if let Err(e) = interaction
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::new().add_embed(
+                            builder::CreateEmbed::default()
+                                .title("Error")
+                                .description("Could not find the target message")
+                                .color(Color::RED),
+                        ),
+                    )
+                    .await
+                {
+                    log::error!("Error editing original interaction response: {}", e);
+                }
+                return Ok(());
+            }
+        };
+        let links = find_links(&message.content);
+        if links.is_empty() {
+            if let Err(e) = interaction
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::new().add_embed(
+                        builder::CreateEmbed::default()
+                            .title("Error")
+                            .description("That message does not contain any links")
+                            .color(Color::RED),
+                    ),
+                )
+                .await
+            {
+                log::error!("Error editing original interaction response: {}", e);
+            }
+            return Ok(());
+        }
+        let max_size = crate::commands::embed::max_upload_size(ctx, interaction.guild_id);
+        let results = stream::iter(links.iter().map(|(url, spoiler)| {
+            Video::download_video(url, MediaType::Video, *spoiler, max_size, ClipRange::default())
+        }))
+        .buffered(MAX_CONCURRENT_DOWNLOADS)
+        .collect::<Vec<_>>()
+        .await;
+        // the videos have to stay alive until they are uploaded, dropping them deletes the file
+        let mut videos = Vec::new();
+        let mut files = Vec::new();
+        let mut failures = Vec::new();
+        for ((url, _), result) in links.iter().zip(results) {
+            match result {
+                Ok(VideoType::Disk(video)) => match CreateAttachment::path(&video.path()).await {
+                    Ok(file) => {
+                        files.push(file);
+                        videos.push(video);
+                    }
+                    Err(e) => failures.push(format!("<{}>: {}", url, e)),
+                },
+                Ok(VideoType::Url(_)) => {
+                    failures.push(format!("<{}>: Video was not downloaded", url));
+                }
+                Err(e) => failures.push(format!("<{}>: {}", url, e)),
+            }
+        }
+        if !files.is_empty() {
+            // followups to a deferred ephemeral response are ephemeral too until it is gone
+            if let Err(e) = interaction.delete_response(&ctx.http).await {
+                log::error!("Error deleting original interaction response: {}", e);
+            }
+        }
+        for chunk in files.chunks(MAX_ATTACHMENTS) {
+            if let Err(e) = interaction
+                .create_followup(
+                    &ctx.http,
+                    CreateInteractionResponseFollowup::new().add_files(chunk.to_vec()),
+                )
+                .await
+            {
+                failures.push(format!("Failed to upload: {}", e));
+            }
+        }
+        drop(videos);
+        if failures.is_empty() {
+            return Ok(());
+        }
+        let embed = builder::CreateEmbed::default()
+            .title("Error")
+            .description(describe_failures(&failures))
+            .color(Color::RED);
+        if files.is_empty() {
+            if let Err(e) = interaction
+                .edit_response(&ctx.http, EditInteractionResponse::new().add_embed(embed))
+                .await
+            {
+                log::error!("Error editing original interaction response: {}", e);
+            }
+        } else if let Err(e) = interaction
+            .create_followup(
+                &ctx.http,
+                CreateInteractionResponseFollowup::new()
+                    .add_embed(embed)
+                    .ephemeral(true),
+            )
+            .await
+        {
+            log::error!("Fatal error creating followup message: {}", e);
+        }
+        Ok(())
+    }
+    fn command_name(&self) -> &str {
+        "Embed this"
+    }
+}
+// joins as many failures as fit in an embed description, noting how many were left out
+fn describe_failures(failures: &[String]) -> String {
+    let mut lines = Vec::new();
+    let mut length = 0;
+    for (i, failure) in failures.iter().enumerate() {
+        let line = failure.chars().take(MAX_FAILURE_LENGTH).collect::<String>();
+        // leaves room for the line saying how many were left out
+        if length + line.len() + 32 > MAX_DESCRIPTION_LENGTH {
+            lines.push(format!("...and {} more", failures.len() - i));
+            break;
+        }
+        length += line.len() + 1;
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+// returns the first MAX_LINKS links in the message, each with whether it was inside a ||spoiler||
+pub(crate) fn find_links(content: &str) -> Vec<(String, bool)> {
+    let sections = content.split("||").collect::<Vec<&str>>();
+    let mut links: Vec<(String, bool)> = Vec::new();
+    for (i, section) in sections.iter().enumerate() {
+        // odd sections are between a pair of ||, unless the last one was never closed
+        let spoiler = i % 2 == 1 && i + 1 < sections.len();
+        for word in section.split_whitespace() {
+            let word = word.trim_start_matches('<').trim_end_matches('>');
+            if !(word.starts_with("https://") || word.starts_with("http://")) {
+                continue;
+            }
+            if let Some((_, s)) = links.iter_mut().find(|(l, _)| l == word) {
+                *s |= spoiler;
+            } else if links.len() < MAX_LINKS {
+                links.push((word.to_owned(), spoiler));
+            }
+        }
+    }
+    links
+}
+#[cfg(test)]
+mod test {
+    use super::*;
 
+    #[test]
+    fn spoilers() {
+        let links = find_links("look ||https://a.com/1|| and https://a.com/2 ||https://a.com/3");
+        assert_eq!(
+            links,
+            vec![
+                ("https://a.com/1".to_owned(), true),
+                ("https://a.com/2".to_owned(), false),
+                // the last || is never closed, so it isn't a spoiler
+                ("https://a.com/3".to_owned(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_link_keeps_spoiler() {
+        let links = find_links("<https://a.com/1> ||https://a.com/1||");
+        assert_eq!(links, vec![("https://a.com/1".to_owned(), true)]);
+    }
+
+    #[test]
+    fn link_cap() {
+        let content = (0..15)
+            .map(|i| format!("https://a.com/{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let links = find_links(&content);
+        assert_eq!(links.len(), MAX_LINKS);
+        assert_eq!(links.last().unwrap().0, format!("https://a.com/{}", MAX_LINKS - 1));
+    }
+
+    #[test]
+    fn failures_fit_in_a_description() {
+        let failures = (0..20)
+            .map(|i| format!("<https://a.com/{}>: {}", i, "x".repeat(1000)))
+            .collect::<Vec<_>>();
+        let description = describe_failures(&failures);
+        assert!(description.len() <= MAX_DESCRIPTION_LENGTH);
+        assert!(description.ends_with("more"));
+        assert_eq!(describe_failures(&failures[..2]).lines().count(), 2);
+    }
+}
@@ -7,6 +7,7 @@
     clippy::clone_on_ref_ptr
 )]
 #![allow(clippy::needless_return)]
+mod auto_embed;
 mod commands;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -154,6 +155,14 @@ impl EventHandler for PlanetHandler {
                 log::info!("Ping received: {:?}", p);
             }
             Interaction::Component(mci) => {
+                if let Some(name) = mci
+                    .data
+                    .custom_id
+                    .strip_prefix(music_commands::voice_preference::PREVIEW_PREFIX)
+                {
+                    music_commands::voice_preference::preview(&ctx, &mci, name).await;
+                    return;
+                }
                 // special case for feedback response
                 if let Ok(feedback_id) = FeedbackCustomId::try_from(mci.data.custom_id.as_str()) {
                     let feedback = {
@@ -666,7 +675,7 @@ impl EventHandler for PlanetHandler {
                             return;
                         }
                         match cmd {
-                            original_command if ["pause", "skip", "stop", "looped", "shuffle", "repeat", "autoplay", "read_titles"].iter().any(|a| *a == original_command) => {
+                            original_command if ["pause", "skip", "stop", "tts_clear", "looped", "shuffle", "repeat", "autoplay", "read_titles"].iter().any(|a| *a == original_command) => {
                                 let guild_id = match mci.guild_id {
                                     Some(id) => id,
                                     None => {
@@ -707,6 +716,7 @@ impl EventHandler for PlanetHandler {
                                                     "pause" => AudioPromiseCommand::Paused(OrToggle::Toggle),
                                                     "skip" => AudioPromiseCommand::Skip,
                                                     "stop" => AudioPromiseCommand::Stop(None),
+                                                    "tts_clear" => AudioPromiseCommand::TtsClear,
                                                     "looped" => AudioPromiseCommand::Loop(OrToggle::Toggle),
                                                     "shuffle" => AudioPromiseCommand::Shuffle(OrToggle::Toggle),
                                                     "repeat" => AudioPromiseCommand::Repeat(OrToggle::Toggle),
@@ -944,6 +954,47 @@ impl EventHandler for PlanetHandler {
                                     }
                                 }
                             }
+                            station if let Some(name) = station.strip_prefix("station:") => {
+                                let preset = match long_term_storage::RadioPreset::load_opt(guild_id, name).await {
+                                    Ok(Some(preset)) => preset,
+                                    Ok(None) => {
+                                        if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(format!("There is no station called `{}`", name)).ephemeral(true))).await {
+                                            log::error!("Failed to send response: {}", e);
+                                        }
+                                        return;
+                                    }
+                                    Err(e) => {
+                                        log::error!("Failed to load radio preset: {:?}", e);
+                                        if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("Failed to load that station").ephemeral(true))).await {
+                                            log::error!("Failed to send response: {}", e);
+                                        }
+                                        return;
+                                    }
+                                };
+                                let audio_command_handler = match ctx.data.read().await.get::<AudioCommandHandler>() {
+                                    Some(a) => Arc::clone(a),
+                                    None => {
+                                        log::error!("Expected AudioCommandHandler in TypeMap");
+                                        if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("Failed to get audio command handler").ephemeral(true))).await {
+                                            log::error!("Failed to send response: {}", e);
+                                        }
+                                        return;
+                                    }
+                                };
+                                let mut audio_command_handler = audio_command_handler.write().await;
+                                if let Some(tx) = audio_command_handler.get_mut(&channel) {
+                                    let (rtx, _rrx) = oneshot::channel::<Arc<str>>();
+                                    if let Err(e) = tx.send((rtx, AudioPromiseCommand::SwitchRadio(preset.station()))) {
+                                        if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(format!("Failed to switch station ERR {}", e)).ephemeral(true))).await {
+                                            log::error!("Failed to send response: {}", e);
+                                        }
+                                        return;
+                                    }
+                                }
+                                if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Acknowledge).await {
+                                    log::error!("Failed to send response: {}", e);
+                                }
+                            }
                             p => {
                                 if let Err(e) = mci.create_response(&ctx.http, CreateInteractionResponse::Modal(CreateModal::new("missing_feature_feedback", "Feedback").components(vec![CreateActionRow::InputText(CreateInputText::new(InputTextStyle::Paragraph, format!("How should clicking `{}` work?", p), "feedback").placeholder("Read the discord documentation and figure out what i can ACTUALLY do. I can't think of anything.").required(true))]))).await {
                                     log::error!("Failed to send response: {}", e);
@@ -1354,10 +1405,18 @@ impl EventHandler for PlanetHandler {
         //     }
         // }
     }
-    async fn message(&self, _ctx: Context, new_message: Message) {
-        if new_message.author.bot || new_message.content.trim().is_empty() {
+    async fn message(&self, ctx: Context, new_message: Message) {
+        // attachments and stickers are described even when there's no text with them
+        if new_message.author.id == ctx.cache.current_user().id
+            || (new_message.content.trim().is_empty()
+                && new_message.attachments.is_empty()
+                && new_message.sticker_items.is_empty())
+        {
             return;
         }
+        if !new_message.author.bot {
+            tokio::task::spawn(auto_embed::handle(ctx.clone(), new_message.clone()));
+        }
         // if let Some(guild_id) = global_data::transcribe::get_transcribe(new_message.channel_id) {
         //     let em = match commands::music::get_transcribe_channel_handler(&ctx, &guild_id).await {
         //         Ok(e) => e,
@@ -1369,7 +1428,26 @@ impl EventHandler for PlanetHandler {
         //     em.write().await.send_tts(&ctx, &new_message).await;
         // }
         // global_data::transcribe::send_message(new_message).await;
-        long_term_storage::send_tts_message(new_message).await;
+        // other bots and webhooks are left to each guild's tts filter
+        long_term_storage::send_tts_message(new_message, &ctx.cache).await;
+    }
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        cancel_tts(&ctx, guild_id, vec![deleted_message_id]).await;
+    }
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        guild_id: Option<GuildId>,
+    ) {
+        cancel_tts(&ctx, guild_id, multiple_deleted_messages_ids).await;
     }
     async fn resume(&self, ctx: Context, _: ResumedEvent) {
         log::info!("Resumed");
@@ -1670,6 +1748,7 @@ async fn main() {
             Box::new(music_commands::repeat::Command),
             Box::new(music_commands::loop_queue::Command),
             Box::new(music_commands::voice_preference::Command),
+            Box::new(music_commands::tts::Command::new()),
             Box::new(music_commands::pause::Command),
             Box::new(music_commands::add::Command),
             Box::new(music_commands::add::AddRaw),
@@ -1677,6 +1756,7 @@ async fn main() {
             Box::new(music_commands::setbitrate::Command),
             Box::new(music_commands::remove::Command),
             Box::new(music_commands::resume::Command),
+            Box::new(music_commands::radio::Command::new()),
             Box::new(music_commands::shuffle::Command),
             Box::new(music_commands::skip::Command),
             Box::new(music_commands::stop::Command),
@@ -1686,10 +1766,12 @@ async fn main() {
             Box::new(national_debt::Command),
             Box::new(commands::embed::DlVideo),
             Box::new(commands::embed::DlAudio),
+            Box::new(context_menu::embed_video::Command),
             Box::new(commands::john::Command),
             Box::new(commands::feedback::Feedback),
             Box::new(config_command::Command::new()),
             Box::new(commands::remind::Command::new()),
+            Box::new(commands::schedule::Command::new()),
         ],
         BOTS.planet.playing.clone(),
     );
@@ -1904,8 +1986,36 @@ impl EventHandler for SatelliteHandler {
 
 const SECS: u64 = 5;
 
+// deleted messages shouldn't be read out, every call in the guild drops them if they're waiting
+async fn cancel_tts(ctx: &Context, guild_id: Option<GuildId>, messages: Vec<MessageId>) {
+    let guild_id = match guild_id {
+        Some(g) => g,
+        None => return,
+    };
+    let audio_command_handler = match ctx.data.read().await.get::<AudioCommandHandler>() {
+        Some(a) => Arc::clone(a),
+        None => {
+            log::error!("Expected AudioCommandHandler in TypeMap");
+            return;
+        }
+    };
+    let audio_command_handler = audio_command_handler.read().await;
+    for sender in audio_command_handler.values() {
+        if sender.guild_id != guild_id {
+            continue;
+        }
+        for message in messages.iter() {
+            // nobody is waiting on the response
+            let (tx, _) = oneshot::channel::<Arc<str>>();
+            if let Err(e) = sender.send((tx, AudioPromiseCommand::TtsCancel(*message))) {
+                log::error!("Failed to cancel tts: {:?}", e);
+            }
+        }
+    }
+}
 async fn reminders(ctx: Context, mut rx: oneshot::Receiver<()>) {
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(SECS));
+    let running_schedules = Arc::new(Mutex::new(std::collections::HashSet::new()));
     loop {
         tokio::select! {
             _ = interval.tick() => {
@@ -1932,6 +2042,43 @@ async fn reminders(ctx: Context, mut rx: oneshot::Receiver<()>) {
                         }
                     }
                 };
+                match long_term_storage::Schedule::all_schedules(before).await {
+                    Err(e) => {
+                        log::error!("Failed to get schedules: {}", e);
+                    }
+                    Ok(v) => {
+                        let now = chrono::Utc::now();
+                        for schedule in v.into_iter() {
+                            if schedule.run_at > now {
+                                continue;
+                            }
+                            // joining and resolving can take longer than a tick, don't run it twice
+                            let id = schedule.id().to_string();
+                            if !running_schedules.lock().await.insert(id.clone()) {
+                                continue;
+                            }
+                            let ctx = ctx.clone();
+                            let running_schedules = Arc::clone(&running_schedules);
+                            tokio::task::spawn(async move {
+                                match music_commands::schedule::run(&ctx, &schedule).await {
+                                    Ok(msg) => {
+                                        log::info!("Ran schedule {}: {}", id, msg);
+                                        if let Err(e) = schedule.finished().await {
+                                            log::error!("Failed to mark schedule as finished: {}", e);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::error!("Failed to run schedule {}: {}", id, e);
+                                        if let Err(e) = schedule.failed().await {
+                                            log::error!("Failed to mark schedule as failed: {}", e);
+                                        }
+                                    }
+                                }
+                                running_schedules.lock().await.remove(&id);
+                            });
+                        }
+                    }
+                };
             }
             _ = &mut rx => {
                 break;
@@ -0,0 +1,81 @@
+use common::{
+    log,
+    serenity::all::{Context, GuildId, UserId},
+    tokio::time::Duration,
+};
+use std::{collections::HashMap, sync::Arc};
+/// how long captions pile up before they're posted, a few messages per window keeps well clear of
+/// discord's rate limit of 5 messages every 5 seconds in a channel
+pub const CAPTION_INTERVAL: Duration = Duration::from_secs(4);
+const MAX_MESSAGE_LENGTH: usize = 2000;
+/// transcribed speech waiting to be posted as live captions, oldest first
+#[derive(Debug, Default)]
+pub struct Captions {
+    lines: Vec<(UserId, String)>,
+}
+impl Captions {
+    pub fn push(&mut self, user_id: UserId, text: &str) {
+        let text = text.trim();
+        if !text.is_empty() {
+            self.lines.push((user_id, text.to_owned()));
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+    /// takes everything said so far, formatted as `**Name:** text` and packed into as few
+    /// messages as fit
+    pub async fn take(&mut self, ctx: &Context, guild_id: GuildId) -> Vec<Arc<str>> {
+        let lines = std::mem::take(&mut self.lines);
+        let mut names = HashMap::new();
+        let mut messages = Vec::new();
+        let mut message = String::new();
+        for (user_id, text) in lines {
+            if !names.contains_key(&user_id) {
+                let name = match guild_id.member(ctx, user_id).await {
+                    Ok(member) => member.display_name().to_owned(),
+                    Err(e) => {
+                        log::error!("Failed to get member {}: {:?}", user_id, e);
+                        "Someone".to_owned()
+                    }
+                };
+                names.insert(user_id, name);
+            }
+            let mut line = format!(
+                "**{}:** {}",
+                escape_markdown(&names[&user_id]),
+                escape_markdown(&text)
+            );
+            if line.chars().count() > MAX_MESSAGE_LENGTH {
+                line = line.chars().take(MAX_MESSAGE_LENGTH).collect();
+            }
+            if !message.is_empty()
+                && message.chars().count() + 1 + line.chars().count() > MAX_MESSAGE_LENGTH
+            {
+                messages.push(Arc::from(std::mem::take(&mut message)));
+            }
+            if !message.is_empty() {
+                message.push('\n');
+            }
+            message.push_str(&line);
+        }
+        if !message.is_empty() {
+            messages.push(Arc::from(message));
+        }
+        messages
+    }
+}
+// names and transcripts are shown as they are, not as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '|' | '`' | '>' | '#' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -7,11 +7,18 @@ use common::{
     // rand::seq::SliceRandom as _,
     serenity::all::*,
     tokio::{self, sync::Mutex},
+    tts::{DectalkOptions, DectalkVoice, Dictionary, TtsBackendKind},
     video::{Author, LazyLoadedVideo, MetaVideo, Video, VideoType},
 };
 use std::{pin::Pin, sync::Arc};
 
 use crate::gemini::Engine;
+// feedback is a quick confirmation, so it's read a bit faster than dectalk's usual 180
+const FEEDBACK_DECTALK: DectalkOptions = DectalkOptions {
+    voice: Some(DectalkVoice::Paul),
+    rate: Some(220),
+    allow_commands: false,
+};
 fn filter_input(s: &str) -> String {
     s.to_lowercase()
         .chars()
@@ -26,19 +33,16 @@ fn filter_input(s: &str) -> String {
 pub struct CommandState {
     pub is_conversation: bool,
     pub engine: Arc<Mutex<Engine>>,
-}
-
-impl Default for CommandState {
-    fn default() -> Self {
-        Self::new()
-    }
+    // whose tts quota anything spoken counts towards
+    pub guild_id: GuildId,
 }
 
 impl CommandState {
-    pub fn new() -> Self {
+    pub fn new(guild_id: GuildId) -> Self {
         Self {
             is_conversation: false,
             engine: Arc::new(Mutex::new(Engine::new())),
+            guild_id,
         }
     }
     pub async fn parse_commands(&mut self, s: &str, u: UserId, http: Arc<Http>) -> WithFeedback {
@@ -93,11 +97,12 @@ impl CommandState {
             t if ["play", "add", "queue", "played"].contains(&t) => {
                 let query = args.join(" ");
                 let http = Arc::clone(&http);
+                let guild_id = self.guild_id;
                 if query.replace(' ', "").contains("wonderwall") {
                     WithFeedback::new_with_feedback(
                         Box::pin(async move {
                             Ok(ParsedCommand::Command(AudioPromiseCommand::Play(
-                                get_videos(query, http, u).await?,
+                                get_videos(query, http, guild_id, u).await?,
                             )))
                         }),
                         "Anyway, here's wonderwall",
@@ -108,7 +113,7 @@ impl CommandState {
                     WithFeedback::new_with_feedback(
                         Box::pin(async move {
                             Ok(ParsedCommand::Command(AudioPromiseCommand::Play(
-                                get_videos(query, http, u).await?,
+                                get_videos(query, http, guild_id, u).await?,
                             )))
                         }),
                         &response,
@@ -241,6 +246,7 @@ impl CommandState {
                     .join(" ");
                 let engine = Arc::clone(&self.engine);
                 let is_conversation = self.is_conversation;
+                let guild_id = self.guild_id;
                 let command = Box::pin(async move {
                     // use tokio, begin a timer for 10 seconds, wait for the remainder after our work is done before returning
                     let timer = tokio::time::sleep(tokio::time::Duration::from_secs(6));
@@ -259,16 +265,20 @@ impl CommandState {
                     // log::trace!("Formatted response:\n{}", fmttd);
 
                     let v = if fmttd.contains('\n') && !is_conversation {
-                        dectalk::get_speech(&format!(
-                            "You tried to jailbreak didn't you. \
+                        common::tts::speak(
+                            &format!(
+                                "You tried to jailbreak didn't you. \
                                 Well you aren't costing me $160 in \
                                 google tee tee ess requests.\n\n{}",
-                            fmttd
-                        ))
+                                fmttd
+                            ),
+                            None,
+                            &[TtsBackendKind::Dectalk],
+                        )
                         .await?
                     } else {
-                        common::youtube::get_tts(
-                            if is_conversation {
+                        long_term_storage::tts_speak_with(
+                            &if is_conversation {
                                 fmttd
                             } else {
                                 format!("Yo whattup its ya boy, the oracle.\n{}", fmttd)
@@ -278,6 +288,10 @@ impl CommandState {
                                 "en-US-Studio-Q",
                                 "MALE",
                             )),
+                            &[TtsBackendKind::Google],
+                            &Dictionary::default(),
+                            long_term_storage::tts_guild(Some(guild_id)).await.as_ref(),
+                            Some(u),
                         )
                         .await?
                     };
@@ -470,7 +484,15 @@ async fn get_speech(text: &str) -> Option<Video> {
     } else {
         format!("{}.", text)
     };
-    match dectalk::get_speech(&text).await {
+    match common::tts::speak_with(
+        &text,
+        None,
+        &[TtsBackendKind::Dectalk],
+        &Dictionary::default(),
+        FEEDBACK_DECTALK,
+    )
+    .await
+    {
         Ok(vid) => Some(vid),
         Err(e) => {
             log::error!("Error getting speech: {:?}", e);
@@ -478,33 +500,79 @@ async fn get_speech(text: &str) -> Option<Video> {
         }
     }
 }
-async fn get_videos(query: String, http: Arc<Http>, u: UserId) -> Result<Vec<MetaVideo>> {
+async fn get_videos(
+    query: String,
+    http: Arc<Http>,
+    guild_id: GuildId,
+    u: UserId,
+) -> Result<Vec<MetaVideo>> {
     let vids = Video::get_video(&query, true, true).await;
     match vids {
         Ok(vids) => {
             let mut truevideos = Vec::new();
+            // read out the same way as songs added with /add
+            #[cfg(feature = "tts")]
+            let guild = long_term_storage::tts_guild(Some(guild_id)).await;
+            #[cfg(feature = "tts")]
+            let announcer = guild.as_ref().map(|g| g.announcer.clone()).unwrap_or_default();
+            #[cfg(feature = "tts")]
+            let backends = announcer.backends(long_term_storage::tts_backends(
+                guild.as_ref(),
+                long_term_storage::tts_user(Some(u)).await.as_ref(),
+            ));
+            #[cfg(feature = "tts")]
+            let dictionary = long_term_storage::tts_dictionary(guild.as_ref(), None).await;
             for v in vids {
-                let title = match &v {
-                    VideoType::Disk(v) => v.title(),
-                    VideoType::Url(v) => v.title(),
-                };
                 #[cfg(feature = "tts")]
-                truevideos.push(MetaVideo {
-                    video: v,
-                    ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn(
-                        common::youtube::get_tts(Arc::clone(&title), None),
-                    ))),
-                    // title,
-                    author: http.get_user(u).await.ok().map(|u| Author {
+                {
+                    let (title, artist, duration) = match &v {
+                        VideoType::Disk(v) => (v.title(), None, Some(v.duration())),
+                        VideoType::Url(v) => (v.title(), v.artist(), v.duration()),
+                    };
+                    let author = http.get_user(u).await.ok().map(|u| Author {
                         name: u.name.clone(),
                         pfp_url: u
                             .avatar_url()
                             .clone()
                             .unwrap_or(u.default_avatar_url().clone()),
-                    }),
-                });
+                    });
+                    let text = announcer.announce(&common::tts::Announcement {
+                        title: &title,
+                        artist: artist.as_deref(),
+                        requester: author.as_ref().map(|a| a.name.as_str()),
+                        duration,
+                    });
+                    truevideos.push(MetaVideo {
+                        video: v,
+                        ttsmsg: Some(LazyLoadedVideo::new(tokio::spawn({
+                            let backends = backends.clone();
+                            let dictionary = dictionary.clone();
+                            let voice = announcer.voice;
+                            let guild = guild.clone();
+                            async move {
+                                long_term_storage::tts_speak_with(
+                                    &text,
+                                    voice,
+                                    &backends,
+                                    &dictionary,
+                                    guild.as_ref(),
+                                    Some(u),
+                                )
+                                .await
+                            }
+                        }))),
+                        // title,
+                        author,
+                    });
+                }
                 #[cfg(not(feature = "tts"))]
-                truevideos.push(MetaVideo { video: v, title });
+                {
+                    let title = match &v {
+                        VideoType::Disk(v) => v.title(),
+                        VideoType::Url(v) => v.title(),
+                    };
+                    truevideos.push(MetaVideo { video: v, title });
+                }
             }
             Ok(truevideos)
         }
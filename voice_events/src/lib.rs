@@ -1,14 +1,16 @@
 #![feature(if_let_guard, duration_millis_float, try_blocks, let_chains)]
 #![allow(dead_code)]
+mod captions;
 mod commands;
 mod gemini;
 mod structs;
+mod stt;
 mod user;
+use captions::{Captions, CAPTION_INTERVAL};
 use commands::{ParsedCommand, WithFeedback};
 use common::{
-    anyhow::{self, Result},
     audio::AudioPromiseCommand,
-    get_config, log,
+    log,
     serenity::all::*,
     songbird::{
         self,
@@ -25,13 +27,12 @@ use common::{
         time::{Duration, Instant},
     },
     utils::{DeleteAfterFinish, TranscriptionMessage},
-    PostSomething, WEB_CLIENT,
+    PostSomething,
 };
 use futures::{
     stream::{FuturesOrdered, FuturesUnordered},
     StreamExt as _,
 };
-use serde::Deserialize as _;
 use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
 const SAMPLES_PER_MILLISECOND: f64 = 96.0;
 const MIN_SAMPLES_FOR_TRANSCRIPTION: usize = 64 * 1024;
@@ -42,6 +43,7 @@ pub static EVENTS: &[Event] = &[
 pub async fn transcription_thread(
     call: Arc<common::serenity::prelude::Mutex<Call>>,
     context: Context,
+    guild_id: GuildId,
     otx: mpsc::UnboundedSender<(oneshot::Sender<Arc<str>>, AudioPromiseCommand)>,
     mut commands: mpsc::UnboundedReceiver<TranscriptionMessage>,
     // tx: mpsc::UnboundedSender<(String, UserId)>,
@@ -52,30 +54,47 @@ pub async fn transcription_thread(
     let mut threads: Vec<user::TranscriptionThread> = Vec::new();
     let mut pending_commands = FuturesOrdered::new();
     let mut pending_feedback = FuturesOrdered::new();
+    let mut captions = Captions::default();
+    // off until the main loop says the voice channel has them
+    let mut captioning = false;
+    let mut caption_tick = tokio::time::interval(CAPTION_INTERVAL);
+    caption_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
     loop {
         tokio::select! {
             Some(command) = commands.recv() => {
                 match command {
                     TranscriptionMessage::Stop => {
+                        if !captions.is_empty() {
+                            post_captions(&mut captions, guild_id, &context, &tx).await;
+                        }
                         let mut threads = threads.into_iter().map(|t| t.stop()).collect::<FuturesUnordered<_>>();
                         while threads.next().await.is_some() {
                             log::trace!("Stopped a user thread");
                         }
                         break;
                     }
+                    TranscriptionMessage::Captions(on) => {
+                        captioning = on;
+                        if !on {
+                            captions.clear();
+                        }
+                    }
                 }
             }
             Some(packet) = packets.recv() => {
                 if let Some(thread) = threads.iter().find(|t| t.user_id == packet.user_id) {
                     thread.send(packet);
                 } else {
-                    let thread = user::TranscriptionThread::new(packet.user_id, responses.clone(), Arc::clone(&context.http));
+                    let thread = user::TranscriptionThread::new(packet.user_id, guild_id, responses.clone(), Arc::clone(&context.http));
                     thread.send(packet);
                     threads.push(thread);
                 }
             }
             Some(response) = rx.recv() => {
-                let ThreadResponse { response: WithFeedback { command, feedback }, user_id } = response;
+                let ThreadResponse { response: WithFeedback { command, feedback }, user_id, transcript } = response;
+                if captioning {
+                    captions.push(user_id, &transcript);
+                }
                 if let Some(audio) = feedback {
                     log::trace!("Playing audio for {}", user_id);
                     let mut call = call.lock().await;
@@ -133,11 +152,18 @@ pub async fn transcription_thread(
                     }
                 }
             }
+            _ = caption_tick.tick(), if !captions.is_empty() => {
+                post_captions(&mut captions, guild_id, &context, &tx).await;
+            }
             v = then(&mut pending_feedback) => {
                 match v {
                     Ok(string) => {
                         log::trace!("Feedback: {}", string);
-                        if let Err(e) = tx.send((PostSomething::Text(string), context.cache.current_user().id)) {
+                        let bot_id = context.cache.current_user().id;
+                        if captioning {
+                            // the bot gets its own line in the captions while captioning
+                            captions.push(bot_id, &string);
+                        } else if let Err(e) = tx.send((PostSomething::Text(string), bot_id)) {
                             log::error!("Failed to send feedback to main thread: {:?}", e);
                         }
                     }
@@ -153,6 +179,19 @@ pub async fn transcription_thread(
     call.remove_all_global_events();
 }
 
+// hands what's been said since last time to the main loop, which posts it to the caption channel
+async fn post_captions(
+    captions: &mut Captions,
+    guild_id: GuildId,
+    context: &Context,
+    tx: &mpsc::UnboundedSender<(PostSomething, UserId)>,
+) {
+    for text in captions.take(context, guild_id).await {
+        if let Err(e) = tx.send((PostSomething::Text(text), context.cache.current_user().id)) {
+            log::error!("Failed to send captions to main thread: {:?}", e);
+        }
+    }
+}
 async fn then<T>(queue: &mut FuturesOrdered<T>) -> <T as Future>::Output
 where
     T: Future,
@@ -188,6 +227,8 @@ struct ThreadResponse {
     // action: ThreadResponseAction,
     response: WithFeedback,
     user_id: UserId,
+    // everything they said, whether or not it was a command
+    transcript: String,
 }
 #[derive(Debug)]
 enum ThreadResponseAction {
@@ -362,70 +403,6 @@ pub fn human_readable_bytes(size: usize) -> String {
     }
     format!("{:.2} {}", size, units.get(i).unwrap_or(&"??"))
 }
-async fn transcribe(audio: &[i16]) -> Result<TranscriptionResult> {
-    let cfg = get_config();
-    let response = WEB_CLIENT
-        .post(format!(
-            "{}/transcribe/raw?format=s16le&sample_rate=48000&channels=2",
-            cfg.transcribe_url
-        ))
-        .header("x-token", &cfg.transcribe_token)
-        .header("Content-Type", "multipart/form-data")
-        .body(
-            audio
-                .iter()
-                .flat_map(|i| i.to_le_bytes().to_vec())
-                .collect::<Vec<u8>>(),
-        )
-        .send()
-        .await?
-        .json::<RequestResponse>()
-        .await?;
-    let request_id = match response {
-        RequestResponse::Success { request_id } => request_id,
-        RequestResponse::Error { error } => {
-            return Err(anyhow::anyhow!("Failed to start transcription: {}", error))
-        }
-    };
-    let url = format!("{}/result/{}/wait", cfg.transcribe_url, request_id);
-    let response = WEB_CLIENT
-        .get(url)
-        .header("x-token", cfg.transcribe_token)
-        .send()
-        .await?
-        .json::<TranscriptionResponse>()
-        .await?;
-    match response {
-        TranscriptionResponse::Pending { status } => Err(anyhow::anyhow!(
-            "Transcription is pending, this should not happen: {:?}",
-            status
-        )),
-        TranscriptionResponse::Error { error } => {
-            Err(anyhow::anyhow!("Failed to get transcription: {}", error))
-        }
-        TranscriptionResponse::Success { result } => Ok(result),
-    }
-}
-#[derive(Debug, Clone, serde::Deserialize)]
-#[serde(untagged)]
-enum RequestResponse {
-    Error { error: String },
-    Success { request_id: String },
-}
-#[derive(Debug, Clone, serde::Deserialize)]
-#[serde(untagged)]
-enum TranscriptionResponse {
-    Error {
-        error: String,
-    },
-    #[serde(deserialize_with = "deserialize_pending")]
-    Pending {
-        status: PendingStatus,
-    },
-    Success {
-        result: TranscriptionResult,
-    },
-}
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct TranscriptionResult {
     segments: Vec<TranscriptionSegment>,
@@ -449,33 +426,6 @@ pub struct TranscriptionSegment {
     start: f64,
     text: String,
 }
-#[derive(Debug, Clone)]
-enum PendingStatus {
-    Pending { position: u32 },
-    InProgress,
-}
-fn deserialize_pending<'de, D>(deserializer: D) -> Result<PendingStatus, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let value = serde_json::Value::deserialize(deserializer)?;
-    if let serde_json::Value::Object(map) = value {
-        if let Some(serde_json::Value::String(status)) = map.get("status") {
-            if status == "pending" {
-                if let Some(serde_json::Value::Number(position)) = map.get("position") {
-                    if let Some(position) = position.as_u64() {
-                        return Ok(PendingStatus::Pending {
-                            position: position as u32,
-                        });
-                    }
-                }
-            } else if status == "in-progress" {
-                return Ok(PendingStatus::InProgress);
-            }
-        }
-    }
-    Err(serde::de::Error::custom("Invalid pending status"))
-}
 
 static MALE_NAMES: &[&str] = &[
     "Tom",
@@ -0,0 +1,139 @@
+mod openai;
+mod service;
+#[cfg(feature = "whisper")]
+mod whisper;
+use crate::TranscriptionResult;
+use common::{anyhow::Result, log, serenity::async_trait, SpeechToTextConfig};
+use std::sync::OnceLock;
+// what discord hands us, interleaved stereo
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: u16 = 2;
+/// something that can turn someone talking into text
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    fn name(&self) -> &'static str;
+    // audio is 48khz interleaved stereo, straight from discord
+    async fn transcribe(&self, audio: &[i16]) -> Result<TranscriptionResult>;
+}
+// stands in for a configured backend this build can't run, refusing everything
+#[cfg(not(feature = "whisper"))]
+struct Unavailable(&'static str);
+#[cfg(not(feature = "whisper"))]
+#[async_trait]
+impl SpeechToText for Unavailable {
+    fn name(&self) -> &'static str {
+        "nothing"
+    }
+    async fn transcribe(&self, _audio: &[i16]) -> Result<TranscriptionResult> {
+        Err(common::anyhow::anyhow!(self.0))
+    }
+}
+static BACKEND: OnceLock<Box<dyn SpeechToText>> = OnceLock::new();
+/// the backend the config picks, set up the first time something is transcribed
+pub fn backend() -> &'static dyn SpeechToText {
+    BACKEND
+        .get_or_init(|| {
+            let backend: Box<dyn SpeechToText> = match common::get_config().speech_to_text {
+                SpeechToTextConfig::Service => Box::new(service::Service),
+                SpeechToTextConfig::OpenAi {
+                    url,
+                    api_key,
+                    model,
+                } => Box::new(openai::OpenAi::new(url, api_key, model)),
+                #[cfg(feature = "whisper")]
+                SpeechToTextConfig::Whisper { model } => Box::new(whisper::Whisper::new(model)),
+                // quietly sending audio somewhere else instead isn't what anyone configured
+                #[cfg(not(feature = "whisper"))]
+                SpeechToTextConfig::Whisper { .. } => {
+                    log::error!("Whisper needs the whisper feature, nothing will be transcribed");
+                    Box::new(Unavailable("Whisper needs the whisper feature"))
+                }
+            };
+            log::info!("Transcribing with {}", backend.name());
+            backend
+        })
+        .as_ref()
+}
+// a 16 bit pcm wav, for backends that want a file rather than raw samples
+fn wav(audio: &[i16]) -> Vec<u8> {
+    let data_len = (audio.len() * 2) as u32;
+    let block_align = CHANNELS * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&CHANNELS.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend(audio.iter().flat_map(|s| s.to_le_bytes()));
+    wav
+}
+// whisper wants 16khz mono floats, 48khz divides evenly so averaging each 3 frames is enough
+#[cfg(feature = "whisper")]
+fn whisper_samples(audio: &[i16]) -> Vec<f32> {
+    const WHISPER_RATE: u32 = 16000;
+    let chunk = CHANNELS as usize * (SAMPLE_RATE / WHISPER_RATE) as usize;
+    audio
+        .chunks(chunk)
+        .map(|c| c.iter().map(|&s| s as f32).sum::<f32>() / (c.len() as f32 * 32768.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    }
+
+    #[test]
+    fn wav_header() {
+        let audio = [0, 1, -1, i16::MAX, i16::MIN, 256];
+        let wav = wav(&audio);
+        assert_eq!(wav.len(), 44 + audio.len() * 2);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(&wav, 4) as usize, wav.len() - 8);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(&wav, 16), 16);
+        // pcm
+        assert_eq!(u16_at(&wav, 20), 1);
+        assert_eq!(u16_at(&wav, 22), 2);
+        assert_eq!(u32_at(&wav, 24), 48000);
+        assert_eq!(u32_at(&wav, 28), 48000 * 4);
+        assert_eq!(u16_at(&wav, 32), 4);
+        assert_eq!(u16_at(&wav, 34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(&wav, 40) as usize, audio.len() * 2);
+        assert_eq!(u16_at(&wav, 44 + 3 * 2) as i16, i16::MAX);
+        assert_eq!(u16_at(&wav, 44 + 4 * 2) as i16, i16::MIN);
+    }
+
+    #[test]
+    fn empty_wav() {
+        let wav = wav(&[]);
+        assert_eq!(wav.len(), 44);
+        assert_eq!(u32_at(&wav, 4), 36);
+        assert_eq!(u32_at(&wav, 40), 0);
+    }
+
+    #[cfg(feature = "whisper")]
+    #[test]
+    fn whisper_downmix() {
+        // three stereo frames at 48khz make one mono sample at 16khz
+        assert_eq!(whisper_samples(&[16384; 12]), vec![0.5, 0.5]);
+        assert_eq!(whisper_samples(&[1000, -1000, 1000, -1000, 1000, -1000]), vec![0.0]);
+        let left_only = whisper_samples(&[i16::MIN, 0, i16::MIN, 0, i16::MIN, 0]);
+        assert_eq!(left_only, vec![-0.5]);
+    }
+}
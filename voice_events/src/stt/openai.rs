@@ -0,0 +1,82 @@
+use super::SpeechToText;
+use crate::{TranscriptionResult, TranscriptionSegment};
+use common::{
+    anyhow::{self, Result},
+    reqwest::multipart::{Form, Part},
+    serenity::async_trait,
+    WEB_CLIENT,
+};
+const DEFAULT_MODEL: &str = "whisper-1";
+/// anything serving openai's `/v1/audio/transcriptions`, openai itself or a local whisper server
+pub struct OpenAi {
+    url: String,
+    api_key: Option<String>,
+    model: String,
+}
+impl OpenAi {
+    pub fn new(url: String, api_key: Option<String>, model: Option<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_owned(),
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_owned()),
+        }
+    }
+}
+#[async_trait]
+impl SpeechToText for OpenAi {
+    fn name(&self) -> &'static str {
+        "an openai compatible endpoint"
+    }
+    async fn transcribe(&self, audio: &[i16]) -> Result<TranscriptionResult> {
+        let form = Form::new()
+            .part(
+                "file",
+                Part::bytes(super::wav(audio))
+                    .file_name("audio.wav")
+                    .mime_str("audio/wav")?,
+            )
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json");
+        let mut request = WEB_CLIENT
+            .post(format!("{}/v1/audio/transcriptions", self.url))
+            .multipart(form);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to get transcription: {} {}",
+                status,
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        let response = response.json::<VerboseTranscription>().await?;
+        // servers that ignore verbose_json only send the text back
+        let segments = match response.segments {
+            Some(segments) if !segments.is_empty() => segments
+                .into_iter()
+                .map(|s| TranscriptionSegment {
+                    start: s.start,
+                    text: s.text,
+                })
+                .collect(),
+            _ => vec![TranscriptionSegment {
+                start: 0.0,
+                text: response.text,
+            }],
+        };
+        Ok(TranscriptionResult { segments })
+    }
+}
+#[derive(Debug, Clone, serde::Deserialize)]
+struct VerboseTranscription {
+    text: String,
+    segments: Option<Vec<VerboseSegment>>,
+}
+#[derive(Debug, Clone, serde::Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    text: String,
+}
@@ -0,0 +1,108 @@
+use super::SpeechToText;
+use crate::TranscriptionResult;
+use common::{
+    anyhow::{self, Result},
+    get_config,
+    serenity::async_trait,
+    WEB_CLIENT,
+};
+use serde::Deserialize as _;
+/// the transcription service at `transcribe_url`, which queues requests and is polled for them
+pub struct Service;
+#[async_trait]
+impl SpeechToText for Service {
+    fn name(&self) -> &'static str {
+        "the transcription service"
+    }
+    async fn transcribe(&self, audio: &[i16]) -> Result<TranscriptionResult> {
+        let cfg = get_config();
+        let response = WEB_CLIENT
+            .post(format!(
+                "{}/transcribe/raw?format=s16le&sample_rate=48000&channels=2",
+                cfg.transcribe_url
+            ))
+            .header("x-token", &cfg.transcribe_token)
+            .header("Content-Type", "multipart/form-data")
+            .body(
+                audio
+                    .iter()
+                    .flat_map(|i| i.to_le_bytes().to_vec())
+                    .collect::<Vec<u8>>(),
+            )
+            .send()
+            .await?
+            .json::<RequestResponse>()
+            .await?;
+        let request_id = match response {
+            RequestResponse::Success { request_id } => request_id,
+            RequestResponse::Error { error } => {
+                return Err(anyhow::anyhow!("Failed to start transcription: {}", error))
+            }
+        };
+        let url = format!("{}/result/{}/wait", cfg.transcribe_url, request_id);
+        let response = WEB_CLIENT
+            .get(url)
+            .header("x-token", cfg.transcribe_token)
+            .send()
+            .await?
+            .json::<TranscriptionResponse>()
+            .await?;
+        match response {
+            TranscriptionResponse::Pending { status } => Err(anyhow::anyhow!(
+                "Transcription is pending, this should not happen: {:?}",
+                status
+            )),
+            TranscriptionResponse::Error { error } => {
+                Err(anyhow::anyhow!("Failed to get transcription: {}", error))
+            }
+            TranscriptionResponse::Success { result } => Ok(result),
+        }
+    }
+}
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum RequestResponse {
+    Error { error: String },
+    Success { request_id: String },
+}
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+enum TranscriptionResponse {
+    Error {
+        error: String,
+    },
+    #[serde(deserialize_with = "deserialize_pending")]
+    Pending {
+        status: PendingStatus,
+    },
+    Success {
+        result: TranscriptionResult,
+    },
+}
+#[derive(Debug, Clone)]
+enum PendingStatus {
+    Pending { position: u32 },
+    InProgress,
+}
+fn deserialize_pending<'de, D>(deserializer: D) -> Result<PendingStatus, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if let serde_json::Value::Object(map) = value {
+        if let Some(serde_json::Value::String(status)) = map.get("status") {
+            if status == "pending" {
+                if let Some(serde_json::Value::Number(position)) = map.get("position") {
+                    if let Some(position) = position.as_u64() {
+                        return Ok(PendingStatus::Pending {
+                            position: position as u32,
+                        });
+                    }
+                }
+            } else if status == "in-progress" {
+                return Ok(PendingStatus::InProgress);
+            }
+        }
+    }
+    Err(serde::de::Error::custom("Invalid pending status"))
+}
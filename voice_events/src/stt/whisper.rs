@@ -0,0 +1,72 @@
+use super::SpeechToText;
+use crate::{TranscriptionResult, TranscriptionSegment};
+use common::{
+    anyhow::{self, Result},
+    log,
+    serenity::async_trait,
+    tokio::{self, sync::OnceCell},
+};
+use std::{path::PathBuf, sync::Arc};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+/// whisper.cpp running in the bot itself, nothing leaves the machine
+pub struct Whisper {
+    model: PathBuf,
+    // models are hundreds of megabytes, so it's only loaded once someone actually talks
+    context: OnceCell<Arc<WhisperContext>>,
+}
+impl Whisper {
+    pub fn new(model: PathBuf) -> Self {
+        Self {
+            model,
+            context: OnceCell::new(),
+        }
+    }
+    async fn context(&self) -> Result<Arc<WhisperContext>> {
+        self.context
+            .get_or_try_init(|| async {
+                let model = self.model.clone();
+                log::info!("Loading whisper model from {}", model.display());
+                tokio::task::spawn_blocking(move || {
+                    let path = model
+                        .to_str()
+                        .ok_or_else(|| anyhow::anyhow!("Whisper model path isn't utf-8"))?;
+                    let context =
+                        WhisperContext::new_with_params(path, WhisperContextParameters::default())?;
+                    Ok::<_, anyhow::Error>(Arc::new(context))
+                })
+                .await?
+            })
+            .await
+            .cloned()
+    }
+}
+#[async_trait]
+impl SpeechToText for Whisper {
+    fn name(&self) -> &'static str {
+        "whisper"
+    }
+    async fn transcribe(&self, audio: &[i16]) -> Result<TranscriptionResult> {
+        let context = self.context().await?;
+        let samples = super::whisper_samples(audio);
+        tokio::task::spawn_blocking(move || {
+            let mut state = context.create_state()?;
+            let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+            params.set_print_progress(false);
+            params.set_print_realtime(false);
+            params.set_print_special(false);
+            params.set_print_timestamps(false);
+            state.full(params, &samples)?;
+            let segments = (0..state.full_n_segments()?)
+                .map(|i| {
+                    Ok(TranscriptionSegment {
+                        // whisper counts in hundredths of a second
+                        start: state.full_get_segment_t0(i)? as f64 / 100.0,
+                        text: state.full_get_segment_text(i)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok::<_, anyhow::Error>(TranscriptionResult { segments })
+        })
+        .await?
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     commands::CommandState,
-    transcribe,
+    stt,
 };
 
 use super::{
@@ -11,7 +11,7 @@ use common::{
     anyhow::Result,
     log,
     serenity::{
-        all::{Http, UserId},
+        all::{GuildId, Http, UserId},
         futures::{stream::FuturesUnordered, StreamExt as _},
     },
     tokio::{
@@ -31,6 +31,7 @@ pub struct TranscriptionThread {
 impl TranscriptionThread {
     pub fn new(
         user_id: UserId,
+        guild_id: GuildId,
         responses: mpsc::UnboundedSender<ThreadResponse>,
         http: Arc<Http>,
     ) -> Self {
@@ -40,7 +41,7 @@ impl TranscriptionThread {
             rx,
             responses,
             http,
-            Arc::new(Mutex::new(CommandState::default())),
+            Arc::new(Mutex::new(CommandState::new(guild_id))),
         ));
         Self {
             handle,
@@ -146,12 +147,16 @@ impl TranscriptionThread {
         state: Arc<Mutex<CommandState>>,
     ) -> Result<ThreadResponse> {
         // pcm_s16le_to_mp3(&buf).await
-        let resp = transcribe(audio).await?;
+        let resp = stt::backend().transcribe(audio).await?;
         let content = resp.to_string();
         let mut state = state.lock().await;
 
         let response = state.parse_commands(&content, user_id, http).await;
 
-        Ok(ThreadResponse { response, user_id })
+        Ok(ThreadResponse {
+            response,
+            user_id,
+            transcript: content,
+        })
     }
 }